@@ -1,5 +1,10 @@
 use clap::{Parser, Subcommand};
 
+use crate::{
+    export::ExportFormat, import::ImportSource, merge::Strategy,
+    output::OutputFormat,
+};
+
 #[derive(Parser, Debug)]
 #[command(name = "blazinit")]
 #[command(version)]
@@ -10,6 +15,32 @@ use clap::{Parser, Subcommand};
 pub struct Cli {
     #[command(subcommand)]
     pub command: Commands,
+
+    #[arg(
+        long,
+        global = true,
+        help = "Print structured JSON on stdout (same as --format json)"
+    )]
+    pub json: bool,
+
+    #[arg(
+        long,
+        global = true,
+        value_enum,
+        default_value_t,
+        help = "Output format for command results"
+    )]
+    pub format: OutputFormat,
+}
+
+impl Cli {
+    pub fn output_format(&self) -> OutputFormat {
+        if self.json {
+            OutputFormat::Json
+        } else {
+            self.format
+        }
+    }
 }
 
 #[derive(Subcommand, Debug)]
@@ -31,6 +62,30 @@ pub enum Commands {
         profile: String,
     },
 
+    #[command(about = "Copy a profile under a new name")]
+    Copy {
+        #[arg(help = "Name of the profile to copy")]
+        source: String,
+        #[arg(help = "Name of the new profile")]
+        destination: String,
+    },
+
+    #[command(about = "Rename a profile, keeping it the default if it was")]
+    Rename {
+        #[arg(help = "Current name of the profile")]
+        old: String,
+        #[arg(help = "New name of the profile")]
+        new: String,
+    },
+
+    #[command(about = "Open a profile in $EDITOR and save it if it is valid")]
+    Edit {
+        #[arg(
+            help = "Profile name to edit. Defaults to current default profile if not specified"
+        )]
+        profile: Option<String>,
+    },
+
     #[command(about = "List all packages in a profile")]
     Show {
         #[arg(
@@ -44,30 +99,75 @@ pub enum Commands {
 
     #[command(about = "Add a package dependency to a profile")]
     Add {
-        #[arg(help = "Package identifier to add")]
-        package: String,
+        #[arg(
+            value_name = "PACKAGES",
+            required_unless_present = "interactive",
+            help = "Package identifiers to add, separated by commas"
+        )]
+        packages: Option<String>,
         #[arg(
             help = "Profile name to add package to. Defaults to current default profile if not specified"
         )]
         profile: Option<String>,
         #[arg(
             long,
-            help = "Pin a specific installer for this package (apt, brew, pacman, ...)"
+            help = "Pin a specific installer for these packages (apt, brew, pacman, ...)"
         )]
         installer: Option<String>,
+        #[arg(
+            long,
+            help = "Version requirement for this package (1.5.7, 1.5.x, >=1.2, ...)"
+        )]
+        version: Option<String>,
+        #[arg(
+            long,
+            help = "Skip packages already in the profile instead of failing"
+        )]
+        ignore_existing: bool,
+        #[arg(
+            long,
+            help = "Also add the packages' registry dependencies to the profile"
+        )]
+        with_deps: bool,
+        #[arg(
+            long = "tag",
+            value_name = "TAG",
+            value_delimiter = ',',
+            help = "Tag the added packages, for `install --tag` (repeatable)"
+        )]
+        tags: Vec<String>,
+        #[arg(
+            short,
+            long,
+            value_name = "PROFILE",
+            num_args = 0..=1,
+            conflicts_with_all = ["packages", "version"],
+            help = "Pick the packages of PROFILE (default profile if omitted) from a searchable checklist of the registry"
+        )]
+        interactive: Option<Option<String>>,
     },
 
     #[command(about = "Remove a package dependency from a profile")]
     Remove {
-        #[arg(help = "Package identifier to remove")]
-        package: String,
+        #[arg(
+            value_name = "PACKAGES",
+            help = "Package identifiers to remove, separated by commas"
+        )]
+        packages: String,
         #[arg(
             help = "Profile name to remove package from. Defaults to current default profile if not specified"
         )]
         profile: Option<String>,
+        #[arg(
+            long,
+            help = "Also remove profile packages that depend on the removed ones"
+        )]
+        cascade: bool,
     },
 
-    #[command(about = "Export a profile to a TOML file")]
+    #[command(
+        about = "Export a profile to a TOML file, or as a Dockerfile, install script or native package list (--to)"
+    )]
     Export {
         #[arg(
             help = "Profile name to export. Defaults to current default profile if not specified"
@@ -77,20 +177,150 @@ pub enum Commands {
             help = "Optional file path to export to. Prints to stdout if omitted"
         )]
         file: Option<String>,
+        #[arg(
+            long,
+            value_enum,
+            default_value_t = ExportFormat::Toml,
+            help = "What to export the profile as"
+        )]
+        to: ExportFormat,
+        #[arg(
+            long,
+            help = "Installer of the target system (apt, brew, winget, ...). Defaults to the one matching --to"
+        )]
+        installer: Option<String>,
+        #[arg(
+            long,
+            help = "Base image for --to dockerfile. Defaults to one matching the installer"
+        )]
+        image: Option<String>,
+        #[arg(
+            long,
+            help = "Embed the custom registry packages the profile needs so it can be imported elsewhere"
+        )]
+        bundle: bool,
     },
 
-    #[command(about = "Import a profile from a TOML file")]
+    #[command(
+        about = "Import a profile from a TOML file, or from the packages a package manager has installed"
+    )]
     Import {
-        #[arg(help = "File path to import the profile from")]
-        file: String,
+        #[arg(
+            required_unless_present = "from",
+            help = "File path, http(s) URL or git+URL#path=FILE to import the profile from. With --from, a package list, Brewfile or winget export to read instead of querying the system"
+        )]
+        file: Option<String>,
+        #[arg(
+            long,
+            value_enum,
+            help = "Build the profile from this package manager's installed packages"
+        )]
+        from: Option<ImportSource>,
+        #[arg(
+            long,
+            requires = "from",
+            help = "Name of the profile to create with --from [default: imported]"
+        )]
+        name: Option<String>,
+        #[arg(
+            long,
+            value_name = "HEX",
+            conflicts_with = "from",
+            help = "Refuse the file unless its SHA-256 matches"
+        )]
+        sha256: Option<String>,
+        #[arg(
+            long = "as",
+            value_name = "NAME",
+            conflicts_with = "from",
+            help = "Import under this name instead of the one in the file"
+        )]
+        rename: Option<String>,
+        #[arg(
+            long,
+            conflicts_with = "from",
+            help = "Overwrite an existing profile of the same name"
+        )]
+        force: bool,
+        #[arg(
+            long,
+            conflicts_with = "from",
+            help = "Accept custom install commands in a bundle without asking"
+        )]
+        trust: bool,
+    },
+
+    #[command(
+        about = "Show how a profile differs from another profile or file"
+    )]
+    Diff {
+        #[arg(help = "Profile to compare from")]
+        profile: String,
+        #[arg(
+            help = "Profile, file path, http(s) URL or git+URL#path=FILE to compare to"
+        )]
+        other: String,
+    },
+
+    #[command(about = "Merge the packages of a profile or file into a profile")]
+    Merge {
+        #[arg(
+            help = "Profile, file path, http(s) URL or git+URL#path=FILE to take packages from"
+        )]
+        source: String,
+        #[arg(help = "Profile to merge into")]
+        destination: String,
+        #[arg(
+            long,
+            value_enum,
+            default_value = "union",
+            help = "How to resolve packages pinned differently on each side"
+        )]
+        strategy: Strategy,
+    },
+
+    #[command(
+        about = "Import a remote profile and keep it in sync with its source"
+    )]
+    Subscribe {
+        #[arg(help = "File path, http(s) URL or git+URL#path=FILE to follow")]
+        source: String,
+        #[arg(
+            long,
+            help = "Name of the local profile. Defaults to the name in the file"
+        )]
+        name: Option<String>,
+        #[arg(
+            long,
+            help = "Accept custom install commands in a bundle without asking"
+        )]
+        trust: bool,
+    },
+
+    #[command(about = "Update subscribed profiles from their sources")]
+    Sync {
+        #[arg(
+            help = "Subscribed profile to sync. Syncs all of them if not specified"
+        )]
+        profile: Option<String>,
+        #[arg(long, help = "Show the changes without applying them")]
+        dry_run: bool,
+        #[arg(short, long, help = "Apply changes without asking")]
+        yes: bool,
+        #[arg(
+            long,
+            help = "Accept custom install commands in a bundle without asking"
+        )]
+        trust: bool,
     },
 
     #[command(about = "Install all packages defined in a profile")]
     Install {
         #[arg(
-            help = "Profile name to install. Defaults to current default profile if not specified"
+            value_name = "PROFILES",
+            help = "Profiles to install together. Defaults to current default profile if not specified"
         )]
-        profile: Option<String>,
+        profiles: Vec<String>,
         #[arg(
             long,
             help = "Force reinstall even if already detected as installed"
@@ -106,6 +336,75 @@ pub enum Commands {
             help = "Print what would be run without executing anything"
         )]
         dry_run: bool,
+        #[arg(
+            long,
+            conflicts_with = "installer",
+            help = "Install exactly what the profile's lock file records"
+        )]
+        locked: bool,
+        #[arg(
+            long,
+            conflicts_with = "only",
+            help = "Choose which of the profile's packages to install from a checklist"
+        )]
+        select: bool,
+        #[arg(
+            long,
+            value_name = "PACKAGES",
+            value_delimiter = ',',
+            help = "Install only these packages of the profile (and their dependencies)"
+        )]
+        only: Option<Vec<String>>,
+        #[arg(
+            long,
+            value_name = "PACKAGES",
+            value_delimiter = ',',
+            help = "Leave these packages of the profile out"
+        )]
+        except: Vec<String>,
+        #[arg(
+            long = "tag",
+            value_name = "TAG",
+            value_delimiter = ',',
+            help = "Install only packages carrying this tag (repeatable)"
+        )]
+        tags: Vec<String>,
+        #[arg(
+            long = "host",
+            value_name = "USER@HOST",
+            help = "Install on a remote host over ssh instead (repeatable)"
+        )]
+        hosts: Vec<String>,
+    },
+
+    #[command(
+        about = "Show which packages of a profile are installed, missing or outdated"
+    )]
+    Status {
+        #[arg(
+            help = "Profile name to check. Defaults to current default profile if not specified"
+        )]
+        profile: Option<String>,
+    },
+
+    #[command(
+        about = "Install a profile in a fresh container and check the result"
+    )]
+    Test {
+        #[arg(
+            help = "Profile name to test. Defaults to current default profile if not specified"
+        )]
+        profile: Option<String>,
+        #[arg(long, help = "Container image to test in (debian:12, ...)")]
+        image: String,
+    },
+
+    #[command(about = "Record resolved installers and versions in a lock file")]
+    Lock {
+        #[arg(
+            help = "Profile name to lock. Defaults to current default profile if not specified"
+        )]
+        profile: Option<String>,
     },
 
     #[command(about = "Set the default profile")]
@@ -120,6 +419,9 @@ pub enum Commands {
         command: RegistryCommands,
     },
 
+    #[command(about = "Diagnose the environment blazinit runs in")]
+    Doctor,
+
     #[command(about = "Update blazinit to the latest release")]
     SelfUpdate {
         #[arg(long, help = "Check for a newer version without downloading it")]
@@ -134,4 +436,39 @@ pub enum RegistryCommands {
         #[arg(help = "Optional search query to filter packages")]
         query: Option<String>,
     },
+
+    #[command(
+        about = "Create a registry package, probing local package managers for its details"
+    )]
+    New {
+        #[arg(help = "Name of the new package")]
+        name: String,
+        #[arg(long, help = "Human-readable name shown in listings")]
+        display: Option<String>,
+        #[arg(
+            long,
+            help = "Command that succeeds when the package is installed"
+        )]
+        detect: Option<String>,
+        #[arg(
+            long = "installer",
+            value_name = "INSTALLER=PACKAGE",
+            help = "Package name for an installer, e.g. apt=fd-find (repeatable)"
+        )]
+        installers: Vec<String>,
+        #[arg(long, help = "Custom install command")]
+        custom: Option<String>,
+        #[arg(
+            long = "depends-on",
+            value_name = "PACKAGE",
+            help = "Registry package this one depends on (repeatable)"
+        )]
+        dependencies: Vec<String>,
+        #[arg(long, help = "Do not query local package managers")]
+        no_probe: bool,
+        #[arg(short, long, help = "Accept the suggestions without prompting")]
+        yes: bool,
+        #[arg(long, help = "Replace an existing registry package")]
+        force: bool,
+    },
 }
@@ -6,6 +6,8 @@ use include_dir::{Dir, include_dir};
 use serde::{Deserialize, Serialize};
 
 use crate::{
+    error::{Error, Result},
+    output::say,
     profile::{PROFILE_DIRNAME, ensure_default_profile},
     registry::ensure_registry,
 };
@@ -24,6 +26,8 @@ struct Config {
     preferred_installer: Option<String>,
     #[serde(default = "default_registry_url")]
     registry_url: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    bin_dir: Option<String>,
 }
 
 fn default_registry_url() -> String {
@@ -36,6 +40,7 @@ impl Default for Config {
             default_profile: DEFAULT_PROFILE_NAME.to_string(),
             preferred_installer: None,
             registry_url: DEFAULT_REGISTRY_URL.to_string(),
+            bin_dir: None,
         }
     }
 }
@@ -48,6 +53,19 @@ pub fn get_registry_url() -> String {
     read_config().registry_url
 }
 
+pub fn get_bin_dir() -> Result<PathBuf> {
+    if let Some(dir) = read_config().bin_dir {
+        return Ok(PathBuf::from(dir));
+    }
+    dirs_next::executable_dir()
+        .or_else(|| dirs_next::home_dir().map(|h| h.join(".local/bin")))
+        .ok_or_else(|| {
+            Error::Other(
+                "Cannot find a directory for installed binaries; set bin_dir in the config".to_string(),
+            )
+        })
+}
+
 fn config_file_path() -> PathBuf {
     config_dir().join(CONFIG_FILENAME)
 }
@@ -67,24 +85,26 @@ pub fn get_default_profile() -> String {
     read_config().default_profile
 }
 
-pub fn set_default_profile(profile_name: &str) -> Result<(), String> {
+pub fn set_default_profile(profile_name: &str) -> Result<()> {
     let profile_path = crate::profile::profile_path(profile_name);
     if !profile_path.exists() {
         log::error!(
             "set_default_profile: profile '{}' does not exist",
             profile_name
         );
-        return Err(format!("Profile '{}' does not exist.", profile_name));
+        return Err(crate::profile::profile_not_found(profile_name));
     }
 
     let mut config = read_config();
     config.default_profile = profile_name.to_string();
 
-    let toml_str = toml::to_string(&config).map_err(|e| e.to_string())?;
-    fs::write(config_file_path(), toml_str).map_err(|e| e.to_string())?;
+    let toml_str =
+        toml::to_string(&config).map_err(Error::serialize("config"))?;
+    fs::write(config_file_path(), toml_str)
+        .map_err(Error::io("Failed to write config file"))?;
 
     log::info!("default profile set to '{}'", profile_name);
-    println!(
+    say!(
         "{} '{}'.",
         "Default profile set to".green(),
         profile_name.cyan()
@@ -105,11 +125,11 @@ pub fn profiles_dir() -> PathBuf {
     dir
 }
 
-pub fn bootstrap_config() -> Result<(), String> {
+pub fn bootstrap_config() -> Result<()> {
     let base = config_dir();
     log::debug!("bootstrap: config dir = {:?}", base);
     fs::create_dir_all(&base)
-        .map_err(|e| format!("Failed to create config dir: {}", e))?;
+        .map_err(Error::io("Failed to create config dir"))?;
 
     let default = get_default_profile();
     log::debug!("bootstrap: ensuring default profile '{}'", default);
@@ -149,7 +169,7 @@ mod tests {
     fn create_dummy_profile(
         _temp_dir: &TempDir,
         profile_name: &str,
-    ) -> Result<(), String> {
+    ) -> std::io::Result<()> {
         // Use the actual config functions to get the correct path
         let profile_path = crate::profile::profile_path(profile_name);
 
@@ -158,8 +178,7 @@ mod tests {
 
         // Use an empty packages list in the dummy profile
         let content = format!("name = \"{}\"\npackages = []", profile_name);
-        fs::write(&profile_path, content).map_err(|e| e.to_string())?;
-        Ok(())
+        fs::write(&profile_path, content)
     }
 
     #[test]
@@ -193,11 +212,10 @@ mod tests {
         let _temp = setup_test_env();
         let profile_name = "non_existent";
         let result = set_default_profile(profile_name);
-        assert!(result.is_err());
-        assert_eq!(
-            result.err().unwrap(),
-            format!("Profile '{}' does not exist.", profile_name)
-        );
+        assert!(matches!(
+            result,
+            Err(Error::ProfileNotFound { ref name, .. }) if name == profile_name
+        ));
         assert_eq!(get_default_profile(), "default"); // Should remain default
     }
 
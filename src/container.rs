@@ -0,0 +1,349 @@
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+
+use colored::Colorize;
+use serde::Serialize;
+
+use crate::{
+    error::{Error, Result},
+    installer::{InstallOutcome, InstallReport},
+    output::{self, say},
+    profile, registry,
+    runner::{CommandRunner, SystemRunner},
+    status::{PackageState, StatusReport},
+};
+
+// Overrides the container runtime binary (docker, podman or a wrapper).
+pub const RUNTIME_ENV: &str = "BLAZINIT_CONTAINER_RUNTIME";
+
+const RUNTIMES: &[&str] = &["docker", "podman"];
+
+// Mount point of the staging directory inside the container.
+const MOUNT: &str = "/blazinit";
+
+// Runs inside the container with the staging directory as $R. Fresh images
+// have no sudo and, for Debian-based ones, no package lists, so a
+// pass-through sudo is put last on PATH and the lists are fetched first.
+// BLAZINIT_ROOT is only set when a runtime shim runs the script outside a
+// container, where the system's package lists must be left alone.
+const SCRIPT: &str = r#"R=${BLAZINIT_ROOT:-/blazinit}
+export XDG_CONFIG_HOME="$R/config" PATH="$R/bin:$PATH:$R/fallback"
+if [ -z "$BLAZINIT_ROOT" ] && command -v apt-get >/dev/null &&
+    ! ls /var/lib/apt/lists/*_Packages >/dev/null 2>&1; then
+    apt-get update -qq >&2 || true
+fi
+blazinit --json install "$1" > "$R/out/install.json"
+blazinit --json status "$1" > "$R/out/status.json"
+exit 0
+"#;
+
+const SUDO_SHIM: &str = "#!/bin/sh\nexec \"$@\"\n";
+
+#[derive(Serialize, Debug, Clone)]
+pub struct ContainerTestReport {
+    pub profile: String,
+    pub image: String,
+    pub runtime: String,
+    pub install: InstallReport,
+    pub status: StatusReport,
+    pub failed_install: Vec<String>,
+    pub failed_detect: Vec<String>,
+}
+
+impl ContainerTestReport {
+    pub fn passed(&self) -> bool {
+        self.failed_install.is_empty() && self.failed_detect.is_empty()
+    }
+}
+
+fn select_runtime() -> Result<String> {
+    if let Ok(runtime) = env::var(RUNTIME_ENV) {
+        return Ok(runtime);
+    }
+    RUNTIMES
+        .iter()
+        .find(|r| SystemRunner.is_available(r))
+        .map(|r| r.to_string())
+        .ok_or_else(|| {
+            Error::Unsupported(
+                "No container runtime found. Install docker or podman."
+                    .to_string(),
+            )
+        })
+}
+
+fn copy_dir(from: &Path, to: &Path) -> Result<()> {
+    fs::create_dir_all(to).map_err(Error::io("Failed to create directory"))?;
+    let entries = fs::read_dir(from)
+        .map_err(Error::io(format!("Failed to read {:?}", from)))?;
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            copy_dir(&path, &to.join(entry.file_name()))?;
+        } else if path.is_file() {
+            fs::copy(&path, to.join(entry.file_name()))
+                .map_err(Error::io(format!("Failed to copy {:?}", path)))?;
+        }
+    }
+    Ok(())
+}
+
+fn write_executable(path: &Path, content: &[u8]) -> Result<()> {
+    fs::write(path, content)
+        .map_err(Error::io(format!("Failed to write {:?}", path)))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(path, fs::Permissions::from_mode(0o755))
+            .map_err(Error::io(format!("Failed to chmod {:?}", path)))?;
+    }
+    Ok(())
+}
+
+// Lays out everything the container needs in one directory: this binary,
+// a config pointing at the profile and a frozen copy of the registry. The
+// registry URL is unreachable on purpose so the local registry is what gets
+// tested rather than whatever is published.
+fn stage(profile_name: &str) -> Result<PathBuf> {
+    let root =
+        env::temp_dir().join(format!("blazinit-test-{}", std::process::id()));
+    let config = root.join("config/blazinit");
+    for dir in ["bin", "fallback", "out", "config/blazinit/profiles"] {
+        fs::create_dir_all(root.join(dir))
+            .map_err(Error::io("Failed to create staging directory"))?;
+    }
+
+    let exe = env::current_exe()
+        .map_err(Error::io("Failed to locate the blazinit binary"))?;
+    let binary = fs::read(&exe)
+        .map_err(Error::io(format!("Failed to read {:?}", exe)))?;
+    write_executable(&root.join("bin/blazinit"), &binary)?;
+    write_executable(&root.join("fallback/sudo"), SUDO_SHIM.as_bytes())?;
+
+    fs::copy(
+        profile::profile_path(profile_name),
+        config
+            .join("profiles")
+            .join(format!("{}.toml", profile_name)),
+    )
+    .map_err(Error::io("Failed to copy profile"))?;
+    copy_dir(&registry::registry_dir(), &config.join("registry"))?;
+    fs::write(
+        config.join("config.toml"),
+        format!(
+            "default_profile = {:?}\nregistry_url = \"http://127.0.0.1:9\"\n",
+            profile_name
+        ),
+    )
+    .map_err(Error::io("Failed to write staging config"))?;
+
+    log::debug!("staged profile '{}' in {:?}", profile_name, root);
+    Ok(root)
+}
+
+fn read_json<T: serde::de::DeserializeOwned>(path: &Path) -> Option<T> {
+    let content = fs::read_to_string(path).ok()?;
+    serde_json::from_str(&content)
+        .inspect_err(|e| log::warn!("invalid report {:?}: {}", path, e))
+        .ok()
+}
+
+fn run_container(
+    runtime: &str,
+    image: &str,
+    profile_name: &str,
+    root: &Path,
+) -> Result<ContainerTestReport> {
+    let mut command = Command::new(runtime);
+    command
+        .args(["run", "--rm", "-v"])
+        .arg(format!("{}:{}", root.display(), MOUNT))
+        .args([image, "sh", "-c", SCRIPT, "sh", profile_name]);
+    if output::is_structured() {
+        command.stdout(Stdio::null());
+    }
+    log::info!("running {} with image '{}'", runtime, image);
+    let status = command
+        .status()
+        .map_err(Error::io(format!("Failed to run '{}'", runtime)))?;
+
+    let container_error = |message: String| Error::Container {
+        image: image.to_string(),
+        message,
+    };
+    if !status.success() {
+        return Err(container_error(format!(
+            "{} exited with {}",
+            runtime, status
+        )));
+    }
+    let install: InstallReport = read_json(&root.join("out/install.json"))
+        .ok_or_else(|| {
+            container_error("the install produced no report".to_string())
+        })?;
+    // Without a status report nothing was verified, which must not pass.
+    let status: StatusReport = read_json(&root.join("out/status.json"))
+        .ok_or_else(|| {
+            container_error("the status check produced no report".to_string())
+        })?;
+
+    let failed_install: Vec<String> = install
+        .packages
+        .iter()
+        .filter(|p| p.outcome == InstallOutcome::Failed)
+        .map(|p| p.name.clone())
+        .collect();
+    // Packages that failed to install are bound to fail detection too; only
+    // the ones that installed but still cannot be detected are interesting.
+    let failed_detect = status
+        .packages
+        .iter()
+        .filter(|p| p.state != PackageState::Installed)
+        .filter(|p| !failed_install.contains(&p.name))
+        .map(|p| p.name.clone())
+        .collect();
+
+    Ok(ContainerTestReport {
+        profile: profile_name.to_string(),
+        image: image.to_string(),
+        runtime: runtime.to_string(),
+        install,
+        status,
+        failed_install,
+        failed_detect,
+    })
+}
+
+pub fn test_profile(profile_name: &str, image: &str) -> Result<()> {
+    profile::read_profile(profile_name)?;
+    let runtime = select_runtime()?;
+    say!(
+        "{} {} in {} ({})",
+        "Testing profile".bold(),
+        profile_name.cyan().bold(),
+        image.cyan(),
+        runtime
+    );
+
+    let root = stage(profile_name)?;
+    let result = run_container(&runtime, image, profile_name, &root);
+    if let Err(e) = fs::remove_dir_all(&root) {
+        log::warn!("failed to remove staging directory {:?}: {}", root, e);
+    }
+    let report = result?;
+
+    if output::is_structured() {
+        output::emit(&report)?;
+    } else {
+        print_report(&report);
+    }
+
+    if !report.passed() {
+        return Err(Error::ContainerTestFailed {
+            profile: profile_name.to_string(),
+            image: image.to_string(),
+            failed: report.failed_install.len() + report.failed_detect.len(),
+        });
+    }
+    Ok(())
+}
+
+fn print_report(report: &ContainerTestReport) {
+    say!("");
+    for name in &report.failed_install {
+        say!("  {} {}", "[install failed]".red().bold(), name.cyan());
+    }
+    for name in &report.failed_detect {
+        say!("  {} {}", "[detect failed] ".red().bold(), name.cyan());
+    }
+    if report.passed() {
+        say!(
+            "{} all {} packages installed and detected in {}",
+            "[ok]".green().bold(),
+            report.install.packages.len(),
+            report.image.cyan()
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serial_test::serial;
+    use tempfile::TempDir;
+
+    use super::*;
+    use crate::profile::Profile;
+
+    #[test]
+    #[serial]
+    fn test_stage_lays_out_profile_and_registry() {
+        let temp = TempDir::new().unwrap();
+        unsafe {
+            env::set_var("HOME", temp.path());
+            env::set_var("XDG_CONFIG_HOME", temp.path().join(".config"));
+        }
+        let reg_dir = registry::registry_dir();
+        fs::create_dir_all(&reg_dir).unwrap();
+        fs::write(reg_dir.join("tool.toml"), "[packages]\napt = \"tool\"\n")
+            .unwrap();
+        fs::create_dir_all(crate::config::profiles_dir()).unwrap();
+        profile::write_profile(&Profile {
+            name: "ci".to_string(),
+            origin: None,
+            packages: Vec::new(),
+        })
+        .unwrap();
+
+        let root = stage("ci").unwrap();
+        let config = root.join("config/blazinit");
+        assert!(root.join("bin/blazinit").is_file());
+        assert!(root.join("fallback/sudo").is_file());
+        assert!(config.join("profiles/ci.toml").is_file());
+        assert!(config.join("registry/tool.toml").is_file());
+        let settings = fs::read_to_string(config.join("config.toml")).unwrap();
+        assert!(settings.contains("default_profile = \"ci\""));
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_missing_status_report_fails() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path().join("stage");
+        fs::create_dir_all(root.join("out")).unwrap();
+        let install = serde_json::json!({
+            "profile": "ci",
+            "dry_run": false,
+            "packages": [],
+            "installed": 0,
+            "skipped": 0,
+            "failed": 0,
+        });
+        fs::write(temp.path().join("install.json"), install.to_string())
+            .unwrap();
+        // Writes the install report but never gets to the status check.
+        let runtime = temp.path().join("fake-docker");
+        write_executable(
+            &runtime,
+            format!(
+                "#!/bin/sh\ncp {:?} {:?}\n",
+                temp.path().join("install.json"),
+                root.join("out/install.json")
+            )
+            .as_bytes(),
+        )
+        .unwrap();
+
+        let result =
+            run_container(runtime.to_str().unwrap(), "debian:12", "ci", &root);
+        match result {
+            Err(Error::Container { message, .. }) => {
+                assert!(message.contains("status check"))
+            }
+            other => panic!("expected a container error, got {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,452 @@
+use std::fs;
+
+use colored::Colorize;
+use serde::Serialize;
+
+use crate::{
+    config,
+    error::{self, Error, Result},
+    installer,
+    output::{self, say},
+    profile, registry,
+    runner::CommandRunner,
+    updater::current_target,
+};
+
+const REMOTE_TIMEOUT_SECS: u64 = 5;
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct InstallerCheck {
+    pub name: String,
+    pub available: bool,
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum SudoState {
+    NotNeeded,
+    Passwordless,
+    RequiresPassword,
+    NotInstalled,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct RegistryFileError {
+    pub file: String,
+    pub error: String,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct UnknownPackage {
+    pub profile: String,
+    pub package: String,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct SystemInfo {
+    pub os: String,
+    pub distro: Option<String>,
+    pub arch: String,
+    pub target: Option<String>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct PathsInfo {
+    pub config_dir: String,
+    pub profiles_dir: String,
+    pub registry_dir: String,
+    pub bin_dir: String,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct RegistryInfo {
+    pub url: String,
+    pub version: Option<String>,
+    pub bundled_version: Option<String>,
+    pub packages: usize,
+    pub reachable: bool,
+    pub remote_version: Option<String>,
+    pub remote_error: Option<String>,
+    pub errors: Vec<RegistryFileError>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct DoctorReport {
+    pub system: SystemInfo,
+    pub installers: Vec<InstallerCheck>,
+    pub selected_installer: Option<String>,
+    pub preferred_installer: Option<String>,
+    pub sudo: SudoState,
+    pub paths: PathsInfo,
+    pub registry: RegistryInfo,
+    pub unknown_packages: Vec<UnknownPackage>,
+}
+
+impl DoctorReport {
+    pub fn problem_count(&self) -> usize {
+        self.registry.errors.len()
+            + self.unknown_packages.len()
+            + usize::from(!self.registry.reachable)
+            + usize::from(self.selected_installer.is_none())
+    }
+}
+
+fn parse_os_release(content: &str) -> Option<String> {
+    let field = |key: &str| {
+        content.lines().find_map(|l| {
+            l.strip_prefix(key)
+                .and_then(|v| v.strip_prefix('='))
+                .map(|v| v.trim_matches('"').to_string())
+        })
+    };
+    field("PRETTY_NAME").or_else(|| field("NAME"))
+}
+
+fn detect_distro(runner: &dyn CommandRunner) -> Option<String> {
+    match std::env::consts::OS {
+        "linux" => fs::read_to_string("/etc/os-release")
+            .ok()
+            .and_then(|c| parse_os_release(&c)),
+        "macos" => runner
+            .output("sw_vers -productVersion")
+            .ok()
+            .filter(|o| o.success())
+            .map(|o| format!("macOS {}", o.stdout.trim())),
+        _ => None,
+    }
+}
+
+fn check_sudo(runner: &dyn CommandRunner) -> SudoState {
+    if cfg!(windows) {
+        return SudoState::NotNeeded;
+    }
+    let is_root = runner
+        .output("id -u")
+        .is_ok_and(|o| o.success() && o.stdout.trim() == "0");
+    if !runner.is_available("sudo") {
+        return if is_root {
+            SudoState::NotNeeded
+        } else {
+            SudoState::NotInstalled
+        };
+    }
+    let passwordless = runner.output("sudo -n true").is_ok_and(|o| o.success());
+    if passwordless {
+        SudoState::Passwordless
+    } else {
+        SudoState::RequiresPassword
+    }
+}
+
+pub fn registry_errors() -> Vec<RegistryFileError> {
+    registry::validate_registry_files()
+        .into_iter()
+        .map(|(file, error)| RegistryFileError { file, error })
+        .collect()
+}
+
+pub fn unknown_packages() -> Vec<UnknownPackage> {
+    let mut unknown = Vec::new();
+    for name in profile::profile_names() {
+        let Ok(p) = profile::read_profile(&name) else {
+            continue;
+        };
+        for pkg_ref in p.packages {
+            if !registry::is_package_in_registry(&pkg_ref.name).unwrap_or(false)
+            {
+                unknown.push(UnknownPackage {
+                    profile: name.clone(),
+                    package: pkg_ref.name,
+                });
+            }
+        }
+    }
+    unknown
+}
+
+pub fn installer_checks(runner: &dyn CommandRunner) -> Vec<InstallerCheck> {
+    installer::INSTALLER_PRIORITY
+        .iter()
+        .map(|name| InstallerCheck {
+            name: name.to_string(),
+            available: runner.is_available(name),
+        })
+        .collect()
+}
+
+pub fn collect_report(runner: &dyn CommandRunner) -> DoctorReport {
+    let installers = installer_checks(runner);
+    let selected_installer = installer::default_installer(&None, runner);
+
+    let (reachable, remote_version, remote_error) =
+        match registry::check_remote_registry(REMOTE_TIMEOUT_SECS) {
+            Ok(v) => (true, Some(v), None),
+            Err(e) => (false, None, Some(error::chain(&e))),
+        };
+
+    let errors = registry_errors();
+    let packages = registry::read_registry()
+        .ok()
+        .and_then(|r| {
+            r.get("package").and_then(|p| p.as_table()).map(|t| t.len())
+        })
+        .unwrap_or(0);
+
+    DoctorReport {
+        system: SystemInfo {
+            os: std::env::consts::OS.to_string(),
+            distro: detect_distro(runner),
+            arch: std::env::consts::ARCH.to_string(),
+            target: current_target().map(String::from),
+        },
+        installers,
+        selected_installer,
+        preferred_installer: config::get_preferred_installer(),
+        sudo: check_sudo(runner),
+        paths: PathsInfo {
+            config_dir: config::config_dir().display().to_string(),
+            profiles_dir: config::profiles_dir().display().to_string(),
+            registry_dir: registry::registry_dir().display().to_string(),
+            bin_dir: config::get_bin_dir()
+                .map(|d| d.display().to_string())
+                .unwrap_or_else(|e| e.to_string()),
+        },
+        registry: RegistryInfo {
+            url: config::get_registry_url(),
+            version: registry::registry_version(),
+            bundled_version: registry::bundled_registry_version(),
+            packages,
+            reachable,
+            remote_version,
+            remote_error,
+            errors,
+        },
+        unknown_packages: if packages > 0 {
+            unknown_packages()
+        } else {
+            Vec::new()
+        },
+    }
+}
+
+fn print_report(report: &DoctorReport) {
+    let ok = |b: bool| if b { "ok".green() } else { "missing".red() };
+    let or_unknown =
+        |v: &Option<String>| v.clone().unwrap_or_else(|| "unknown".to_string());
+
+    say!("{}", "System".bold());
+    say!(
+        "  OS:        {} ({})",
+        report.system.os.cyan(),
+        or_unknown(&report.system.distro)
+    );
+    say!("  Arch:      {}", report.system.arch.cyan());
+    say!("  Target:    {}", or_unknown(&report.system.target));
+
+    say!("{}", "Installers".bold());
+    for check in &report.installers {
+        let selected =
+            report.selected_installer.as_deref() == Some(check.name.as_str());
+        say!(
+            "  {:<8} {}{}",
+            check.name,
+            ok(check.available),
+            if selected {
+                " (selected)".green().bold().to_string()
+            } else {
+                String::new()
+            }
+        );
+    }
+    if let Some(preferred) = &report.preferred_installer {
+        let selected = report.selected_installer.as_ref() == Some(preferred)
+            && !report.installers.iter().any(|i| &i.name == preferred);
+        say!(
+            "  Preferred in config: {}{}",
+            preferred.cyan(),
+            if selected {
+                " (selected)".green().bold().to_string()
+            } else {
+                String::new()
+            }
+        );
+    }
+    let sudo = match report.sudo {
+        SudoState::NotNeeded => "not needed".green(),
+        SudoState::Passwordless => "passwordless".green(),
+        SudoState::RequiresPassword => "requires a password".yellow(),
+        SudoState::NotInstalled => "not installed".red(),
+    };
+    say!("  {:<8} {}", "sudo", sudo);
+
+    say!("{}", "Paths".bold());
+    say!("  Config:    {}", report.paths.config_dir);
+    say!("  Profiles:  {}", report.paths.profiles_dir);
+    say!("  Registry:  {}", report.paths.registry_dir);
+    say!("  Binaries:  {}", report.paths.bin_dir);
+
+    say!("{}", "Registry".bold());
+    say!(
+        "  Version:   {} (bundled {}), {} packages",
+        or_unknown(&report.registry.version).cyan(),
+        or_unknown(&report.registry.bundled_version),
+        report.registry.packages
+    );
+    if report.registry.reachable {
+        say!(
+            "  Remote:    {} reachable (version {})",
+            report.registry.url,
+            or_unknown(&report.registry.remote_version).cyan()
+        );
+    } else {
+        say!(
+            "  Remote:    {} {} ({})",
+            report.registry.url,
+            "unreachable".red(),
+            or_unknown(&report.registry.remote_error)
+        );
+    }
+
+    let problems = report.problem_count();
+    if problems == 0 {
+        say!("\n{}", "No problems found.".green());
+        return;
+    }
+    say!("\n{} ({})", "Problems".bold(), problems.to_string().red());
+    if report.selected_installer.is_none() {
+        say!("  - no supported system installer found");
+    }
+    for err in &report.registry.errors {
+        say!("  - registry file {}: {}", err.file.cyan(), err.error);
+    }
+    for unknown in &report.unknown_packages {
+        say!(
+            "  - profile {} references unknown package {}",
+            unknown.profile.cyan(),
+            unknown.package.cyan()
+        );
+    }
+    if !report.registry.reachable {
+        say!("  - registry URL is not reachable");
+    }
+}
+
+// The report is printed either way; problems make the run fail so scripts
+// and CI can act on them.
+pub fn run_doctor(runner: &dyn CommandRunner) -> Result<()> {
+    let report = collect_report(runner);
+    let problems = report.problem_count();
+    log::info!("doctor: {} problems found", problems);
+    if output::is_structured() {
+        output::emit(&report)?;
+    } else {
+        print_report(&report);
+    }
+    if problems > 0 {
+        return Err(Error::DoctorProblems(problems));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use serial_test::serial;
+    use tempfile::TempDir;
+
+    use super::*;
+    use crate::runner::FakeRunner;
+
+    fn setup_test_env() -> TempDir {
+        let temp_dir = TempDir::new().expect("Failed to create temp dir");
+        unsafe {
+            env::set_var("HOME", temp_dir.path());
+            env::set_var("XDG_CONFIG_HOME", temp_dir.path().join(".config"));
+        }
+        let reg_dir = config::config_dir().join("registry");
+        fs::create_dir_all(&reg_dir).unwrap();
+        fs::write(reg_dir.join("metadata.toml"), "version = \"3\"\n").unwrap();
+        fs::write(reg_dir.join("git.toml"), "display = \"Git\"\n").unwrap();
+        temp_dir
+    }
+
+    #[test]
+    fn test_installer_and_sudo_checks_use_the_runner() {
+        let runner = FakeRunner::new().with_program("brew");
+        let available: Vec<String> = installer_checks(&runner)
+            .into_iter()
+            .filter(|c| c.available)
+            .map(|c| c.name)
+            .collect();
+        assert_eq!(available, vec!["brew"]);
+
+        if cfg!(windows) {
+            return;
+        }
+        assert_eq!(check_sudo(&runner), SudoState::NotInstalled);
+        let root = FakeRunner::new().respond("id -u", 0, "0\n");
+        assert_eq!(check_sudo(&root), SudoState::NotNeeded);
+        let sudo = FakeRunner::new().with_program("sudo").respond(
+            "sudo -n true",
+            0,
+            "",
+        );
+        assert_eq!(check_sudo(&sudo), SudoState::Passwordless);
+        let sudo = FakeRunner::new().with_program("sudo");
+        assert_eq!(check_sudo(&sudo), SudoState::RequiresPassword);
+    }
+
+    #[test]
+    fn test_parse_os_release() {
+        let content = "NAME=\"Debian GNU/Linux\"\nPRETTY_NAME=\"Debian GNU/Linux 12 (bookworm)\"\n";
+        assert_eq!(
+            parse_os_release(content),
+            Some("Debian GNU/Linux 12 (bookworm)".to_string())
+        );
+        assert_eq!(parse_os_release("NAME=Arch\n"), Some("Arch".to_string()));
+        assert_eq!(parse_os_release(""), None);
+    }
+
+    #[test]
+    #[serial]
+    fn test_registry_errors_reports_broken_files() {
+        let _temp = setup_test_env();
+        let reg_dir = config::config_dir().join("registry");
+        fs::write(reg_dir.join("broken.toml"), "display = [").unwrap();
+
+        let errors = registry_errors();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].file, "broken.toml");
+    }
+
+    #[test]
+    #[serial]
+    fn test_unknown_packages_across_profiles() {
+        let _temp = setup_test_env();
+        profile::write_profile(&profile::Profile {
+            name: "work".to_string(),
+            origin: None,
+            packages: vec![
+                profile::PackageRef {
+                    name: "git".to_string(),
+                    ..Default::default()
+                },
+                profile::PackageRef {
+                    name: "nvim".to_string(),
+                    ..Default::default()
+                },
+            ],
+        })
+        .unwrap();
+
+        let unknown = unknown_packages();
+        assert_eq!(
+            unknown,
+            vec![UnknownPackage {
+                profile: "work".to_string(),
+                package: "nvim".to_string(),
+            }]
+        );
+    }
+}
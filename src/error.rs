@@ -0,0 +1,381 @@
+use std::{io, path::PathBuf};
+
+use crate::suggest;
+
+type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum Error {
+    #[error("Profile '{name}' does not exist{}", suggest::hint(suggestions))]
+    ProfileNotFound {
+        name: String,
+        suggestions: Vec<String>,
+    },
+
+    #[error("Profile '{0}' already exists")]
+    ProfileExists(String),
+
+    #[error("Cannot delete the default profile '{0}'")]
+    DefaultProfile(String),
+
+    #[error(
+        "Package '{name}' not found in registry{}",
+        suggest::hint(suggestions)
+    )]
+    PackageNotFound {
+        name: String,
+        suggestions: Vec<String>,
+    },
+
+    #[error("Package '{package}' is already present in profile '{profile}'")]
+    PackageExists { package: String, profile: String },
+
+    #[error("Package '{package}' is not present in profile '{profile}'")]
+    PackageNotInProfile { package: String, profile: String },
+
+    #[error(
+        "No package in profile '{profile}' is tagged '{tag}'{}",
+        suggest::hint(suggestions)
+    )]
+    TagNotFound {
+        tag: String,
+        profile: String,
+        suggestions: Vec<String>,
+    },
+
+    #[error(
+        "Invalid registry file '{file}'{}: {message}",
+        line.map(|l| format!(" at line {}", l)).unwrap_or_default()
+    )]
+    RegistryParse {
+        file: String,
+        line: Option<usize>,
+        message: String,
+    },
+
+    #[error(
+        "{conflicts} packages are pinned differently in '{destination}'. Pick a side with --strategy theirs or ours."
+    )]
+    MergeConflict {
+        destination: String,
+        conflicts: usize,
+    },
+
+    #[error(
+        "Package '{package}' is pinned differently in '{first}' ({first_pins}) and '{second}' ({second_pins}). Align the pins or install the profiles separately."
+    )]
+    PinConflict {
+        package: String,
+        first: String,
+        first_pins: String,
+        second: String,
+        second_pins: String,
+    },
+
+    #[error("Invalid package definition '{name}': {message}")]
+    InvalidDefinition { name: String, message: String },
+
+    #[error(
+        "Package '{0}' is already in the registry. Use --force to replace it."
+    )]
+    RegistryEntryExists(String),
+
+    #[error("Invalid {kind} file {path:?}")]
+    InvalidFile {
+        kind: &'static str,
+        path: PathBuf,
+        #[source]
+        source: Box<toml::de::Error>,
+    },
+
+    #[error("Invalid {kind}: {message}")]
+    InvalidData { kind: &'static str, message: String },
+
+    #[error("Invalid version requirement '{0}'")]
+    InvalidVersion(String),
+
+    #[error("Invalid version regex '{pattern}'")]
+    InvalidRegex {
+        pattern: String,
+        #[source]
+        source: regex::Error,
+    },
+
+    #[error("Circular dependency detected involving '{0}'")]
+    DependencyCycle(String),
+
+    #[error("Installer '{installer}' not available for package '{package}'")]
+    InstallerUnavailable { installer: String, package: String },
+
+    #[error("No suitable installer found for package '{0}'")]
+    NoInstaller(String),
+
+    #[error(
+        "Installing '{package}' failed: {}",
+        code.map(|c| format!("exit status: {}", c))
+            .unwrap_or_else(|| "terminated by signal".to_string())
+    )]
+    InstallFailed { package: String, code: Option<i32> },
+
+    #[error(
+        "'{package}' is at {installed} after installing, which does not satisfy '{wanted}'"
+    )]
+    VersionUnsatisfied {
+        package: String,
+        installed: String,
+        wanted: String,
+    },
+
+    #[error(
+        "{failed} of {total} packages in profile '{profile}' failed to install"
+    )]
+    InstallIncomplete {
+        profile: String,
+        failed: usize,
+        total: usize,
+    },
+
+    #[error("Could not list installed packages with `{command}`: {message}")]
+    NativeList { command: String, message: String },
+
+    #[error("{failed} of {total} hosts did not complete the install")]
+    HostsIncomplete { failed: usize, total: usize },
+
+    #[error("{failed} of {total} subscribed profiles failed to sync")]
+    SyncIncomplete { failed: usize, total: usize },
+
+    #[error("Container test with image '{image}' failed: {message}")]
+    Container { image: String, message: String },
+
+    #[error(
+        "{failed} packages of profile '{profile}' failed in image '{image}'"
+    )]
+    ContainerTestFailed {
+        profile: String,
+        image: String,
+        failed: usize,
+    },
+
+    #[error("Cannot reach host '{host}': {message}")]
+    HostUnreachable { host: String, message: String },
+
+    #[error(
+        "{failing} of {total} packages in profile '{profile}' are not installed as required"
+    )]
+    Unsatisfied {
+        profile: String,
+        failing: usize,
+        total: usize,
+    },
+
+    #[error("doctor found {0} problems")]
+    DoctorProblems(usize),
+
+    #[error("Profile '{0}' has no lock file. Run `blazinit lock {0}` first.")]
+    LockMissing(String),
+
+    #[error(
+        "Profile '{profile}' has packages missing from its lock file: {}. Re-run `blazinit lock {profile}`.",
+        packages.join(", ")
+    )]
+    LockIncomplete {
+        profile: String,
+        packages: Vec<String>,
+    },
+
+    #[error(
+        "Cannot lock '{profile}': {} not installed and not pinned to an exact version. Install the profile first or pin exact versions.",
+        packages.join(", ")
+    )]
+    LockUnresolved {
+        profile: String,
+        packages: Vec<String>,
+    },
+
+    #[error(
+        "Registry has drifted since '{profile}' was locked: {}. Re-run `blazinit lock {profile}`.",
+        packages.join(", ")
+    )]
+    RegistryDrift {
+        profile: String,
+        packages: Vec<String>,
+    },
+
+    #[error(
+        "Profile '{profile}' no longer matches its lock file: {}. Re-run `blazinit lock {profile}`.",
+        changes.join("; ")
+    )]
+    LockStale {
+        profile: String,
+        changes: Vec<String>,
+    },
+
+    #[error(
+        "Profile '{profile}' bundles custom install commands for {}. Review them and re-run with --trust.",
+        packages.join(", ")
+    )]
+    UntrustedCommands {
+        profile: String,
+        packages: Vec<String>,
+    },
+
+    #[error("Profile '{profile}' left unchanged; your edits are in {draft:?}")]
+    EditRejected {
+        profile: String,
+        draft: PathBuf,
+        #[source]
+        source: Box<Error>,
+    },
+
+    #[error("Invalid name '{name}': {reason}")]
+    InvalidName { name: String, reason: String },
+
+    #[error("Request to {url} failed")]
+    Network {
+        url: String,
+        #[source]
+        source: BoxError,
+    },
+
+    #[error("git could not fetch {url}: {message}")]
+    GitFailed { url: String, message: String },
+
+    #[error("Unexpected response from {url}: {message}")]
+    InvalidResponse { url: String, message: String },
+
+    #[error(
+        "Checksum mismatch for '{asset}': expected {expected}, got {actual}"
+    )]
+    ChecksumMismatch {
+        asset: String,
+        expected: String,
+        actual: String,
+    },
+
+    #[error("{context}")]
+    Io {
+        context: String,
+        #[source]
+        source: io::Error,
+    },
+
+    #[error("Failed to serialize {what}")]
+    Serialize {
+        what: &'static str,
+        #[source]
+        source: BoxError,
+    },
+
+    #[error("{0}")]
+    Unsupported(String),
+
+    #[error("{0}")]
+    Other(String),
+}
+
+impl Error {
+    pub(crate) fn io(
+        context: impl Into<String>,
+    ) -> impl FnOnce(io::Error) -> Self {
+        let context = context.into();
+        move |source| Error::Io { context, source }
+    }
+
+    pub(crate) fn network<E: Into<BoxError>>(
+        url: &str,
+    ) -> impl FnOnce(E) -> Self {
+        let url = url.to_string();
+        move |source| Error::Network {
+            url,
+            source: source.into(),
+        }
+    }
+
+    pub(crate) fn serialize<E: Into<BoxError>>(
+        what: &'static str,
+    ) -> impl FnOnce(E) -> Self {
+        move |source| Error::Serialize {
+            what,
+            source: source.into(),
+        }
+    }
+
+    pub(crate) fn registry_parse(
+        file: &str,
+        content: &str,
+        err: &toml::de::Error,
+    ) -> Self {
+        Error::RegistryParse {
+            file: file.to_string(),
+            line: err
+                .span()
+                .map(|span| content[..span.start].matches('\n').count() + 1),
+            message: err.message().to_string(),
+        }
+    }
+}
+
+// The error and every source below it on one line, for places that only keep
+// a string (install outcomes, the doctor report, the CLI).
+pub fn chain(err: &dyn std::error::Error) -> String {
+    let mut out = err.to_string();
+    let mut source = err.source();
+    while let Some(cause) = source {
+        out.push_str(": ");
+        out.push_str(cause.to_string().trim());
+        source = cause.source();
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_registry_parse_reports_line() {
+        let content = "display = \"Git\"\n\ndetect = = 1\nversion = \"1\"\n";
+        let err = toml::from_str::<toml::Value>(content).unwrap_err();
+        let error = Error::registry_parse("git.toml", content, &err);
+        match &error {
+            Error::RegistryParse { file, line, .. } => {
+                assert_eq!(file, "git.toml");
+                assert_eq!(*line, Some(3));
+            }
+            other => panic!("unexpected error: {:?}", other),
+        }
+        assert!(
+            error
+                .to_string()
+                .starts_with("Invalid registry file 'git.toml' at line 3")
+        );
+    }
+
+    #[test]
+    fn test_not_found_errors_carry_suggestions() {
+        let error = Error::ProfileNotFound {
+            name: "wrok".to_string(),
+            suggestions: vec!["work".to_string()],
+        };
+        assert_eq!(
+            error.to_string(),
+            "Profile 'wrok' does not exist. Did you mean 'work'?"
+        );
+        let error = Error::PackageNotFound {
+            name: "zzz".to_string(),
+            suggestions: Vec::new(),
+        };
+        assert_eq!(error.to_string(), "Package 'zzz' not found in registry");
+    }
+
+    #[test]
+    fn test_chain_includes_sources() {
+        let error = Error::io("Failed to write lock file")(io::Error::other(
+            "disk full",
+        ));
+        assert_eq!(chain(&error), "Failed to write lock file: disk full");
+    }
+}
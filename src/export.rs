@@ -0,0 +1,842 @@
+use std::{collections::BTreeMap, fs};
+
+use clap::ValueEnum;
+use colored::Colorize;
+use serde::Serialize;
+
+use crate::{
+    error::{Error, Result},
+    installer::{self, install_prefix, install_target},
+    output::{note, say},
+    profile::{self, PackageRef, Profile},
+    registry,
+    version::VersionReq,
+};
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Toml,
+    Dockerfile,
+    Sh,
+    Powershell,
+    Brewfile,
+    AptList,
+    Winget,
+    Ansible,
+}
+
+impl ExportFormat {
+    fn default_installers(self) -> &'static [&'static str] {
+        match self {
+            ExportFormat::Powershell | ExportFormat::Winget => &["winget"],
+            ExportFormat::Brewfile => &["brew"],
+            ExportFormat::Ansible => &["apt", "brew"],
+            _ => &["apt"],
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ExportOptions {
+    pub format: ExportFormat,
+    // Installer of the machine the output is meant for, not this one.
+    pub installer: Option<String>,
+    pub image: Option<String>,
+    // Embed custom registry definitions in a TOML export.
+    pub bundle: bool,
+}
+
+// One command: consecutive packages of the same system installer share it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Step {
+    pub installer: String,
+    pub packages: Vec<String>,
+    pub targets: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Plan {
+    pub steps: Vec<Step>,
+    pub unmapped: Vec<String>,
+    // Packages whose installer pin the target cannot use, with that pin.
+    pub ignored_pins: Vec<(String, String)>,
+}
+
+// The installer `pkg_ref` pins, when it is none of `usable`.
+fn unusable_pin(
+    pkg_ref: Option<&PackageRef>,
+    usable: &[&str],
+) -> Option<String> {
+    pkg_ref
+        .and_then(|r| r.installer.clone())
+        .filter(|pin| !usable.contains(&pin.as_str()))
+}
+
+fn batches(installer: &str) -> bool {
+    matches!(installer, "apt" | "dnf" | "yum" | "pacman" | "brew")
+}
+
+// Resolves install order and installers for `target` without looking at
+// this machine: only the target installer and custom commands qualify, and
+// a per-package pin can only prefer the custom command.
+pub fn plan(profile: &Profile, target: &str) -> Result<Plan> {
+    let mut plan = Plan::default();
+    for name in installer::topological_sort(&profile.packages)? {
+        let pkg = registry::get_package_details(&name)?;
+        let pkg_ref = profile.packages.iter().find(|p| p.name == name);
+        let candidates = match pkg_ref.and_then(|r| r.installer.as_deref()) {
+            Some("custom") => ["custom", target],
+            _ => [target, "custom"],
+        };
+        if let Some(pin) = unusable_pin(pkg_ref, &[target, "custom"]) {
+            log::warn!("'{}': pin '{}' ignored for {}", name, pin, target);
+            plan.ignored_pins.push((name.clone(), pin));
+        }
+        let Some((installer, value)) = candidates.iter().find_map(|i| {
+            pkg.installers.get(*i).map(|v| (i.to_string(), v.clone()))
+        }) else {
+            log::warn!("'{}' has no '{}' or custom installer", name, target);
+            plan.unmapped.push(name);
+            continue;
+        };
+
+        let version = pkg_ref
+            .and_then(|r| r.version.as_deref())
+            .map(VersionReq::parse)
+            .transpose()?
+            .and_then(|req| req.install_version());
+        let target = install_target(&installer, &value, version.as_deref());
+
+        match plan.steps.last_mut() {
+            Some(step)
+                if step.installer == installer && batches(&installer) =>
+            {
+                step.packages.push(name);
+                step.targets.push(target)
+            }
+            _ => plan.steps.push(Step {
+                installer,
+                packages: vec![name],
+                targets: vec![target],
+            }),
+        }
+    }
+    Ok(plan)
+}
+
+fn shell_command(step: &Step) -> String {
+    match install_prefix(&step.installer) {
+        Some(prefix) => format!("{} {}", prefix, step.targets.join(" ")),
+        None => step.targets.join(" "),
+    }
+}
+
+// Builds run as root without sudo, and apt needs its package lists first.
+fn docker_command(step: &Step) -> String {
+    let packages = step.targets.join(" ");
+    match step.installer.as_str() {
+        "apt" => format!(
+            "apt-get update && apt-get install -y {} && rm -rf /var/lib/apt/lists/*",
+            packages
+        ),
+        _ => {
+            let command = shell_command(step);
+            command
+                .strip_prefix("sudo ")
+                .unwrap_or(&command)
+                .to_string()
+        }
+    }
+}
+
+fn default_image(installer: &str) -> Option<&'static str> {
+    match installer {
+        "apt" => Some("debian:12"),
+        "dnf" => Some("fedora:latest"),
+        "yum" => Some("rockylinux:9"),
+        "pacman" => Some("archlinux:latest"),
+        "brew" => Some("homebrew/brew:latest"),
+        _ => None,
+    }
+}
+
+fn header(comment: &str, profile: &str, installer: &str) -> String {
+    format!(
+        "{} Generated by blazinit from profile '{}' for {}.\n",
+        comment, profile, installer
+    )
+}
+
+fn skipped(comment: &str, plan: &Plan, installer: &str) -> String {
+    plan.unmapped
+        .iter()
+        .map(|name| {
+            format!(
+                "{} skipped {}: no {} or custom installer\n",
+                comment, name, installer
+            )
+        })
+        .collect()
+}
+
+pub fn render_script(
+    profile: &Profile,
+    plan: &Plan,
+    installer: &str,
+    options: &ExportOptions,
+) -> Result<String> {
+    let mut out = String::new();
+    match options.format {
+        ExportFormat::Sh => {
+            out.push_str("#!/bin/sh\n");
+            out.push_str(&header("#", &profile.name, installer));
+            out.push_str(&skipped("#", plan, installer));
+            out.push_str("set -e\n\n");
+            for step in &plan.steps {
+                out.push_str(&shell_command(step));
+                out.push('\n');
+            }
+        }
+        ExportFormat::Powershell => {
+            out.push_str(&header("#", &profile.name, installer));
+            out.push_str(&skipped("#", plan, installer));
+            out.push_str("$ErrorActionPreference = 'Stop'\n\n");
+            // A failing native command does not stop the script by itself.
+            // Custom commands are written for sh and cannot run here.
+            for step in &plan.steps {
+                if step.installer == "custom" {
+                    out.push_str(&format!(
+                        "# skipped {}: custom command is for sh: {}\n",
+                        step.packages.join(", "),
+                        shell_command(step)
+                    ));
+                    continue;
+                }
+                out.push_str(&shell_command(step));
+                out.push_str(
+                    "\nif ($LASTEXITCODE -ne 0) { exit $LASTEXITCODE }\n",
+                );
+            }
+        }
+        ExportFormat::Dockerfile => {
+            let image = match &options.image {
+                Some(image) => image.as_str(),
+                None => default_image(installer).ok_or_else(|| {
+                    Error::Unsupported(format!(
+                        "No default base image for installer '{}'. Pass --image.",
+                        installer
+                    ))
+                })?,
+            };
+            out.push_str(&header("#", &profile.name, installer));
+            out.push_str(&skipped("#", plan, installer));
+            out.push_str(&format!("FROM {}\n\n", image));
+            for step in &plan.steps {
+                out.push_str(&format!("RUN {}\n", docker_command(step)));
+            }
+        }
+        format => {
+            return Err(Error::Unsupported(format!(
+                "{:?} is not a script format",
+                format
+            )));
+        }
+    }
+    Ok(out)
+}
+
+// A package under its name in one installer's own ecosystem.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mapped {
+    pub installer: String,
+    pub value: String,
+    pub version: Option<String>,
+}
+
+impl Mapped {
+    fn target(&self) -> String {
+        install_target(&self.installer, &self.value, self.version.as_deref())
+    }
+}
+
+// Native package lists carry no commands, so unlike `plan` a package is only
+// exported when the registry names it for one of `installers`.
+pub fn map_packages(
+    profile: &Profile,
+    installers: &[&str],
+) -> Result<(Vec<Mapped>, Vec<String>)> {
+    let mut mapped = Vec::new();
+    let mut unmapped = Vec::new();
+    for name in installer::topological_sort(&profile.packages)? {
+        let pkg = registry::get_package_details(&name)?;
+        let version = profile
+            .packages
+            .iter()
+            .find(|p| p.name == name)
+            .and_then(|r| r.version.as_deref())
+            .map(VersionReq::parse)
+            .transpose()?
+            .and_then(|req| req.install_version());
+        let before = mapped.len();
+        for installer in installers {
+            if let Some(value) = pkg.installers.get(*installer) {
+                mapped.push(Mapped {
+                    installer: installer.to_string(),
+                    value: value.clone(),
+                    version: version.clone(),
+                });
+            }
+        }
+        if mapped.len() == before {
+            log::warn!("'{}' has no mapping for {:?}", name, installers);
+            unmapped.push(name);
+        }
+    }
+    Ok((mapped, unmapped))
+}
+
+#[derive(Serialize)]
+struct WingetExport {
+    #[serde(rename = "$schema")]
+    schema: &'static str,
+    #[serde(rename = "Sources")]
+    sources: Vec<WingetSource>,
+}
+
+#[derive(Serialize)]
+struct WingetSource {
+    #[serde(rename = "Packages")]
+    packages: Vec<WingetPackage>,
+    #[serde(rename = "SourceDetails")]
+    source_details: WingetSourceDetails,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct WingetPackage {
+    package_identifier: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    version: Option<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct WingetSourceDetails {
+    argument: &'static str,
+    identifier: &'static str,
+    name: &'static str,
+    #[serde(rename = "Type")]
+    kind: &'static str,
+}
+
+#[derive(Serialize)]
+struct Play {
+    name: String,
+    hosts: &'static str,
+    tasks: Vec<Task>,
+}
+
+#[derive(Serialize)]
+struct Task {
+    name: String,
+    #[serde(rename = "become", skip_serializing_if = "std::ops::Not::not")]
+    become_root: bool,
+    #[serde(flatten)]
+    module: BTreeMap<&'static str, ModuleArgs>,
+    when: String,
+}
+
+#[derive(Serialize)]
+struct ModuleArgs {
+    name: Vec<String>,
+    state: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    update_cache: Option<bool>,
+}
+
+// Ansible module, OS family and whether it needs root, per installer.
+fn ansible_module(
+    installer: &str,
+) -> Option<(&'static str, &'static str, bool)> {
+    match installer {
+        "apt" => Some(("ansible.builtin.apt", "Debian", true)),
+        "dnf" => Some(("ansible.builtin.dnf", "RedHat", true)),
+        "yum" => Some(("ansible.builtin.yum", "RedHat", true)),
+        "pacman" => Some(("community.general.pacman", "Archlinux", true)),
+        "brew" => Some(("community.general.homebrew", "Darwin", false)),
+        _ => None,
+    }
+}
+
+fn ansible_playbook(
+    profile: &Profile,
+    mapped: &[Mapped],
+    installers: &[&str],
+) -> Result<String> {
+    let mut tasks = Vec::new();
+    for installer in installers {
+        let (module, family, become_root) = ansible_module(installer)
+            .ok_or_else(|| {
+                Error::Unsupported(format!(
+                    "Ansible export does not support installer '{}'",
+                    installer
+                ))
+            })?;
+        let names: Vec<String> = mapped
+            .iter()
+            .filter(|m| m.installer == *installer)
+            .map(Mapped::target)
+            .collect();
+        if names.is_empty() {
+            continue;
+        }
+        tasks.push(Task {
+            name: format!("Install {} packages", installer),
+            become_root,
+            module: BTreeMap::from([(
+                module,
+                ModuleArgs {
+                    name: names,
+                    state: "present",
+                    update_cache: (*installer == "apt").then_some(true),
+                },
+            )]),
+            when: format!("ansible_facts['os_family'] == '{}'", family),
+        });
+    }
+    let play = vec![Play {
+        name: format!("Install blazinit profile {}", profile.name),
+        hosts: "all",
+        tasks,
+    }];
+    serde_yaml::to_string(&play).map_err(Error::serialize("playbook"))
+}
+
+pub fn render_native(
+    profile: &Profile,
+    mapped: &[Mapped],
+    installers: &[&str],
+    format: ExportFormat,
+) -> Result<String> {
+    // These lists only have room for their own ecosystem's packages.
+    let single = matches!(
+        format,
+        ExportFormat::Brewfile | ExportFormat::AptList | ExportFormat::Winget
+    );
+    if single && installers != format.default_installers() {
+        return Err(Error::Unsupported(format!(
+            "--to {} only lists {} packages; drop --installer {}",
+            format.to_possible_value().unwrap().get_name(),
+            format.default_installers()[0],
+            installers.join(",")
+        )));
+    }
+    let mut out = String::new();
+    match format {
+        ExportFormat::Brewfile => {
+            out.push_str(&header("#", &profile.name, "brew"));
+            for m in mapped {
+                out.push_str(&format!("brew \"{}\"\n", m.target()));
+            }
+        }
+        // One package per line, ready for `xargs apt-get install -y`.
+        ExportFormat::AptList => {
+            for m in mapped {
+                out.push_str(&m.target());
+                out.push('\n');
+            }
+        }
+        ExportFormat::Winget => {
+            let doc = WingetExport {
+                schema: "https://aka.ms/winget-packages.schema.2.0.json",
+                sources: vec![WingetSource {
+                    packages: mapped
+                        .iter()
+                        .map(|m| WingetPackage {
+                            package_identifier: m.value.clone(),
+                            version: m.version.clone(),
+                        })
+                        .collect(),
+                    source_details: WingetSourceDetails {
+                        argument: "https://cdn.winget.microsoft.com/cache",
+                        identifier: "Microsoft.Winget.Source_8wekyb3d8bbwe",
+                        name: "winget",
+                        kind: "Microsoft.PreIndexed.Package",
+                    },
+                }],
+            };
+            out = serde_json::to_string_pretty(&doc)
+                .map_err(Error::serialize("winget export"))?;
+            out.push('\n');
+        }
+        ExportFormat::Ansible => {
+            out.push_str(&header("#", &profile.name, &installers.join("/")));
+            out.push_str(&ansible_playbook(profile, mapped, installers)?);
+        }
+        format => {
+            return Err(Error::Unsupported(format!(
+                "{:?} is not a package list format",
+                format
+            )));
+        }
+    }
+    Ok(out)
+}
+
+pub fn export(
+    profile_name: &str,
+    file: &Option<String>,
+    options: &ExportOptions,
+) -> Result<()> {
+    if options.format == ExportFormat::Toml {
+        if options.installer.is_some() || options.image.is_some() {
+            return Err(Error::Unsupported(
+                "--installer and --image do not apply to TOML exports"
+                    .to_string(),
+            ));
+        }
+        return profile::export_profile(profile_name, file, options.bundle);
+    }
+    if options.bundle {
+        return Err(Error::Unsupported(
+            "--bundle only applies to TOML exports".to_string(),
+        ));
+    }
+    if options.image.is_some() && options.format != ExportFormat::Dockerfile {
+        return Err(Error::Unsupported(
+            "--image only applies to --to dockerfile".to_string(),
+        ));
+    }
+    let profile = profile::read_profile(profile_name)?;
+    let installers = match &options.installer {
+        Some(installer) => vec![installer.as_str()],
+        None => options.format.default_installers().to_vec(),
+    };
+    log::info!(
+        "exporting profile '{}' as {:?} for {:?}",
+        profile_name,
+        options.format,
+        installers
+    );
+
+    // Packages left out or exported differently than the profile asks, and
+    // why.
+    let mut warnings: Vec<(String, String)> = Vec::new();
+    let content = match options.format {
+        ExportFormat::Dockerfile
+        | ExportFormat::Sh
+        | ExportFormat::Powershell => {
+            let plan = plan(&profile, installers[0])?;
+            let content =
+                render_script(&profile, &plan, installers[0], options)?;
+            for name in &plan.unmapped {
+                warnings.push((
+                    name.clone(),
+                    format!(
+                        "has no {} or custom installer and was left out",
+                        installers[0]
+                    ),
+                ));
+            }
+            for (name, pin) in &plan.ignored_pins {
+                warnings.push((
+                    name.clone(),
+                    format!(
+                        "is pinned to {}, which a {} export cannot use; the pin was ignored",
+                        pin, installers[0]
+                    ),
+                ));
+            }
+            if options.format == ExportFormat::Powershell {
+                for step in
+                    plan.steps.iter().filter(|s| s.installer == "custom")
+                {
+                    for name in &step.packages {
+                        warnings.push((
+                            name.clone(),
+                            "only has an sh install command and was left out"
+                                .to_string(),
+                        ));
+                    }
+                }
+            }
+            content
+        }
+        format => {
+            let (mapped, unmapped) = map_packages(&profile, &installers)?;
+            let content =
+                render_native(&profile, &mapped, &installers, format)?;
+            for name in &unmapped {
+                warnings.push((
+                    name.clone(),
+                    format!(
+                        "has no {} package and was left out",
+                        installers.join(" or ")
+                    ),
+                ));
+            }
+            for package in &profile.packages {
+                if let Some(pin) = unusable_pin(Some(package), &installers) {
+                    warnings.push((
+                        package.name.clone(),
+                        format!(
+                            "is pinned to {}, which a {} list cannot use; the pin was ignored",
+                            pin,
+                            installers.join("/")
+                        ),
+                    ));
+                }
+            }
+            content
+        }
+    };
+    for (name, reason) in &warnings {
+        note!(
+            "{} '{}' {}",
+            "Warning:".yellow().bold(),
+            name.cyan(),
+            reason
+        );
+    }
+
+    match file {
+        Some(dest) => {
+            fs::write(dest, content)
+                .map_err(Error::io("Failed to export profile"))?;
+            log::info!("exported profile '{}' to '{}'", profile_name, dest);
+            say!(
+                "{} '{}' exported to '{}'",
+                "Profile".green(),
+                profile_name.cyan(),
+                dest.cyan()
+            );
+        }
+        None => print!("{}", content),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use serial_test::serial;
+    use tempfile::TempDir;
+
+    use super::*;
+    use crate::profile::PackageRef;
+
+    fn setup_test_env() -> TempDir {
+        let temp_dir = TempDir::new().expect("Failed to create temp dir");
+        unsafe {
+            env::set_var("HOME", temp_dir.path());
+            env::set_var("XDG_CONFIG_HOME", temp_dir.path().join(".config"));
+        }
+        let reg_dir = registry::registry_dir();
+        fs::create_dir_all(&reg_dir).unwrap();
+        for (name, content) in [
+            ("git", "[packages]\napt = \"git\"\nbrew = \"git\"\n"),
+            ("curl", "[packages]\napt = \"curl\"\nbrew = \"curl\"\n"),
+            (
+                "rustup",
+                "dependencies = [\"curl\"]\n\n[packages]\ncustom = \"curl -sSf https://sh.rustup.rs | sh -s -- -y\"\n",
+            ),
+            ("ripgrep", "[packages]\napt = \"ripgrep\"\n"),
+            ("only-brew", "[packages]\nbrew = \"only-brew\"\n"),
+        ] {
+            fs::write(reg_dir.join(format!("{}.toml", name)), content).unwrap();
+        }
+        temp_dir
+    }
+
+    fn profile(packages: &[(&str, Option<&str>)]) -> Profile {
+        Profile {
+            name: "dev".to_string(),
+            origin: None,
+            packages: packages
+                .iter()
+                .map(|(name, version)| PackageRef {
+                    name: name.to_string(),
+                    version: version.map(String::from),
+                    ..Default::default()
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    #[serial]
+    fn test_plan_batches_consecutive_installs() {
+        let _temp = setup_test_env();
+        let profile = profile(&[
+            ("git", None),
+            ("rustup", None),
+            ("ripgrep", Some("14.1.0")),
+            ("only-brew", None),
+        ]);
+
+        let plan = plan(&profile, "apt").unwrap();
+        assert_eq!(
+            plan.steps,
+            vec![
+                Step {
+                    installer: "apt".to_string(),
+                    packages: vec!["git".to_string(), "curl".to_string()],
+                    targets: vec!["git".to_string(), "curl".to_string()],
+                },
+                Step {
+                    installer: "custom".to_string(),
+                    packages: vec!["rustup".to_string()],
+                    targets: vec![
+                        "curl -sSf https://sh.rustup.rs | sh -s -- -y"
+                            .to_string()
+                    ],
+                },
+                Step {
+                    installer: "apt".to_string(),
+                    packages: vec!["ripgrep".to_string()],
+                    targets: vec!["ripgrep=14.1.0".to_string()],
+                },
+            ]
+        );
+        assert_eq!(plan.unmapped, vec!["only-brew"]);
+    }
+
+    #[test]
+    #[serial]
+    fn test_plan_reports_ignored_pins_and_powershell_skips_sh() {
+        let _temp = setup_test_env();
+        let mut profile = profile(&[("git", None), ("rustup", None)]);
+        profile.packages[0].installer = Some("brew".to_string());
+
+        let plan = plan(&profile, "apt").unwrap();
+        assert_eq!(
+            plan.ignored_pins,
+            vec![("git".to_string(), "brew".to_string())]
+        );
+
+        let script = render_script(
+            &profile,
+            &plan,
+            "apt",
+            &ExportOptions {
+                format: ExportFormat::Powershell,
+                installer: None,
+                image: None,
+                bundle: false,
+            },
+        )
+        .unwrap();
+        assert!(script.contains(
+            "# skipped rustup: custom command is for sh: curl -sSf https://sh.rustup.rs | sh -s -- -y\n"
+        ));
+        assert!(!script.contains("\ncurl -sSf"));
+    }
+
+    #[test]
+    #[serial]
+    fn test_render_targets() {
+        let _temp = setup_test_env();
+        let profile = profile(&[("git", None), ("curl", None)]);
+        let plan = plan(&profile, "apt").unwrap();
+        let options = |format| ExportOptions {
+            format,
+            installer: None,
+            image: None,
+            bundle: false,
+        };
+
+        let sh =
+            render_script(&profile, &plan, "apt", &options(ExportFormat::Sh))
+                .unwrap();
+        assert!(sh.starts_with("#!/bin/sh\n"));
+        assert!(sh.contains("set -e\n\nsudo apt install -y git curl\n"));
+
+        let dockerfile = render_script(
+            &profile,
+            &plan,
+            "apt",
+            &options(ExportFormat::Dockerfile),
+        )
+        .unwrap();
+        assert!(dockerfile.contains("FROM debian:12\n"));
+        assert!(dockerfile.contains(
+            "RUN apt-get update && apt-get install -y git curl && rm -rf /var/lib/apt/lists/*\n"
+        ));
+
+        let dockerfile = render_script(
+            &profile,
+            &plan,
+            "winget",
+            &options(ExportFormat::Dockerfile),
+        );
+        assert!(matches!(dockerfile, Err(Error::Unsupported(_))));
+    }
+
+    #[test]
+    #[serial]
+    fn test_render_native_formats() {
+        let _temp = setup_test_env();
+        let profile = profile(&[
+            ("git", Some("=2.43")),
+            ("ripgrep", None),
+            ("rustup", None),
+        ]);
+
+        let (mapped, unmapped) = map_packages(&profile, &["brew"]).unwrap();
+        assert_eq!(unmapped, vec!["ripgrep", "rustup"]);
+        let brewfile =
+            render_native(&profile, &mapped, &["brew"], ExportFormat::Brewfile)
+                .unwrap();
+        assert!(brewfile.ends_with("brew \"git@2.43\"\nbrew \"curl\"\n"));
+
+        let (mapped, _) = map_packages(&profile, &["apt"]).unwrap();
+        let list =
+            render_native(&profile, &mapped, &["apt"], ExportFormat::AptList)
+                .unwrap();
+        assert_eq!(list, "git=2.43\nripgrep\ncurl\n");
+
+        let (mapped, _) = map_packages(&profile, &["apt", "brew"]).unwrap();
+        let playbook = render_native(
+            &profile,
+            &mapped,
+            &["apt", "brew"],
+            ExportFormat::Ansible,
+        )
+        .unwrap();
+        let plays: serde_yaml::Value = serde_yaml::from_str(&playbook).unwrap();
+        let tasks = &plays[0]["tasks"];
+        assert_eq!(
+            tasks[0]["ansible.builtin.apt"]["name"],
+            serde_yaml::to_value(["git=2.43", "ripgrep", "curl"]).unwrap()
+        );
+        assert_eq!(tasks[0]["become"], serde_yaml::Value::Bool(true));
+        assert_eq!(
+            tasks[1]["community.general.homebrew"]["name"],
+            serde_yaml::to_value(["git@2.43", "curl"]).unwrap()
+        );
+    }
+
+    #[test]
+    #[serial]
+    fn test_render_winget_import_json() {
+        let _temp = setup_test_env();
+        fs::write(
+            registry::registry_dir().join("vscode.toml"),
+            "[packages]\nwinget = \"Microsoft.VisualStudioCode\"\n",
+        )
+        .unwrap();
+        let profile = profile(&[("vscode", Some("1.90.0")), ("git", None)]);
+
+        let (mapped, unmapped) = map_packages(&profile, &["winget"]).unwrap();
+        assert_eq!(unmapped, vec!["git"]);
+        let json =
+            render_native(&profile, &mapped, &["winget"], ExportFormat::Winget)
+                .unwrap();
+        let doc: serde_json::Value = serde_json::from_str(&json).unwrap();
+        let package = &doc["Sources"][0]["Packages"][0];
+        assert_eq!(package["PackageIdentifier"], "Microsoft.VisualStudioCode");
+        assert_eq!(package["Version"], "1.90.0");
+        assert_eq!(doc["Sources"][0]["SourceDetails"]["Name"], "winget");
+    }
+}
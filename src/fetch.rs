@@ -0,0 +1,226 @@
+use std::{
+    env, fs,
+    process::{Command, Stdio},
+};
+
+use sha2::{Digest, Sha256};
+
+use crate::error::{Error, Result};
+
+// Where a profile file comes from: a local path, an http(s) URL, or a file
+// inside a git repository written as `git+URL#path=FILE` (optionally with
+// `&ref=BRANCH_OR_TAG`).
+#[derive(Debug, Clone, PartialEq)]
+pub enum Source {
+    Path(String),
+    Http(String),
+    Git {
+        url: String,
+        path: String,
+        reference: Option<String>,
+    },
+}
+
+impl Source {
+    pub fn parse(source: &str) -> Result<Source> {
+        if let Some(rest) = source.strip_prefix("git+") {
+            let (url, fragment) = rest.split_once('#').unwrap_or((rest, ""));
+            let mut path = None;
+            let mut reference = None;
+            for param in fragment.split('&').filter(|p| !p.is_empty()) {
+                match param.split_once('=') {
+                    Some(("path", value)) => path = Some(value.to_string()),
+                    Some(("ref", value)) => reference = Some(value.to_string()),
+                    _ => {
+                        return Err(Error::Unsupported(format!(
+                            "Unknown parameter '{}' in '{}'",
+                            param, source
+                        )));
+                    }
+                }
+            }
+            let path = path.ok_or_else(|| {
+                Error::Unsupported(format!(
+                    "'{}' does not name a file; add #path=FILE",
+                    source
+                ))
+            })?;
+            return Ok(Source::Git {
+                url: url.to_string(),
+                path,
+                reference,
+            });
+        }
+        if source.starts_with("http://") || source.starts_with("https://") {
+            return Ok(Source::Http(source.to_string()));
+        }
+        Ok(Source::Path(source.to_string()))
+    }
+}
+
+fn fetch_http(url: &str) -> Result<String> {
+    log::debug!("downloading profile from {}", url);
+    ureq::get(url)
+        .set(
+            "User-Agent",
+            &format!("blazinit/{}", env!("CARGO_PKG_VERSION")),
+        )
+        .call()
+        .map_err(Error::network(url))?
+        .into_string()
+        .map_err(Error::network(url))
+}
+
+// A shallow clone into a temporary directory, removed again once the file
+// has been read.
+fn fetch_git(url: &str, path: &str, reference: Option<&str>) -> Result<String> {
+    // Removed again when it goes out of scope.
+    let dir = tempfile::Builder::new()
+        .prefix("blazinit-git-")
+        .tempdir()
+        .map_err(Error::io("Failed to create a checkout directory"))?;
+    let checkout = dir.path().join("checkout");
+
+    let mut command = Command::new("git");
+    command.args(["clone", "--quiet", "--depth", "1"]);
+    if let Some(reference) = reference {
+        command.args(["--branch", reference]);
+    }
+    log::debug!("cloning {} (ref {:?}) into {:?}", url, reference, checkout);
+    // `--` keeps a URL starting with '-' from being read as an option.
+    let output = command
+        .arg("--")
+        .arg(url)
+        .arg(&checkout)
+        .stdin(Stdio::null())
+        .output()
+        .map_err(Error::io("Failed to run git"))?;
+
+    if !output.status.success() {
+        return Err(Error::GitFailed {
+            url: url.to_string(),
+            message: String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        });
+    }
+    // The fragment and any symlinks in the repository must not lead out of
+    // the checkout.
+    let read_error =
+        || Error::io(format!("Failed to read '{}' from {}", path, url));
+    let root = checkout.canonicalize().map_err(read_error())?;
+    let file = checkout.join(path).canonicalize().map_err(read_error())?;
+    if !file.starts_with(&root) {
+        return Err(Error::InvalidData {
+            kind: "git path",
+            message: format!(
+                "'{}' points outside the repository {}",
+                path, url
+            ),
+        });
+    }
+    fs::read_to_string(&file).map_err(read_error())
+}
+
+pub fn fetch(source: &Source) -> Result<String> {
+    match source {
+        Source::Path(path) => fs::read_to_string(path)
+            .map_err(Error::io(format!("Failed to read file '{}'", path))),
+        Source::Http(url) => fetch_http(url),
+        Source::Git {
+            url,
+            path,
+            reference,
+        } => fetch_git(url, path, reference.as_deref()),
+    }
+}
+
+pub fn verify_sha256(name: &str, content: &str, expected: &str) -> Result<()> {
+    let expected = expected.trim().to_lowercase();
+    let actual = format!("{:x}", Sha256::digest(content.as_bytes()));
+    if actual != expected {
+        log::error!(
+            "checksum mismatch for '{}': expected {}, got {}",
+            name,
+            expected,
+            actual
+        );
+        return Err(Error::ChecksumMismatch {
+            asset: name.to_string(),
+            expected,
+            actual,
+        });
+    }
+    log::debug!("checksum verified for '{}'", name);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{BufRead, BufReader, Write},
+        net::TcpListener,
+        thread,
+    };
+
+    use super::*;
+
+    fn serve(body: &'static str) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        thread::spawn(move || {
+            let Ok((mut stream, _)) = listener.accept() else {
+                return;
+            };
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut line = String::new();
+            while reader.read_line(&mut line).unwrap() > 2 {
+                line.clear();
+            }
+            let _ = write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            );
+        });
+        base
+    }
+
+    #[test]
+    fn test_parse_sources() {
+        assert_eq!(
+            Source::parse("work.toml").unwrap(),
+            Source::Path("work.toml".to_string())
+        );
+        assert_eq!(
+            Source::parse("https://example.com/work.toml").unwrap(),
+            Source::Http("https://example.com/work.toml".to_string())
+        );
+        assert_eq!(
+            Source::parse(
+                "git+https://example.com/dotfiles.git#path=profiles/work.toml&ref=v2"
+            )
+            .unwrap(),
+            Source::Git {
+                url: "https://example.com/dotfiles.git".to_string(),
+                path: "profiles/work.toml".to_string(),
+                reference: Some("v2".to_string()),
+            }
+        );
+        assert!(Source::parse("git+https://example.com/dotfiles.git").is_err());
+        assert!(Source::parse("git+https://x.git#path=a&branch=b").is_err());
+    }
+
+    #[test]
+    fn test_fetch_http_and_verify() {
+        let body = "name = \"work\"\npackages = []\n";
+        let base = serve(body);
+        let source = Source::parse(&format!("{}/work.toml", base)).unwrap();
+        let content = fetch(&source).unwrap();
+        assert_eq!(content, body);
+
+        let digest = format!("{:x}", Sha256::digest(body.as_bytes()));
+        assert!(verify_sha256("work.toml", &content, &digest).is_ok());
+        let result = verify_sha256("work.toml", &content, &"0".repeat(64));
+        assert!(matches!(result, Err(Error::ChecksumMismatch { .. })));
+    }
+}
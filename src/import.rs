@@ -0,0 +1,385 @@
+use std::{collections::BTreeSet, fs};
+
+use clap::ValueEnum;
+use colored::Colorize;
+use serde::Serialize;
+
+use crate::{
+    error::{Error, Result},
+    output::{self, say},
+    profile::{self, PackageRef, Profile},
+    registry,
+    runner::CommandRunner,
+};
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ImportSource {
+    Apt,
+    Brew,
+    Pacman,
+    Dnf,
+    Winget,
+    Brewfile,
+}
+
+impl ImportSource {
+    fn installer(self) -> &'static str {
+        match self {
+            ImportSource::Apt => "apt",
+            ImportSource::Brew | ImportSource::Brewfile => "brew",
+            ImportSource::Pacman => "pacman",
+            ImportSource::Dnf => "dnf",
+            ImportSource::Winget => "winget",
+        }
+    }
+
+    // Lists the packages a user asked for, leaving out what was only pulled
+    // in as a dependency.
+    fn command(self) -> Option<&'static str> {
+        match self {
+            ImportSource::Apt => Some("apt-mark showmanual"),
+            ImportSource::Brew => Some("brew leaves"),
+            ImportSource::Pacman => Some("pacman -Qqe"),
+            ImportSource::Dnf => {
+                Some("dnf repoquery --userinstalled --queryformat '%{name}\\n'")
+            }
+            ImportSource::Winget | ImportSource::Brewfile => None,
+        }
+    }
+}
+
+// One name per line; anything after the first word (versions, comments from
+// `apt-mark`) is ignored, as is an apt `=version` suffix.
+fn parse_list(text: &str) -> Vec<String> {
+    text.lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.starts_with('#'))
+        .filter_map(|l| l.split_whitespace().next())
+        .map(|name| name.split('=').next().unwrap_or(name).to_string())
+        .collect()
+}
+
+// `brew "name"` and `cask "name"` entries; taps and options are skipped.
+fn parse_brewfile(text: &str) -> Vec<String> {
+    text.lines()
+        .map(str::trim)
+        .filter_map(|l| {
+            l.strip_prefix("brew ").or_else(|| l.strip_prefix("cask "))
+        })
+        .filter_map(|rest| rest.trim().strip_prefix('"')?.split('"').next())
+        .map(String::from)
+        .collect()
+}
+
+// The JSON written by `winget export`.
+fn parse_winget(text: &str) -> Result<Vec<String>> {
+    let doc: serde_json::Value =
+        serde_json::from_str(text).map_err(|e| Error::InvalidData {
+            kind: "winget export",
+            message: e.to_string(),
+        })?;
+    Ok(doc["Sources"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .flat_map(|source| source["Packages"].as_array().into_iter().flatten())
+        .filter_map(|p| p["PackageIdentifier"].as_str())
+        .map(String::from)
+        .collect())
+}
+
+fn run_listing(runner: &dyn CommandRunner, command: &str) -> Result<String> {
+    log::debug!("listing native packages with '{}'", command);
+    let output = runner.output(command).map_err(|e| Error::NativeList {
+        command: command.to_string(),
+        message: e.to_string(),
+    })?;
+    if !output.success() {
+        return Err(Error::NativeList {
+            command: command.to_string(),
+            message: output.stderr.trim().to_string(),
+        });
+    }
+    Ok(output.stdout)
+}
+
+pub fn native_packages(
+    source: ImportSource,
+    file: Option<&str>,
+    runner: &dyn CommandRunner,
+) -> Result<Vec<String>> {
+    let text = match (file, source.command()) {
+        (Some(file), _) => fs::read_to_string(file)
+            .map_err(Error::io(format!("Failed to read file '{}'", file)))?,
+        (None, Some(command)) => run_listing(runner, command)?,
+        (None, None) if source == ImportSource::Winget => {
+            // Removed again when it goes out of scope.
+            let dir = tempfile::Builder::new()
+                .prefix("blazinit-winget-")
+                .tempdir()
+                .map_err(Error::io("Failed to create an export directory"))?;
+            let path = dir.path().join("packages.json");
+            let command = format!(
+                "winget export -o \"{}\" --accept-source-agreements",
+                path.display()
+            );
+            run_listing(runner, &command)?;
+            fs::read_to_string(&path)
+                .map_err(Error::io("Failed to read winget export"))?
+        }
+        (None, None) => {
+            return Err(Error::Unsupported(format!(
+                "Importing from {:?} needs a file",
+                source
+            )));
+        }
+    };
+    match source {
+        ImportSource::Brewfile => Ok(parse_brewfile(&text)),
+        ImportSource::Winget => parse_winget(&text),
+        _ => Ok(parse_list(&text)),
+    }
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct MappedPackage {
+    pub native: String,
+    pub package: String,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct UnmappedPackage {
+    pub native: String,
+    pub stub: String,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct ImportReport {
+    pub profile: String,
+    pub installer: String,
+    pub packages: Vec<MappedPackage>,
+    pub unmapped: Vec<UnmappedPackage>,
+}
+
+// A registry file to start from for a package the registry does not know.
+fn stub(native: &str, installer: &str) -> String {
+    // Only winget IDs carry a `Publisher.` prefix; apt, dnf and brew names
+    // such as `python3.11` keep their dots.
+    let base = native.rsplit('/').next().unwrap_or(native);
+    let base = match installer {
+        "winget" => base.rsplit('.').next().unwrap_or(base),
+        _ => base,
+    };
+    let name: String = base
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect();
+    format!(
+        "# {}.toml\ndetect = \"command -v {}\"\n\n[packages]\n{} = \"{}\"\n",
+        name, name, installer, native
+    )
+}
+
+pub fn map_native(
+    natives: &[String],
+    installer: &str,
+) -> Result<(Vec<MappedPackage>, Vec<UnmappedPackage>)> {
+    let index = registry::native_index(installer)?;
+    let mut mapped = Vec::new();
+    let mut unmapped = Vec::new();
+    for native in natives {
+        // brew reports versioned formulae as `name@1.2`.
+        let found = index
+            .get(native)
+            .or_else(|| index.get(native.split('@').next().unwrap_or(native)));
+        match found {
+            Some(package) => mapped.push(MappedPackage {
+                native: native.clone(),
+                package: package.clone(),
+            }),
+            None => unmapped.push(UnmappedPackage {
+                native: native.clone(),
+                stub: stub(native, installer),
+            }),
+        }
+    }
+    log::info!(
+        "mapped {} of {} {} packages to the registry",
+        mapped.len(),
+        natives.len(),
+        installer
+    );
+    Ok((mapped, unmapped))
+}
+
+pub fn import_native(
+    source: ImportSource,
+    file: Option<&str>,
+    profile_name: &str,
+    runner: &dyn CommandRunner,
+) -> Result<ImportReport> {
+    if profile::profile_path(profile_name).exists() {
+        return Err(Error::ProfileExists(profile_name.to_string()));
+    }
+    let natives = native_packages(source, file, runner)?;
+    let (packages, unmapped) = map_native(&natives, source.installer())?;
+
+    let names: BTreeSet<&str> =
+        packages.iter().map(|p| p.package.as_str()).collect();
+    profile::write_profile(&Profile {
+        name: profile_name.to_string(),
+        origin: None,
+        packages: names
+            .into_iter()
+            .map(|name| PackageRef {
+                name: name.to_string(),
+                ..Default::default()
+            })
+            .collect(),
+    })?;
+
+    Ok(ImportReport {
+        profile: profile_name.to_string(),
+        installer: source.installer().to_string(),
+        packages,
+        unmapped,
+    })
+}
+
+pub fn show_import(report: &ImportReport) -> Result<()> {
+    if output::is_structured() {
+        return output::emit(report);
+    }
+    say!(
+        "{} '{}' with {} packages from {}.",
+        "Profile imported successfully:".green(),
+        report.profile.cyan(),
+        report.packages.len(),
+        report.installer
+    );
+    if report.unmapped.is_empty() {
+        return Ok(());
+    }
+    say!(
+        "\n{} {} packages are not in the registry. Suggested registry files:\n",
+        "Unmapped:".yellow().bold(),
+        report.unmapped.len()
+    );
+    for unmapped in &report.unmapped {
+        say!("{}", unmapped.stub.dimmed());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use serial_test::serial;
+    use tempfile::TempDir;
+
+    use super::*;
+    use crate::runner::FakeRunner;
+
+    fn setup_test_env() -> TempDir {
+        let temp_dir = TempDir::new().expect("Failed to create temp dir");
+        unsafe {
+            env::set_var("HOME", temp_dir.path());
+            env::set_var("XDG_CONFIG_HOME", temp_dir.path().join(".config"));
+        }
+        let reg_dir = registry::registry_dir();
+        fs::create_dir_all(&reg_dir).unwrap();
+        fs::create_dir_all(crate::config::profiles_dir()).unwrap();
+        for (name, content) in [
+            ("git", "[packages]\napt = \"git\"\nbrew = \"git\"\n"),
+            (
+                "ripgrep",
+                "[packages]\napt = \"ripgrep\"\nbrew = \"ripgrep\"\n",
+            ),
+            ("node", "[packages]\nbrew = \"node\"\n"),
+        ] {
+            fs::write(reg_dir.join(format!("{}.toml", name)), content).unwrap();
+        }
+        temp_dir
+    }
+
+    #[test]
+    fn test_parsers() {
+        assert_eq!(
+            parse_list("git\nripgrep=14.1.0-1\n\n# comment\nhtop extra\n"),
+            vec!["git", "ripgrep", "htop"]
+        );
+        assert_eq!(
+            parse_brewfile(
+                "tap \"homebrew/cask\"\nbrew \"git\"\nbrew \"node@20\", link: true\ncask \"firefox\"\n"
+            ),
+            vec!["git", "node@20", "firefox"]
+        );
+        let winget =
+            r#"{"Sources":[{"Packages":[{"PackageIdentifier":"Git.Git"}]}]}"#;
+        assert_eq!(parse_winget(winget).unwrap(), vec!["Git.Git"]);
+        assert!(matches!(
+            parse_winget("not json"),
+            Err(Error::InvalidData { .. })
+        ));
+    }
+
+    #[test]
+    #[serial]
+    fn test_import_native_maps_and_reports_unmapped() {
+        let _temp = setup_test_env();
+        let runner =
+            FakeRunner::new().respond("brew leaves", 0, "git\nnode@20\nhtop\n");
+
+        let report =
+            import_native(ImportSource::Brew, None, "laptop", &runner).unwrap();
+        assert_eq!(
+            report.packages,
+            vec![
+                MappedPackage {
+                    native: "git".to_string(),
+                    package: "git".to_string(),
+                },
+                MappedPackage {
+                    native: "node@20".to_string(),
+                    package: "node".to_string(),
+                },
+            ]
+        );
+        assert_eq!(report.unmapped.len(), 1);
+        assert_eq!(
+            report.unmapped[0].stub,
+            "# htop.toml\ndetect = \"command -v htop\"\n\n[packages]\nbrew = \"htop\"\n"
+        );
+
+        let profile = profile::read_profile("laptop").unwrap();
+        let names: Vec<_> = profile.packages.iter().map(|p| &p.name).collect();
+        assert_eq!(names, vec!["git", "node"]);
+
+        let again = import_native(ImportSource::Brew, None, "laptop", &runner);
+        assert!(matches!(again, Err(Error::ProfileExists(_))));
+    }
+
+    #[test]
+    fn test_stub_strips_publisher_only_for_winget() {
+        assert!(stub("python3.11", "apt").starts_with(
+            "# python3-11.toml\ndetect = \"command -v python3-11\""
+        ));
+        assert!(stub("Git.Git", "winget").starts_with("# git.toml\n"));
+        assert!(
+            stub("homebrew/cask/firefox", "brew")
+                .starts_with("# firefox.toml\n")
+        );
+    }
+
+    #[test]
+    #[serial]
+    fn test_import_native_listing_failure() {
+        let _temp = setup_test_env();
+        let runner = FakeRunner::new();
+        let result = import_native(ImportSource::Apt, None, "box", &runner);
+        assert!(matches!(result, Err(Error::NativeList { .. })));
+        assert!(!profile::profile_path("box").exists());
+    }
+}
@@ -1,36 +1,82 @@
-use std::{
-    collections::{HashMap, HashSet},
-    process::Command,
-};
+use std::collections::{HashMap, HashSet};
 
-use colored::Colorize;
+use serde::{Deserialize, Serialize};
 
-use crate::profile::{PackageRef, Profile, ProfilePackage};
+use crate::{
+    error::{self, Error, Result},
+    lock::Lockfile,
+    profile::{PackageRef, Profile, ProfilePackage},
+    reporter::{Event, Reporter},
+    runner::CommandRunner,
+    version::{VersionReq, extract_version},
+};
 
-const INSTALLER_PRIORITY: &[&str] =
+pub const INSTALLER_PRIORITY: &[&str] =
     &["apt", "dnf", "yum", "pacman", "brew", "winget"];
 
-fn installer_command(installer: &str, pkg_value: &str) -> String {
+// The package argument for `installer`, with the version spelled the way
+// that installer expects it.
+pub(crate) fn install_target(
+    installer: &str,
+    pkg_value: &str,
+    version: Option<&str>,
+) -> String {
+    match (installer, version) {
+        (_, None) => pkg_value.to_string(),
+        ("apt", Some(v)) => format!("{}={}", pkg_value, v),
+        ("dnf" | "yum", Some(v)) => format!("{}-{}", pkg_value, v),
+        ("brew", Some(v)) => format!("{}@{}", pkg_value, v),
+        ("winget", Some(v)) => format!("{} --version {}", pkg_value, v),
+        (_, Some(v)) => {
+            if installer == "pacman" {
+                log::warn!(
+                    "pacman cannot install a specific version of '{}'",
+                    pkg_value
+                );
+            }
+            pkg_value.replace("{version}", v)
+        }
+    }
+}
+
+// The command a target is appended to; `None` for custom commands, which are
+// complete on their own.
+pub(crate) fn install_prefix(installer: &str) -> Option<&'static str> {
     match installer {
-        "apt" => format!("sudo apt install -y {}", pkg_value),
-        "dnf" => format!("sudo dnf install -y {}", pkg_value),
-        "yum" => format!("sudo yum install -y {}", pkg_value),
-        "pacman" => format!("sudo pacman -S --noconfirm {}", pkg_value),
-        "brew" => format!("brew install {}", pkg_value),
-        "winget" => format!("winget install {}", pkg_value),
-        _ => pkg_value.to_string(),
+        "apt" => Some("sudo apt install -y"),
+        "dnf" => Some("sudo dnf install -y"),
+        "yum" => Some("sudo yum install -y"),
+        "pacman" => Some("sudo pacman -S --noconfirm"),
+        "brew" => Some("brew install"),
+        "winget" => Some("winget install"),
+        _ => None,
+    }
+}
+
+fn installer_command(
+    installer: &str,
+    pkg_value: &str,
+    version: Option<&str>,
+) -> String {
+    let target = install_target(installer, pkg_value, version);
+    match install_prefix(installer) {
+        Some(prefix) => format!("{} {}", prefix, target),
+        None => target,
     }
 }
 
-pub fn detect_available_installer() -> Option<String> {
-    let check_cmd = if cfg!(windows) { "where" } else { "which" };
+fn installer_value(pkg: &ProfilePackage, installer: &str) -> Option<String> {
+    if installer == "release" {
+        return pkg.release.as_ref().map(|r| r.asset.clone());
+    }
+    pkg.installers.get(installer).cloned()
+}
+
+pub fn detect_available_installer(
+    runner: &dyn CommandRunner,
+) -> Option<String> {
     for &installer in INSTALLER_PRIORITY {
-        let ok = Command::new(check_cmd)
-            .arg(installer)
-            .output()
-            .map(|o| o.status.success())
-            .unwrap_or(false);
-        if ok {
+        if runner.is_available(installer) {
             log::debug!("detected system installer: {}", installer);
             return Some(installer.to_string());
         }
@@ -39,20 +85,36 @@ pub fn detect_available_installer() -> Option<String> {
     None
 }
 
+// The installer `select_installer` reaches for first when a package offers
+// it: the CLI flag, then the configured preference, then the first system
+// installer found.
+pub fn default_installer(
+    installer_flag: &Option<String>,
+    runner: &dyn CommandRunner,
+) -> Option<String> {
+    installer_flag
+        .clone()
+        .or_else(crate::config::get_preferred_installer)
+        .or_else(|| detect_available_installer(runner))
+}
+
+// `detected` is the system installer found on this run's host; probing for it
+// once per run keeps this from probing for it for every package.
 pub fn select_installer(
     pkg: &ProfilePackage,
     installer_flag: &Option<String>,
-) -> Result<(String, String), String> {
+    detected: Option<&str>,
+) -> Result<(String, String)> {
     // 1. CLI flag
     if let Some(name) = installer_flag {
-        if let Some(value) = pkg.installers.get(name.as_str()) {
+        if let Some(value) = installer_value(pkg, name) {
             log::debug!(
                 "installer for '{}': cli flag '{}' -> '{}'",
                 pkg.name,
                 name,
                 value
             );
-            return Ok((name.clone(), value.clone()));
+            return Ok((name.clone(), value));
         }
         if name == "custom"
             && let Some(cmd) = pkg.installers.get("custom")
@@ -69,15 +131,15 @@ pub fn select_installer(
             name,
             pkg.name
         );
-        return Err(format!(
-            "Installer '{}' not available for package '{}'",
-            name, pkg.name
-        ));
+        return Err(Error::InstallerUnavailable {
+            installer: name.clone(),
+            package: pkg.name.clone(),
+        });
     }
 
     // 2. Config preferred installer
     if let Some(name) = crate::config::get_preferred_installer()
-        && let Some(value) = pkg.installers.get(name.as_str())
+        && let Some(value) = installer_value(pkg, &name)
     {
         log::debug!(
             "installer for '{}': config preferred '{}' -> '{}'",
@@ -85,12 +147,12 @@ pub fn select_installer(
             name,
             value
         );
-        return Ok((name, value.clone()));
+        return Ok((name, value));
     }
 
-    // 3. Auto-detect first available binary
-    if let Some(detected) = detect_available_installer()
-        && let Some(value) = pkg.installers.get(detected.as_str())
+    // 3. Auto-detected system installer
+    if let Some(detected) = detected
+        && let Some(value) = pkg.installers.get(detected)
     {
         log::debug!(
             "installer for '{}': auto-detected '{}' -> '{}'",
@@ -98,10 +160,20 @@ pub fn select_installer(
             detected,
             value
         );
-        return Ok((detected, value.clone()));
+        return Ok((detected.to_string(), value.clone()));
+    }
+
+    // 4. Fall back to a release download
+    if let Some(spec) = &pkg.release {
+        log::debug!(
+            "installer for '{}': fallback release -> '{}'",
+            pkg.name,
+            spec.asset
+        );
+        return Ok(("release".to_string(), spec.asset.clone()));
     }
 
-    // 4. Fall back to custom
+    // 5. Fall back to custom
     if let Some(cmd) = pkg.installers.get("custom") {
         log::debug!(
             "installer for '{}': fallback custom -> '{}'",
@@ -112,25 +184,30 @@ pub fn select_installer(
     }
 
     log::error!("no suitable installer found for package '{}'", pkg.name);
-    Err(format!(
-        "No suitable installer found for package '{}'",
-        pkg.name
-    ))
+    Err(Error::NoInstaller(pkg.name.clone()))
 }
 
-pub fn is_installed(pkg: &ProfilePackage) -> bool {
+pub fn is_installed(pkg: &ProfilePackage, runner: &dyn CommandRunner) -> bool {
     let Some(detect_cmd) = &pkg.detect else {
+        if pkg.release.is_some() && runner.is_local() {
+            let installed =
+                crate::release::installed_release(&pkg.name).is_some();
+            log::debug!(
+                "'{}': no detect command, release recorded={}",
+                pkg.name,
+                installed
+            );
+            return installed;
+        }
         log::debug!(
             "'{}': no detect command, assuming not installed",
             pkg.name
         );
         return false;
     };
-    let result = Command::new("sh")
-        .arg("-c")
-        .arg(detect_cmd)
-        .output()
-        .map(|o| o.status.success())
+    let result = runner
+        .output(detect_cmd)
+        .map(|o| o.success())
         .unwrap_or(false);
     log::debug!(
         "'{}': detect='{}' -> installed={}",
@@ -141,7 +218,33 @@ pub fn is_installed(pkg: &ProfilePackage) -> bool {
     result
 }
 
-fn topological_sort(packages: &[PackageRef]) -> Result<Vec<String>, String> {
+pub fn detect_version(
+    pkg: &ProfilePackage,
+    runner: &dyn CommandRunner,
+) -> Option<String> {
+    if pkg.version_command.is_none()
+        && runner.is_local()
+        && let Some(installed) = crate::release::installed_release(&pkg.name)
+    {
+        return Some(installed.version);
+    }
+    let cmd = pkg.version_command.as_ref().or(pkg.detect.as_ref())?;
+    let output = runner.output(cmd).ok()?;
+    if !output.success() {
+        log::debug!("'{}': version command '{}' failed", pkg.name, cmd);
+        return None;
+    }
+    let text = format!("{}{}", output.stdout, output.stderr);
+    let version = extract_version(&text, pkg.version_regex.as_deref())
+        .unwrap_or_else(|e| {
+            log::warn!("'{}': {}", pkg.name, e);
+            None
+        });
+    log::debug!("'{}': version command '{}' -> {:?}", pkg.name, cmd, version);
+    version
+}
+
+pub(crate) fn topological_sort(packages: &[PackageRef]) -> Result<Vec<String>> {
     let mut visited: HashSet<String> = HashSet::new();
     let mut in_stack: HashSet<String> = HashSet::new();
     let mut order: Vec<String> = Vec::new();
@@ -151,12 +254,9 @@ fn topological_sort(packages: &[PackageRef]) -> Result<Vec<String>, String> {
         visited: &mut HashSet<String>,
         in_stack: &mut HashSet<String>,
         order: &mut Vec<String>,
-    ) -> Result<(), String> {
+    ) -> Result<()> {
         if in_stack.contains(name) {
-            return Err(format!(
-                "Circular dependency detected involving '{}'",
-                name
-            ));
+            return Err(Error::DependencyCycle(name.to_string()));
         }
         if visited.contains(name) {
             return Ok(());
@@ -184,25 +284,133 @@ fn topological_sort(packages: &[PackageRef]) -> Result<Vec<String>, String> {
     Ok(order)
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum InstallOutcome {
+    Installed,
+    WouldInstall,
+    Skipped,
+    Failed,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PackageOutcome {
+    pub name: String,
+    pub outcome: InstallOutcome,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub installer: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl PackageOutcome {
+    fn new(name: &str, outcome: InstallOutcome) -> Self {
+        PackageOutcome {
+            name: name.to_string(),
+            outcome,
+            installer: None,
+            command: None,
+            version: None,
+            error: None,
+        }
+    }
+
+    fn failed(name: &str, error: &Error) -> Self {
+        PackageOutcome {
+            error: Some(error::chain(error)),
+            ..PackageOutcome::new(name, InstallOutcome::Failed)
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct InstallReport {
+    pub profile: String,
+    pub dry_run: bool,
+    pub packages: Vec<PackageOutcome>,
+    pub installed: usize,
+    pub skipped: usize,
+    pub failed: usize,
+    // Packages outside the selection that were installed as dependencies.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub dependencies: Vec<String>,
+}
+
+impl InstallReport {
+    fn new(profile: &str, dry_run: bool) -> Self {
+        InstallReport {
+            profile: profile.to_string(),
+            dry_run,
+            packages: Vec::new(),
+            installed: 0,
+            skipped: 0,
+            failed: 0,
+            dependencies: Vec::new(),
+        }
+    }
+
+    fn push(&mut self, outcome: PackageOutcome) {
+        match outcome.outcome {
+            InstallOutcome::Installed | InstallOutcome::WouldInstall => {
+                self.installed += 1
+            }
+            InstallOutcome::Skipped => self.skipped += 1,
+            InstallOutcome::Failed => self.failed += 1,
+        }
+        self.packages.push(outcome);
+    }
+}
+
+// An installer only gets an exact version to install; for a range it
+// installs whatever it provides, which may still miss the requirement.
+fn check_installed(
+    name: &str,
+    requirement: Option<&VersionReq>,
+    installed: Option<String>,
+) -> Result<Option<String>> {
+    match (requirement, installed) {
+        (Some(req), Some(found)) if !req.matches(&found) => {
+            Err(Error::VersionUnsatisfied {
+                package: name.to_string(),
+                installed: found,
+                wanted: req.to_string(),
+            })
+        }
+        (_, found) => Ok(found),
+    }
+}
+
 pub fn run_install(
     profile: &Profile,
     force: bool,
     cli_installer: &Option<String>,
-    dry_run: bool,
-) -> Result<(), String> {
+    detected: Option<&str>,
+    lock: Option<&Lockfile>,
+    runner: &dyn CommandRunner,
+    reporter: &dyn Reporter,
+) -> Result<InstallReport> {
+    let dry_run = runner.is_dry_run();
     log::info!(
-        "run_install: profile='{}', packages={}, force={}, dry_run={}, cli_installer={:?}",
+        "run_install: profile='{}', packages={}, force={}, dry_run={}, cli_installer={:?}, detected={:?}, locked={}",
         profile.name,
         profile.packages.len(),
         force,
         dry_run,
-        cli_installer
+        cli_installer,
+        detected,
+        lock.is_some()
     );
 
+    let mut report = InstallReport::new(&profile.name, dry_run);
+
     if profile.packages.is_empty() {
         log::info!("profile '{}' has no packages to install", profile.name);
-        println!("{}", "No packages to install.".yellow());
-        return Ok(());
+        reporter.report(&Event::NothingToInstall);
+        return Ok(report);
     }
 
     let pkg_ref_map: HashMap<&str, &PackageRef> = profile
@@ -213,137 +421,213 @@ pub fn run_install(
 
     let ordered_names = topological_sort(&profile.packages)?;
 
-    let mut installed_count = 0usize;
-    let mut skipped_count = 0usize;
-    let mut failed_count = 0usize;
-
     for name in &ordered_names {
         let pkg = match crate::registry::get_package_details(name) {
             Ok(p) => p,
             Err(e) => {
                 log::error!("failed to get details for '{}': {}", name, e);
-                eprintln!("{} {}: {}", "[fail]".red().bold(), name.cyan(), e);
-                failed_count += 1;
+                reporter.report(&Event::Failed {
+                    display: name,
+                    error: &e,
+                });
+                report.push(PackageOutcome::failed(name, &e));
                 continue;
             }
         };
 
         let display = pkg.display.as_deref().unwrap_or(&pkg.name);
+        let pkg_ref = pkg_ref_map.get(name.as_str()).copied();
+        let locked = lock.and_then(|l| l.get(name));
+
+        // A locked package is held to exactly the version recorded in the
+        // lock file rather than the profile's looser requirement.
+        let requirement = match locked
+            .and_then(|l| l.version.as_ref())
+            .map(|v| format!("={}", v))
+            .or_else(|| pkg_ref.and_then(|r| r.version.clone()))
+            .map(|v| VersionReq::parse(&v))
+            .transpose()
+        {
+            Ok(req) => req,
+            Err(e) => {
+                log::error!("invalid version for '{}': {}", name, e);
+                reporter.report(&Event::Failed { display, error: &e });
+                report.push(PackageOutcome::failed(name, &e));
+                continue;
+            }
+        };
 
-        if !force && is_installed(&pkg) {
-            log::debug!("'{}' already installed, skipping", name);
-            println!(
-                "{} {} — already installed",
-                "[skip]".yellow().bold(),
-                display.cyan()
-            );
-            skipped_count += 1;
-            continue;
+        if !force && is_installed(&pkg, runner) {
+            let found = requirement
+                .as_ref()
+                .and_then(|_| detect_version(&pkg, runner));
+            match (requirement.as_ref(), found) {
+                (Some(req), Some(found)) if !req.matches(&found) => {
+                    log::info!(
+                        "'{}' {} does not satisfy '{}', reinstalling",
+                        name,
+                        found,
+                        req
+                    );
+                    reporter.report(&Event::Outdated {
+                        display,
+                        installed: &found,
+                        wanted: &req.to_string(),
+                    });
+                }
+                (_, found) => {
+                    log::debug!("'{}' already installed, skipping", name);
+                    reporter.report(&Event::Skipped { display });
+                    report.push(PackageOutcome {
+                        version: found,
+                        ..PackageOutcome::new(name, InstallOutcome::Skipped)
+                    });
+                    continue;
+                }
+            }
         }
 
+        let pinned = requirement.as_ref().and_then(|r| r.install_version());
+
         // Priority: CLI flag > per-package profile override > config >
         // auto-detect > custom
-        let pkg_installer = pkg_ref_map
-            .get(name.as_str())
-            .and_then(|r| r.installer.as_ref())
-            .cloned();
+        let pkg_installer = pkg_ref.and_then(|r| r.installer.clone());
         let effective_installer = cli_installer.clone().or(pkg_installer);
 
-        let (installer_name, install_value) =
-            match select_installer(&pkg, &effective_installer) {
-                Ok(pair) => pair,
-                Err(e) => {
-                    log::error!("no installer for '{}': {}", name, e);
-                    eprintln!(
-                        "{} {}: {}",
-                        "[fail]".red().bold(),
-                        display.cyan(),
-                        e
-                    );
-                    failed_count += 1;
-                    continue;
-                }
-            };
+        let selected = match locked {
+            Some(l) => Ok((l.installer.clone(), l.value.clone())),
+            None => select_installer(&pkg, &effective_installer, detected),
+        };
+        let (installer_name, install_value) = match selected {
+            Ok(pair) => pair,
+            Err(e) => {
+                log::error!("no installer for '{}': {}", name, e);
+                reporter.report(&Event::Failed { display, error: &e });
+                report.push(PackageOutcome::failed(name, &e));
+                continue;
+            }
+        };
 
-        let cmd_str = if installer_name == "custom" {
-            install_value.clone()
-        } else {
-            installer_command(&installer_name, &install_value)
+        let release =
+            pkg.release.as_ref().filter(|_| installer_name == "release");
+
+        let cmd_str = match release {
+            Some(spec) => crate::release::describe(spec, pinned.as_deref()),
+            None => installer_command(
+                &installer_name,
+                &install_value,
+                pinned.as_deref(),
+            ),
+        };
+
+        let mut outcome = PackageOutcome {
+            installer: Some(installer_name.clone()),
+            command: Some(cmd_str.clone()),
+            version: pinned.clone(),
+            ..PackageOutcome::new(name, InstallOutcome::Installed)
         };
 
         if dry_run {
             log::debug!("dry-run '{}': would run: {}", name, cmd_str);
-            println!(
-                "{} {} — would run: {}",
-                "[dry-run]".cyan().bold(),
-                display.cyan(),
-                cmd_str.dimmed()
+            reporter.report(&Event::WouldRun {
+                display,
+                command: &cmd_str,
+            });
+            outcome.outcome = InstallOutcome::WouldInstall;
+        } else {
+            log::info!(
+                "installing '{}' via {}: {}",
+                name,
+                installer_name,
+                cmd_str
             );
-            installed_count += 1;
-            continue;
+            reporter.report(&Event::Installing {
+                display,
+                command: &cmd_str,
+            });
         }
 
-        log::info!("installing '{}' via {}: {}", name, installer_name, cmd_str);
-        println!(
-            "{} {} — {}",
-            "[install]".blue().bold(),
-            display.cyan(),
-            cmd_str.dimmed()
-        );
-
-        let status = Command::new("sh").arg("-c").arg(&cmd_str).status();
+        // Release downloads don't go through the runner, so a dry run stops
+        // short of them.
+        if let Some(spec) = release.filter(|_| !dry_run) {
+            let result = if runner.is_local() {
+                crate::release::install(name, spec, pinned.as_deref())
+            } else {
+                Err(Error::Unsupported(format!(
+                    "Release downloads of '{}' are only supported locally",
+                    name
+                )))
+            };
+            let result = result.and_then(|version| {
+                check_installed(name, requirement.as_ref(), Some(version))
+                    .map(|v| v.unwrap_or_default())
+            });
+            match result {
+                Ok(version) => {
+                    log::info!("'{}' {} installed successfully", name, version);
+                    reporter.report(&Event::Installed {
+                        display,
+                        version: Some(&version),
+                    });
+                    outcome.version = Some(version);
+                }
+                Err(e) => {
+                    log::error!("'{}' release install failed: {}", name, e);
+                    reporter.report(&Event::Failed { display, error: &e });
+                    outcome.outcome = InstallOutcome::Failed;
+                    outcome.error = Some(error::chain(&e));
+                }
+            }
+            report.push(outcome);
+            continue;
+        }
 
-        match status {
-            Ok(s) if s.success() => {
-                log::info!("'{}' installed successfully", name);
-                println!("{} {}", "[ok]".green().bold(), display.cyan());
-                installed_count += 1;
+        let result = match runner.run(&cmd_str) {
+            Ok(output) if output.success() => Ok(()),
+            Ok(output) => Err(Error::InstallFailed {
+                package: name.clone(),
+                code: output.code,
+            }),
+            Err(e) => Err(Error::io("Failed to run install command")(e)),
+        };
+        let result = result.and_then(|()| match &requirement {
+            Some(req) if !dry_run => {
+                check_installed(name, Some(req), detect_version(&pkg, runner))
             }
-            Ok(s) => {
-                log::error!("'{}' install failed: exit status {}", name, s);
-                eprintln!(
-                    "{} {} — exited with status {}",
-                    "[fail]".red().bold(),
-                    display.cyan(),
-                    s
-                );
-                failed_count += 1;
+            _ => Ok(None),
+        });
+        match result {
+            Ok(_) if dry_run => {}
+            Ok(found) => {
+                log::info!("'{}' installed successfully", name);
+                reporter.report(&Event::Installed {
+                    display,
+                    version: found.as_deref(),
+                });
+                if found.is_some() {
+                    outcome.version = found;
+                }
             }
             Err(e) => {
-                log::error!("'{}' install command error: {}", name, e);
-                eprintln!(
-                    "{} {} — {}",
-                    "[fail]".red().bold(),
-                    display.cyan(),
-                    e
-                );
-                failed_count += 1;
+                log::error!("'{}' install failed: {}", name, e);
+                reporter.report(&Event::Failed { display, error: &e });
+                outcome.outcome = InstallOutcome::Failed;
+                outcome.error = Some(error::chain(&e));
             }
         }
+        report.push(outcome);
     }
 
-    let installed_label = if dry_run {
-        "would install"
-    } else {
-        "installed"
-    };
     log::info!(
-        "install complete: {} {}, {} skipped, {} failed",
-        installed_count,
-        installed_label,
-        skipped_count,
-        failed_count
-    );
-    println!(
-        "\n{} {} {}  {} skipped  {} failed",
-        "Summary:".bold(),
-        installed_count.to_string().green().bold(),
-        installed_label,
-        skipped_count.to_string().yellow().bold(),
-        failed_count.to_string().red().bold()
+        "install complete: {} installed, {} skipped, {} failed (dry_run={})",
+        report.installed,
+        report.skipped,
+        report.failed,
+        dry_run
     );
+    reporter.report(&Event::Finished { report: &report });
 
-    Ok(())
+    Ok(report)
 }
 
 #[cfg(test)]
@@ -351,11 +635,15 @@ mod tests {
     use std::collections::HashMap;
 
     use super::*;
+    use crate::{
+        reporter::SilentReporter,
+        runner::{FakeRunner, SystemRunner},
+    };
 
     fn make_pkg_ref(name: &str) -> PackageRef {
         PackageRef {
             name: name.to_string(),
-            installer: None,
+            ..Default::default()
         }
     }
 
@@ -372,6 +660,9 @@ mod tests {
                 .collect(),
             detect: None,
             dependencies: vec![],
+            release: None,
+            version_command: None,
+            version_regex: None,
         }
     }
 
@@ -382,6 +673,9 @@ mod tests {
             installers: HashMap::new(),
             detect: detect.map(String::from),
             dependencies: vec![],
+            release: None,
+            version_command: None,
+            version_regex: None,
         }
     }
 
@@ -415,7 +709,7 @@ mod tests {
     fn test_select_installer_flag_override() {
         let pkg =
             make_pkg_with_installers("git", &[("apt", "git"), ("brew", "git")]);
-        let result = select_installer(&pkg, &Some("apt".to_string()));
+        let result = select_installer(&pkg, &Some("apt".to_string()), None);
         assert!(result.is_ok());
         let (name, _) = result.unwrap();
         assert_eq!(name, "apt");
@@ -424,9 +718,8 @@ mod tests {
     #[test]
     fn test_select_installer_flag_not_available() {
         let pkg = make_pkg_with_installers("git", &[("apt", "git")]);
-        let result = select_installer(&pkg, &Some("brew".to_string()));
-        assert!(result.is_err());
-        assert!(result.err().unwrap().contains("not available"));
+        let result = select_installer(&pkg, &Some("brew".to_string()), None);
+        assert!(matches!(result, Err(Error::InstallerUnavailable { .. })));
     }
 
     #[test]
@@ -435,35 +728,262 @@ mod tests {
             "mypkg",
             &[("custom", "install-mypkg.sh")],
         );
-        let result = select_installer(&pkg, &None);
+        let result = select_installer(&pkg, &None, None);
         assert!(result.is_ok());
         let (name, _) = result.unwrap();
         assert_eq!(name, "custom");
     }
 
+    #[test]
+    fn test_select_installer_release_fallback() {
+        let mut pkg = make_pkg_resolved("tool", None);
+        pkg.release = Some(crate::release::ReleaseSpec {
+            api: "https://api.github.com/repos/acme/tool/releases".into(),
+            asset: "tool-{os}-{arch}".into(),
+            checksum: None,
+            bin: None,
+            version: None,
+            tag: None,
+        });
+        let (name, value) = select_installer(&pkg, &None, None).unwrap();
+        assert_eq!(name, "release");
+        assert_eq!(value, "tool-{os}-{arch}");
+
+        let (name, _) =
+            select_installer(&pkg, &Some("release".to_string()), None).unwrap();
+        assert_eq!(name, "release");
+    }
+
     #[test]
     fn test_select_installer_no_installer_available() {
         let pkg = make_pkg_resolved("mypkg", None);
-        let result = select_installer(&pkg, &None);
-        assert!(result.is_err());
-        assert!(result.err().unwrap().contains("No suitable installer"));
+        let result = select_installer(&pkg, &None, None);
+        assert!(matches!(result, Err(Error::NoInstaller(_))));
+    }
+
+    #[test]
+    #[serial_test::serial]
+    fn test_default_installer_follows_config_preference() {
+        let temp = tempfile::TempDir::new().unwrap();
+        unsafe {
+            std::env::set_var("HOME", temp.path());
+            std::env::set_var("XDG_CONFIG_HOME", temp.path().join(".config"));
+        }
+        let runner = FakeRunner::new().with_program("apt").with_program("brew");
+        assert_eq!(default_installer(&None, &runner), Some("apt".to_string()));
+
+        let config_dir = crate::config::config_dir();
+        std::fs::create_dir_all(&config_dir).unwrap();
+        std::fs::write(
+            config_dir.join("config.toml"),
+            "default_profile = \"default\"\npreferred_installer = \"brew\"\n",
+        )
+        .unwrap();
+        assert_eq!(default_installer(&None, &runner), Some("brew".to_string()));
+        assert_eq!(
+            default_installer(&Some("cargo".to_string()), &runner),
+            Some("cargo".to_string())
+        );
+    }
+
+    #[test]
+    fn test_installer_command_with_version() {
+        assert_eq!(
+            installer_command("apt", "terraform", Some("1.5.7")),
+            "sudo apt install -y terraform=1.5.7"
+        );
+        assert_eq!(
+            installer_command("brew", "terraform", Some("1.5")),
+            "brew install terraform@1.5"
+        );
+        assert_eq!(
+            installer_command("dnf", "terraform", Some("1.5.7")),
+            "sudo dnf install -y terraform-1.5.7"
+        );
+        assert_eq!(
+            installer_command("custom", "get.sh --v {version}", Some("2.0")),
+            "get.sh --v 2.0"
+        );
+        assert_eq!(
+            installer_command("apt", "terraform", None),
+            "sudo apt install -y terraform"
+        );
+    }
+
+    #[test]
+    fn test_detect_version_from_version_command() {
+        let mut pkg = make_pkg_resolved("tool", Some("true"));
+        pkg.version_command = Some("echo tool v1.5.7".to_string());
+        pkg.version_regex = Some(r"v(\d+\.\d+\.\d+)".to_string());
+        assert_eq!(
+            detect_version(&pkg, &SystemRunner),
+            Some("1.5.7".to_string())
+        );
+    }
+
+    #[test]
+    fn test_detect_version_falls_back_to_detect() {
+        let pkg = make_pkg_resolved("tool", Some("echo 'tool version 2.39.2'"));
+        assert_eq!(
+            detect_version(&pkg, &SystemRunner),
+            Some("2.39.2".to_string())
+        );
+
+        let pkg = make_pkg_resolved("tool", Some("false"));
+        assert_eq!(detect_version(&pkg, &SystemRunner), None);
     }
 
     #[test]
     fn test_is_installed_no_detect() {
         let pkg = make_pkg_resolved("mypkg", None);
-        assert!(!is_installed(&pkg));
+        assert!(!is_installed(&pkg, &SystemRunner));
     }
 
     #[test]
     fn test_is_installed_true_command() {
         let pkg = make_pkg_resolved("mypkg", Some("true"));
-        assert!(is_installed(&pkg));
+        assert!(is_installed(&pkg, &SystemRunner));
     }
 
     #[test]
     fn test_is_installed_false_command() {
         let pkg = make_pkg_resolved("mypkg", Some("false"));
-        assert!(!is_installed(&pkg));
+        assert!(!is_installed(&pkg, &SystemRunner));
+    }
+
+    struct Recorder(std::cell::RefCell<Vec<String>>);
+
+    impl Reporter for Recorder {
+        fn report(&self, event: &Event) {
+            let line = match event {
+                Event::Skipped { display } => format!("skip {}", display),
+                Event::Installing { display, .. } => {
+                    format!("install {}", display)
+                }
+                Event::Installed { display, .. } => format!("ok {}", display),
+                Event::Failed { display, .. } => format!("fail {}", display),
+                Event::Finished { .. } => "finished".to_string(),
+                other => format!("{:?}", other),
+            };
+            self.0.borrow_mut().push(line);
+        }
+    }
+
+    #[test]
+    #[serial_test::serial]
+    fn test_run_install_reports_events_and_outcomes() {
+        let temp = tempfile::TempDir::new().unwrap();
+        unsafe {
+            std::env::set_var("HOME", temp.path());
+            std::env::set_var("XDG_CONFIG_HOME", temp.path().join(".config"));
+        }
+        let reg_dir = crate::config::config_dir().join("registry");
+        std::fs::create_dir_all(&reg_dir).unwrap();
+        for (name, detect, custom) in [
+            ("present", "true", "true"),
+            ("works", "false", "true"),
+            ("breaks", "false", "false"),
+        ] {
+            std::fs::write(
+                reg_dir.join(format!("{}.toml", name)),
+                format!(
+                    "detect = \"{}\"\n\n[packages]\ncustom = \"{}\"\n",
+                    detect, custom
+                ),
+            )
+            .unwrap();
+        }
+
+        let profile = Profile {
+            name: "events".to_string(),
+            origin: None,
+            packages: vec![
+                make_pkg_ref("present"),
+                make_pkg_ref("works"),
+                make_pkg_ref("breaks"),
+            ],
+        };
+        let recorder = Recorder(Default::default());
+        let report = run_install(
+            &profile,
+            false,
+            &None,
+            None,
+            None,
+            &SystemRunner,
+            &recorder,
+        )
+        .unwrap();
+
+        assert_eq!(
+            *recorder.0.borrow(),
+            vec![
+                "skip present",
+                "install works",
+                "ok works",
+                "install breaks",
+                "fail breaks",
+                "finished",
+            ]
+        );
+        assert_eq!(
+            (report.installed, report.skipped, report.failed),
+            (1, 1, 1)
+        );
+        assert_eq!(report.packages[1].outcome, InstallOutcome::Installed);
+        assert_eq!(report.packages[1].installer, Some("custom".to_string()));
+        assert_eq!(report.packages[2].outcome, InstallOutcome::Failed);
+        assert!(report.packages[2].error.is_some());
+    }
+
+    #[test]
+    #[serial_test::serial]
+    fn test_run_install_fails_when_installed_version_misses_range() {
+        let temp = tempfile::TempDir::new().unwrap();
+        unsafe {
+            std::env::set_var("HOME", temp.path());
+            std::env::set_var("XDG_CONFIG_HOME", temp.path().join(".config"));
+        }
+        let reg_dir = crate::config::config_dir().join("registry");
+        std::fs::create_dir_all(&reg_dir).unwrap();
+        std::fs::write(
+            reg_dir.join("tool.toml"),
+            "detect = \"tool --version\"\n\n[packages]\ncustom = \"get-tool\"\n",
+        )
+        .unwrap();
+        let install =
+            |installed: &str| {
+                let runner = FakeRunner::new()
+                    .respond("get-tool", 0, "")
+                    .respond("tool --version", 0, installed);
+                let profile = Profile {
+                    name: "ranges".to_string(),
+                    origin: None,
+                    packages: vec![PackageRef {
+                        version: Some(">=1.5".to_string()),
+                        ..make_pkg_ref("tool")
+                    }],
+                };
+                run_install(
+                    &profile,
+                    true,
+                    &None,
+                    None,
+                    None,
+                    &runner,
+                    &SilentReporter,
+                )
+                .unwrap()
+                .packages
+                .remove(0)
+            };
+
+        let outcome = install("tool 1.4.2");
+        assert_eq!(outcome.outcome, InstallOutcome::Failed);
+        assert!(outcome.error.unwrap().contains("does not satisfy '>=1.5'"));
+
+        let outcome = install("tool 1.6.0");
+        assert_eq!(outcome.outcome, InstallOutcome::Installed);
+        assert_eq!(outcome.version.as_deref(), Some("1.6.0"));
     }
 }
@@ -1,10 +1,41 @@
 pub mod cli;
 pub mod config;
+pub mod container;
+pub mod doctor;
+pub mod error;
+pub mod export;
+pub mod fetch;
+pub mod import;
 pub mod installer;
+pub mod lock;
 pub mod logging;
+pub mod merge;
+pub mod output;
+pub mod picker;
 pub mod profile;
 pub mod registry;
+pub mod release;
+pub mod remote;
+pub mod reporter;
+pub mod runner;
+pub mod scaffold;
+pub mod status;
+pub mod subscription;
+pub mod suggest;
 pub mod updater;
+pub mod version;
+
+pub use error::{Error, Result};
+
+// `git,curl, jq` -> ["git", "curl", "jq"]
+fn split_packages(packages: &str) -> Vec<String> {
+    packages
+        .split(',')
+        .map(str::trim)
+        .filter(|p| !p.is_empty())
+        .map(String::from)
+        .collect()
+}
 
 fn resolve_profile_name(profile_arg: &Option<String>) -> String {
     profile_arg
@@ -12,7 +43,8 @@ fn resolve_profile_name(profile_arg: &Option<String>) -> String {
         .map_or_else(config::get_default_profile, |s| s.to_string())
 }
 
-pub fn run(cli: cli::Cli) -> Result<(), Box<dyn std::error::Error>> {
+pub fn run(cli: cli::Cli) -> Result<()> {
+    output::set_format(cli.output_format());
     config::bootstrap_config()?;
 
     // Check for registry updates in the background so the command runs
@@ -32,11 +64,24 @@ pub fn run(cli: cli::Cli) -> Result<(), Box<dyn std::error::Error>> {
 
         cli::Commands::Delete { profile } => profile::delete_profile(profile)?,
 
+        cli::Commands::Copy {
+            source,
+            destination,
+        } => profile::copy_profile(source, destination)?,
+
+        cli::Commands::Rename { old, new } => {
+            profile::rename_profile(old, new)?
+        }
+
+        cli::Commands::Edit { profile } => {
+            profile::edit_profile(&resolve_profile_name(profile))?
+        }
+
         cli::Commands::SetDefault { profile } => {
             config::set_default_profile(profile)?;
         }
 
-        cli::Commands::List => profile::list_profiles(),
+        cli::Commands::List => profile::list_profiles()?,
 
         cli::Commands::Show { profile } => {
             let profile_name = resolve_profile_name(profile);
@@ -45,50 +90,240 @@ pub fn run(cli: cli::Cli) -> Result<(), Box<dyn std::error::Error>> {
 
         cli::Commands::Add {
             profile,
-            package,
+            packages,
             installer,
+            version,
+            ignore_existing,
+            with_deps,
+            tags,
+            interactive,
+        } => {
+            let options = profile::AddOptions {
+                installer: installer.clone(),
+                version: version.clone(),
+                ignore_existing: *ignore_existing,
+                with_deps: *with_deps,
+                tags: tags.clone(),
+            };
+            if let Some(picked) = interactive {
+                let profile_name = resolve_profile_name(picked);
+                profile::pick_packages(&profile_name, &options)?;
+            } else if let Some(packages) = packages {
+                profile::add_packages_to_profile(
+                    &resolve_profile_name(profile),
+                    &split_packages(packages),
+                    &options,
+                )?;
+            }
+        }
+
+        cli::Commands::Diff { profile, other } => {
+            merge::diff_profiles(profile, other)?;
+        }
+
+        cli::Commands::Merge {
+            source,
+            destination,
+            strategy,
+        } => {
+            merge::merge_profiles(source, destination, *strategy)?;
+        }
+
+        cli::Commands::Subscribe {
+            source,
+            name,
+            trust,
+        } => {
+            subscription::subscribe(source, name.as_deref(), *trust)?;
+        }
+
+        cli::Commands::Sync {
+            profile,
+            dry_run,
+            yes,
+            trust,
+        } => {
+            subscription::sync(profile.as_deref(), *dry_run, *yes, *trust)?;
+        }
+
+        cli::Commands::Remove {
+            profile,
+            packages,
+            cascade,
         } => {
             let profile_name = resolve_profile_name(profile);
-            profile::add_package_to_profile(
+            profile::remove_packages_from_profile(
                 &profile_name,
-                package,
-                installer.clone(),
+                &split_packages(packages),
+                *cascade,
             )?;
         }
 
-        cli::Commands::Remove { profile, package } => {
+        cli::Commands::Export {
+            profile,
+            file,
+            to,
+            installer,
+            image,
+            bundle,
+        } => {
             let profile_name = resolve_profile_name(profile);
-            profile::remove_package_from_profile(&profile_name, package)?;
+            let options = export::ExportOptions {
+                format: *to,
+                installer: installer.clone(),
+                image: image.clone(),
+                bundle: *bundle,
+            };
+            export::export(&profile_name, file, &options)?;
         }
 
-        cli::Commands::Export { profile, file } => {
-            let profile_name = resolve_profile_name(profile);
-            profile::export_profile(&profile_name, file)?;
+        cli::Commands::Import {
+            file,
+            from: Some(source),
+            name,
+            ..
+        } => {
+            let report = import::import_native(
+                *source,
+                file.as_deref(),
+                name.as_deref().unwrap_or("imported"),
+                &runner::SystemRunner,
+            )?;
+            import::show_import(&report)?;
         }
 
-        cli::Commands::Import { file } => profile::import_profile(file)?,
+        cli::Commands::Import {
+            file,
+            sha256,
+            rename,
+            force,
+            trust,
+            ..
+        } => {
+            let options = profile::ImportOptions {
+                sha256: sha256.clone(),
+                name: rename.clone(),
+                force: *force,
+                trust: *trust,
+            };
+            // clap requires the file whenever --from is absent.
+            profile::import_profile(
+                file.as_deref().unwrap_or_default(),
+                &options,
+            )?
+        }
 
         cli::Commands::Install {
-            profile,
+            profiles,
             force,
             installer,
             dry_run,
+            locked,
+            select,
+            only,
+            except,
+            tags,
+            hosts,
         } => {
+            let profile_names = if profiles.is_empty() {
+                vec![config::get_default_profile()]
+            } else {
+                profiles.clone()
+            };
+            let only = if *select {
+                let Some(packages) = profile::pick_for_install(&profile_names)?
+                else {
+                    output::say!("Cancelled.");
+                    return Ok(());
+                };
+                Some(packages)
+            } else {
+                only.clone()
+            };
+            let options = profile::InstallOptions {
+                force: *force,
+                installer: installer.clone(),
+                dry_run: *dry_run,
+                locked: *locked,
+                only,
+                except: except.clone(),
+                tags: tags.clone(),
+            };
+
+            if !hosts.is_empty() {
+                let reports =
+                    remote::install_on_hosts(hosts, &profile_names, &options)?;
+                let failed = reports.iter().filter(|r| !r.succeeded()).count();
+                if failed > 0 {
+                    return Err(Error::HostsIncomplete {
+                        failed,
+                        total: reports.len(),
+                    });
+                }
+            } else {
+                let report = profile::install_profiles(
+                    &profile_names,
+                    &options,
+                    &runner::SystemRunner,
+                    &reporter::TextReporter,
+                )?;
+                output::emit(&report)?;
+                if report.failed > 0 {
+                    return Err(Error::InstallIncomplete {
+                        profile: report.profile,
+                        failed: report.failed,
+                        total: report.packages.len(),
+                    });
+                }
+            }
+        }
+
+        cli::Commands::Status { profile } => {
             let profile_name = resolve_profile_name(profile);
-            profile::install_profile(
-                &profile_name,
-                *force,
-                installer,
-                *dry_run,
-            )?;
+            status::show_status(&profile_name)?;
+        }
+
+        cli::Commands::Test { profile, image } => {
+            let profile_name = resolve_profile_name(profile);
+            container::test_profile(&profile_name, image)?;
+        }
+
+        cli::Commands::Lock { profile } => {
+            let profile_name = resolve_profile_name(profile);
+            lock::lock_profile(&profile_name, &runner::SystemRunner)?;
         }
 
         cli::Commands::Registry { command } => match command {
             cli::RegistryCommands::List { query } => {
                 registry::list_packages(query)?;
             }
+            cli::RegistryCommands::New {
+                name,
+                display,
+                detect,
+                installers,
+                custom,
+                dependencies,
+                no_probe,
+                yes,
+                force,
+            } => {
+                let new = scaffold::NewPackage {
+                    name: name.clone(),
+                    display: display.clone(),
+                    detect: detect.clone(),
+                    installers: installers.clone(),
+                    custom: custom.clone(),
+                    dependencies: dependencies.clone(),
+                    probe: !no_probe,
+                    force: *force,
+                };
+                scaffold::new_package(&new, !yes, &runner::SystemRunner)?;
+            }
         },
 
+        cli::Commands::Doctor => doctor::run_doctor(&runner::SystemRunner)?,
+
         cli::Commands::SelfUpdate { check } => {
             updater::self_update(*check)?;
         }
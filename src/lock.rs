@@ -0,0 +1,491 @@
+use std::{collections::HashMap, fs, path::PathBuf};
+
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::profiles_dir,
+    error::{Error, Result},
+    installer,
+    output::say,
+    profile::{self, Profile},
+    registry,
+    runner::CommandRunner,
+    version::VersionReq,
+};
+
+pub const LOCK_SUFFIX: &str = ".lock";
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct LockedPackage {
+    pub name: String,
+    pub installer: String,
+    pub value: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    pub hash: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Lockfile {
+    pub profile: String,
+    #[serde(default)]
+    pub packages: Vec<LockedPackage>,
+}
+
+impl Lockfile {
+    pub fn get(&self, package_name: &str) -> Option<&LockedPackage> {
+        self.packages.iter().find(|p| p.name == package_name)
+    }
+}
+
+pub fn lock_path(profile_name: &str) -> PathBuf {
+    profiles_dir().join(format!("{}{}.toml", profile_name, LOCK_SUFFIX))
+}
+
+// Every locked package needs a version to hold later installs to: the one
+// installed here, or the exact version the profile pins. A package with
+// neither would install whatever is latest, so locking refuses it.
+pub fn resolve_lock(
+    profile: &Profile,
+    runner: &dyn CommandRunner,
+) -> Result<Lockfile> {
+    log::debug!("resolving lock for profile '{}'", profile.name);
+    let ordered = installer::topological_sort(&profile.packages)?;
+
+    let detected = installer::detect_available_installer(runner);
+    let mut packages = Vec::new();
+    let mut unresolved = Vec::new();
+    for name in &ordered {
+        let pkg = registry::get_package_details(name)?;
+        let pkg_ref = profile.packages.iter().find(|p| &p.name == name);
+        let pin = pkg_ref.and_then(|p| p.installer.clone());
+        let (installer_name, value) =
+            installer::select_installer(&pkg, &pin, detected.as_deref())?;
+        let installed = installer::is_installed(&pkg, runner);
+        let exact = pkg_ref
+            .and_then(|p| p.version.as_deref())
+            .map(VersionReq::parse)
+            .transpose()?
+            .and_then(|req| req.install_version());
+        let version = if installed {
+            installer::detect_version(&pkg, runner).or(exact)
+        } else if exact.is_some() {
+            exact
+        } else {
+            unresolved.push(name.clone());
+            continue;
+        };
+        log::debug!(
+            "locked '{}': installer={}, value={}, version={:?}",
+            name,
+            installer_name,
+            value,
+            version
+        );
+        packages.push(LockedPackage {
+            name: name.clone(),
+            installer: installer_name,
+            value,
+            version,
+            hash: registry::package_hash(name)?,
+        });
+    }
+    if !unresolved.is_empty() {
+        return Err(Error::LockUnresolved {
+            profile: profile.name.clone(),
+            packages: unresolved,
+        });
+    }
+
+    Ok(Lockfile {
+        profile: profile.name.clone(),
+        packages,
+    })
+}
+
+pub fn write_lock(lock: &Lockfile) -> Result<()> {
+    let path = lock_path(&lock.profile);
+    log::debug!("writing lock for '{}' to {:?}", lock.profile, path);
+    let toml_str =
+        toml::to_string(lock).map_err(Error::serialize("lock file"))?;
+    fs::write(&path, toml_str).map_err(Error::io("Failed to write lock file"))
+}
+
+pub fn read_lock(profile_name: &str) -> Result<Lockfile> {
+    let path = lock_path(profile_name);
+    log::debug!("reading lock for '{}' from {:?}", profile_name, path);
+    if !path.exists() {
+        log::error!("no lock file for profile '{}'", profile_name);
+        return Err(Error::LockMissing(profile_name.to_string()));
+    }
+    let content = fs::read_to_string(&path)
+        .map_err(Error::io(format!("Failed to read lock file {:?}", path)))?;
+    toml::from_str(&content).map_err(|source| Error::InvalidFile {
+        kind: "lock",
+        path,
+        source: Box::new(source),
+    })
+}
+
+// A lock is only reproducible while the profile still lists the same packages
+// with the same pins and the registry definitions it was resolved from are
+// byte-identical.
+pub fn check_drift(lock: &Lockfile, profile: &Profile) -> Result<()> {
+    let missing: Vec<String> = profile
+        .packages
+        .iter()
+        .filter(|p| lock.get(&p.name).is_none())
+        .map(|p| p.name.clone())
+        .collect();
+    if !missing.is_empty() {
+        log::error!(
+            "profile '{}' changed since lock: {:?}",
+            lock.profile,
+            missing
+        );
+        return Err(Error::LockIncomplete {
+            profile: lock.profile.clone(),
+            packages: missing,
+        });
+    }
+
+    // Pins changed since locking, and packages the profile no longer needs.
+    let mut changes = Vec::new();
+    for package in &profile.packages {
+        let Some(locked) = lock.get(&package.name) else {
+            continue;
+        };
+        if let Some(installer) = &package.installer
+            && *installer != locked.installer
+        {
+            changes.push(format!(
+                "{} (locked to installer {}, profile pins {})",
+                package.name, locked.installer, installer
+            ));
+        }
+        if let (Some(req), Some(version)) = (&package.version, &locked.version)
+            && !VersionReq::parse(req)?.matches(version)
+        {
+            changes.push(format!(
+                "{} (locked {} does not satisfy {})",
+                package.name, version, req
+            ));
+        }
+    }
+    let needed = installer::topological_sort(&profile.packages)?;
+    for locked in &lock.packages {
+        if !needed.contains(&locked.name) {
+            changes.push(format!("{} (no longer in the profile)", locked.name));
+        }
+    }
+    if !changes.is_empty() {
+        log::error!("lock '{}' is stale: {:?}", lock.profile, changes);
+        return Err(Error::LockStale {
+            profile: lock.profile.clone(),
+            changes,
+        });
+    }
+
+    let drifted: Vec<String> = lock
+        .packages
+        .iter()
+        .filter(|p| {
+            registry::package_hash(&p.name).map_or(true, |h| h != p.hash)
+        })
+        .map(|p| p.name.clone())
+        .collect();
+    if !drifted.is_empty() {
+        log::error!(
+            "registry drifted for lock '{}': {:?}",
+            lock.profile,
+            drifted
+        );
+        return Err(Error::RegistryDrift {
+            profile: lock.profile.clone(),
+            packages: drifted,
+        });
+    }
+    Ok(())
+}
+
+// The locks of several profiles, each checked against its own profile, as
+// one. A package two locks resolved differently is a conflict.
+pub fn read_locks(profiles: &[Profile]) -> Result<Lockfile> {
+    let mut combined: Option<Lockfile> = None;
+    // The profile whose lock recorded each package, for the error message.
+    let mut recorded_by: HashMap<String, String> = HashMap::new();
+    for profile in profiles {
+        let lock = read_lock(&profile.name)?;
+        check_drift(&lock, profile)?;
+        let Some(combined) = combined.as_mut() else {
+            for package in &lock.packages {
+                recorded_by.insert(package.name.clone(), lock.profile.clone());
+            }
+            combined = Some(lock);
+            continue;
+        };
+        for package in lock.packages {
+            match combined.get(&package.name) {
+                None => {
+                    recorded_by
+                        .insert(package.name.clone(), lock.profile.clone());
+                    combined.packages.push(package);
+                }
+                Some(existing) if *existing == package => {}
+                Some(existing) => {
+                    let pins = |p: &LockedPackage| {
+                        format!(
+                            "locked to {} {}",
+                            p.installer,
+                            p.version.as_deref().unwrap_or("unversioned")
+                        )
+                    };
+                    return Err(Error::PinConflict {
+                        package: package.name.clone(),
+                        first: recorded_by[&package.name].clone(),
+                        first_pins: pins(existing),
+                        second: lock.profile.clone(),
+                        second_pins: pins(&package),
+                    });
+                }
+            }
+        }
+    }
+    combined.ok_or_else(|| Error::Other("No profiles to install".to_string()))
+}
+
+pub fn lock_profile(
+    profile_name: &str,
+    runner: &dyn CommandRunner,
+) -> Result<()> {
+    let profile = profile::read_profile(profile_name)?;
+    let lock = resolve_lock(&profile, runner)?;
+    write_lock(&lock)?;
+
+    log::info!(
+        "locked profile '{}' ({} packages)",
+        profile_name,
+        lock.packages.len()
+    );
+    say!("Locking profile {}:", profile_name.cyan().bold());
+    for pkg in &lock.packages {
+        say!(
+            "  {} {} {}",
+            "=".green().bold(),
+            pkg.name.cyan(),
+            format!(
+                "({} {}{})",
+                pkg.installer,
+                pkg.value,
+                pkg.version
+                    .as_ref()
+                    .map(|v| format!(", {}", v))
+                    .unwrap_or_default()
+            )
+            .dimmed()
+        );
+    }
+    say!(
+        "{} '{}'.",
+        "Lock file written to".green(),
+        lock_path(profile_name).display()
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use serial_test::serial;
+    use tempfile::TempDir;
+
+    use super::*;
+    use crate::{profile::PackageRef, runner::FakeRunner};
+
+    fn setup_test_env() -> TempDir {
+        let temp_dir = TempDir::new().expect("Failed to create temp dir");
+        unsafe {
+            env::set_var("HOME", temp_dir.path());
+            env::set_var("XDG_CONFIG_HOME", temp_dir.path().join(".config"));
+        }
+
+        let reg_dir = crate::config::config_dir().join("registry");
+        fs::create_dir_all(&reg_dir).unwrap();
+        fs::write(reg_dir.join("metadata.toml"), "version = \"7\"\n").unwrap();
+        fs::write(
+            reg_dir.join("tool.toml"),
+            "display = \"Tool\"\ndetect = \"tool --version\"\ndependencies = [\"lib\"]\n\n[packages]\ncustom = \"install-tool.sh\"\n",
+        )
+        .unwrap();
+        fs::write(
+            reg_dir.join("lib.toml"),
+            "display = \"Lib\"\ndetect = \"lib --version\"\n\n[packages]\ncustom = \"install-lib.sh\"\n",
+        )
+        .unwrap();
+
+        profile::write_profile(&Profile {
+            name: "work".to_string(),
+            origin: None,
+            packages: vec![PackageRef {
+                name: "tool".to_string(),
+                ..Default::default()
+            }],
+        })
+        .unwrap();
+        temp_dir
+    }
+
+    // Both packages installed; only tool reports a version.
+    fn installed() -> FakeRunner {
+        FakeRunner::new()
+            .respond("tool --version", 0, "tool 1.2.3")
+            .respond("lib --version", 0, "")
+    }
+
+    #[test]
+    #[serial]
+    fn test_lock_profile_records_resolution() {
+        let _temp = setup_test_env();
+
+        lock_profile("work", &installed()).unwrap();
+        let lock = read_lock("work").unwrap();
+
+        assert_eq!(lock.profile, "work");
+        let names: Vec<&str> =
+            lock.packages.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, vec!["lib", "tool"]);
+
+        let tool = lock.get("tool").unwrap();
+        assert_eq!(tool.installer, "custom");
+        assert_eq!(tool.value, "install-tool.sh");
+        assert_eq!(tool.version, Some("1.2.3".to_string()));
+        assert_eq!(tool.hash, registry::package_hash("tool").unwrap());
+        assert_eq!(lock.get("lib").unwrap().version, None);
+    }
+
+    #[test]
+    #[serial]
+    fn test_lock_refuses_uninstalled_packages_without_exact_pin() {
+        let _temp = setup_test_env();
+        let runner =
+            FakeRunner::new().respond("tool --version", 0, "tool 1.2.3");
+        match lock_profile("work", &runner) {
+            Err(Error::LockUnresolved { packages, .. }) => {
+                assert_eq!(packages, vec!["lib"])
+            }
+            other => panic!("expected unresolved lock, got {:?}", other),
+        }
+        assert!(!lock_path("work").exists());
+
+        let mut profile = profile::read_profile("work").unwrap();
+        profile.packages.push(PackageRef {
+            name: "lib".to_string(),
+            version: Some("=0.4.1".to_string()),
+            ..Default::default()
+        });
+        let lock = resolve_lock(&profile, &runner).unwrap();
+        assert_eq!(lock.get("lib").unwrap().version.as_deref(), Some("0.4.1"));
+    }
+
+    #[test]
+    #[serial]
+    fn test_check_drift_ok_when_unchanged() {
+        let _temp = setup_test_env();
+        lock_profile("work", &installed()).unwrap();
+
+        let lock = read_lock("work").unwrap();
+        let profile = profile::read_profile("work").unwrap();
+        assert!(check_drift(&lock, &profile).is_ok());
+    }
+
+    #[test]
+    #[serial]
+    fn test_check_drift_detects_registry_change() {
+        let _temp = setup_test_env();
+        lock_profile("work", &installed()).unwrap();
+
+        let reg_dir = crate::config::config_dir().join("registry");
+        fs::write(
+            reg_dir.join("lib.toml"),
+            "display = \"Lib\"\n\n[packages]\ncustom = \"other.sh\"\n",
+        )
+        .unwrap();
+
+        let lock = read_lock("work").unwrap();
+        let profile = profile::read_profile("work").unwrap();
+        let result = check_drift(&lock, &profile);
+        match result {
+            Err(Error::RegistryDrift { packages, .. }) => {
+                assert_eq!(packages, vec!["lib".to_string()])
+            }
+            other => panic!("expected registry drift, got {:?}", other),
+        }
+    }
+
+    #[test]
+    #[serial]
+    fn test_check_drift_detects_new_profile_package() {
+        let _temp = setup_test_env();
+        lock_profile("work", &installed()).unwrap();
+
+        let mut profile = profile::read_profile("work").unwrap();
+        profile.packages.push(PackageRef {
+            name: "extra".to_string(),
+            ..Default::default()
+        });
+
+        let lock = read_lock("work").unwrap();
+        let result = check_drift(&lock, &profile);
+        assert!(matches!(result, Err(Error::LockIncomplete { .. })));
+    }
+
+    #[test]
+    #[serial]
+    fn test_check_drift_detects_changed_pins_and_stale_entries() {
+        let _temp = setup_test_env();
+        lock_profile("work", &installed()).unwrap();
+        let lock = read_lock("work").unwrap();
+
+        let mut profile = profile::read_profile("work").unwrap();
+        profile.packages[0].installer = Some("apt".to_string());
+        profile.packages[0].version = Some("2.x".to_string());
+        match check_drift(&lock, &profile) {
+            Err(Error::LockStale { changes, .. }) => {
+                assert_eq!(changes.len(), 2);
+                assert!(changes[0].contains("profile pins apt"));
+                assert!(changes[1].contains("1.2.3 does not satisfy 2.x"));
+            }
+            other => panic!("expected stale lock, got {:?}", other),
+        }
+
+        profile.packages.clear();
+        match check_drift(&lock, &profile) {
+            Err(Error::LockStale { changes, .. }) => assert_eq!(
+                changes,
+                vec![
+                    "lib (no longer in the profile)",
+                    "tool (no longer in the profile)"
+                ]
+            ),
+            other => panic!("expected stale lock, got {:?}", other),
+        }
+    }
+
+    #[test]
+    #[serial]
+    fn test_lock_suffix_is_not_a_profile_name() {
+        let _temp = setup_test_env();
+        let result = profile::create_profile("work.lock");
+        assert!(matches!(result, Err(Error::InvalidName { .. })));
+    }
+
+    #[test]
+    #[serial]
+    fn test_read_lock_missing() {
+        let _temp = setup_test_env();
+        let result = read_lock("work");
+        assert!(matches!(result, Err(Error::LockMissing(_))));
+    }
+}
@@ -1,12 +1,165 @@
-use blazinit::{cli::Cli, logging, run};
+use blazinit::{Error, cli::Cli, error, logging, run};
 use clap::Parser;
 
+// Process exit codes, one per error category so scripts can react without
+// parsing messages. 2 is left to clap for invalid command-line usage.
+//
+//   0  success
+//   1  any other error
+//   2  invalid arguments (clap)
+//   3  not found: profile, package, lock file
+//   4  conflict: profile or package already exists, default profile
+//   5  invalid data: registry, profile or lock file, version, dependencies,
+//      registry drift since the lock file was written, native package exports
+//   6  network: unreachable URL or host, unexpected response, checksum
+//      mismatch
+//   7  installation failed or no installer available, on any host or in a
+//      test container; the installed version misses the requirement; native
+//      package listing failed
+//   8  `status`: packages are missing or outdated; `lock`: packages are
+//      neither installed nor pinned to an exact version; `doctor`: problems
+//      found
+//   9  filesystem I/O
+//  10  bundled custom install commands were not trusted
+//  11  `sync`: some subscribed profiles failed while the others synced
+//
+// A rejected `edit` exits with the code of the reason it was rejected.
+fn exit_code(e: &Error) -> i32 {
+    match e {
+        Error::EditRejected { source, .. } => exit_code(source),
+        Error::ProfileNotFound { .. }
+        | Error::PackageNotFound { .. }
+        | Error::PackageNotInProfile { .. }
+        | Error::TagNotFound { .. }
+        | Error::LockMissing(_) => 3,
+        Error::ProfileExists(_)
+        | Error::PackageExists { .. }
+        | Error::DefaultProfile(_)
+        | Error::RegistryEntryExists(_)
+        | Error::MergeConflict { .. }
+        | Error::PinConflict { .. } => 4,
+        Error::RegistryParse { .. }
+        | Error::InvalidFile { .. }
+        | Error::InvalidData { .. }
+        | Error::InvalidDefinition { .. }
+        | Error::InvalidVersion(_)
+        | Error::InvalidRegex { .. }
+        | Error::DependencyCycle(_)
+        | Error::LockIncomplete { .. }
+        | Error::RegistryDrift { .. }
+        | Error::LockStale { .. }
+        | Error::InvalidName { .. }
+        | Error::Serialize { .. } => 5,
+        Error::Network { .. }
+        | Error::InvalidResponse { .. }
+        | Error::ChecksumMismatch { .. }
+        | Error::GitFailed { .. }
+        | Error::HostUnreachable { .. } => 6,
+        Error::InstallerUnavailable { .. }
+        | Error::NoInstaller(_)
+        | Error::InstallFailed { .. }
+        | Error::VersionUnsatisfied { .. }
+        | Error::InstallIncomplete { .. }
+        | Error::HostsIncomplete { .. }
+        | Error::NativeList { .. }
+        | Error::Container { .. }
+        | Error::ContainerTestFailed { .. } => 7,
+        Error::Unsatisfied { .. }
+        | Error::LockUnresolved { .. }
+        | Error::DoctorProblems(_) => 8,
+        Error::Io { .. } => 9,
+        Error::UntrustedCommands { .. } => 10,
+        Error::SyncIncomplete { .. } => 11,
+        _ => 1,
+    }
+}
+
 fn main() {
     logging::init_logger();
     let cli = Cli::parse();
 
     if let Err(e) = run(cli) {
-        eprintln!("{}", e);
-        std::process::exit(1);
+        log::error!("{:?}", e);
+        eprintln!("{}", error::chain(&e));
+        std::process::exit(exit_code(&e));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    use super::*;
+
+    #[test]
+    fn test_exit_code_per_category() {
+        let cases = [
+            (Error::ProfileExists("work".to_string()), 4),
+            (
+                Error::PackageNotFound {
+                    name: "gti".to_string(),
+                    suggestions: vec!["git".to_string()],
+                },
+                3,
+            ),
+            (Error::InvalidVersion("latest".to_string()), 5),
+            (
+                Error::GitFailed {
+                    url: "https://example.com/x.git".to_string(),
+                    message: "not found".to_string(),
+                },
+                6,
+            ),
+            (Error::NoInstaller("git".to_string()), 7),
+            (
+                Error::Unsatisfied {
+                    profile: "work".to_string(),
+                    failing: 1,
+                    total: 2,
+                },
+                8,
+            ),
+            (
+                Error::Io {
+                    context: "Failed to write".to_string(),
+                    source: io::Error::other("disk full"),
+                },
+                9,
+            ),
+            (
+                Error::UntrustedCommands {
+                    profile: "work".to_string(),
+                    packages: vec!["tool".to_string()],
+                },
+                10,
+            ),
+            (
+                Error::SyncIncomplete {
+                    failed: 1,
+                    total: 3,
+                },
+                11,
+            ),
+            (Error::Other("boom".to_string()), 1),
+        ];
+        for (error, code) in cases {
+            assert_eq!(exit_code(&error), code, "{:?}", error);
+        }
+    }
+
+    #[test]
+    fn test_rejected_edit_exits_with_reason_code() {
+        let error = Error::EditRejected {
+            profile: "work".to_string(),
+            draft: "/tmp/blazinit-draft.toml".into(),
+            source: Box::new(Error::PackageNotFound {
+                name: "ghost".to_string(),
+                suggestions: Vec::new(),
+            }),
+        };
+        assert_eq!(exit_code(&error), 3);
+        assert!(
+            error::chain(&error).ends_with("'ghost' not found in registry")
+        );
     }
 }
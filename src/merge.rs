@@ -0,0 +1,502 @@
+use clap::ValueEnum;
+use colored::Colorize;
+use serde::Serialize;
+
+use crate::{
+    error::{Error, Result},
+    output::{self, say},
+    profile::{self, PackageRef, Profile},
+};
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct PackageChange {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from: Option<PackageRef>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub to: Option<PackageRef>,
+}
+
+// Packages added, removed or re-pinned going from `old` to `new`, ordered by
+// name.
+pub fn diff(old: &[PackageRef], new: &[PackageRef]) -> Vec<PackageChange> {
+    let mut changes: Vec<PackageChange> = Vec::new();
+    for before in old {
+        let after = new.iter().find(|p| p.name == before.name);
+        if after != Some(before) {
+            changes.push(PackageChange {
+                name: before.name.clone(),
+                from: Some(before.clone()),
+                to: after.cloned(),
+            });
+        }
+    }
+    for after in new {
+        if !old.iter().any(|p| p.name == after.name) {
+            changes.push(PackageChange {
+                name: after.name.clone(),
+                from: None,
+                to: Some(after.clone()),
+            });
+        }
+    }
+    changes.sort_by(|a, b| a.name.cmp(&b.name));
+    changes
+}
+
+pub(crate) fn pins(package: &PackageRef) -> String {
+    let mut pins = Vec::new();
+    if let Some(version) = &package.version {
+        pins.push(format!("version: {}", version));
+    }
+    if let Some(installer) = &package.installer {
+        pins.push(format!("installer: {}", installer));
+    }
+    if !package.tags.is_empty() {
+        pins.push(format!("tags: {}", package.tags.join(", ")));
+    }
+    if pins.is_empty() {
+        "unpinned".to_string()
+    } else {
+        pins.join(", ")
+    }
+}
+
+pub fn print_changes(changes: &[PackageChange]) {
+    for change in changes {
+        match (&change.from, &change.to) {
+            (None, Some(_)) => {
+                say!("  {} {}", "+".green().bold(), change.name.cyan())
+            }
+            (Some(_), None) => {
+                say!("  {} {}", "-".red().bold(), change.name.cyan())
+            }
+            (Some(from), Some(to)) => say!(
+                "  {} {} {}",
+                "~".yellow().bold(),
+                change.name.cyan(),
+                format!("({} -> {})", pins(from), pins(to)).dimmed()
+            ),
+            (None, None) => {}
+        }
+    }
+}
+
+// A profile name, or a file, URL or git source when no profile has that
+// name.
+fn load(reference: &str) -> Result<Profile> {
+    if profile::profile_path(reference).exists() {
+        return profile::read_profile(reference);
+    }
+    profile::fetch_profile(reference, None).map(|fetched| fetched.profile)
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct DiffReport {
+    pub from: String,
+    pub to: String,
+    pub changes: Vec<PackageChange>,
+}
+
+pub fn diff_profiles(a: &str, b: &str) -> Result<()> {
+    let old = profile::read_profile(a)?;
+    let new = load(b)?;
+    let report = DiffReport {
+        from: a.to_string(),
+        to: b.to_string(),
+        changes: diff(&old.packages, &new.packages),
+    };
+    if output::is_structured() {
+        return output::emit(&report);
+    }
+
+    say!(
+        "{} {} {} {}",
+        "Diff".bold(),
+        a.cyan().bold(),
+        "->".dimmed(),
+        b.cyan().bold()
+    );
+    if report.changes.is_empty() {
+        say!("  {}", "No differences.".dimmed());
+    }
+    print_changes(&report.changes);
+    Ok(())
+}
+
+#[derive(ValueEnum, Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Strategy {
+    // Refuse to merge while any package is pinned differently.
+    Union,
+    // Conflicting pins take the source's value.
+    Theirs,
+    // Conflicting pins keep the destination's value.
+    Ours,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Conflict {
+    pub name: String,
+    pub ours: PackageRef,
+    pub theirs: PackageRef,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct MergeReport {
+    pub source: String,
+    pub destination: String,
+    pub strategy: Strategy,
+    pub added: Vec<String>,
+    pub conflicts: Vec<Conflict>,
+    pub applied: bool,
+}
+
+// Every package of `ours` plus those only `theirs` has. Packages in both
+// with different pins are conflicts, resolved according to `strategy`.
+pub fn merge_packages(
+    ours: &[PackageRef],
+    theirs: &[PackageRef],
+    strategy: Strategy,
+) -> (Vec<PackageRef>, Vec<String>, Vec<Conflict>) {
+    let mut merged = ours.to_vec();
+    let mut added = Vec::new();
+    let mut conflicts = Vec::new();
+    for their in theirs {
+        match merged.iter_mut().find(|p| p.name == their.name) {
+            None => {
+                added.push(their.name.clone());
+                merged.push(their.clone());
+            }
+            // Only the installer and version are pins; tags from both
+            // sides are kept.
+            Some(our) => {
+                if our.installer != their.installer
+                    || our.version != their.version
+                {
+                    conflicts.push(Conflict {
+                        name: their.name.clone(),
+                        ours: our.clone(),
+                        theirs: their.clone(),
+                    });
+                    if strategy == Strategy::Theirs {
+                        our.installer = their.installer.clone();
+                        our.version = their.version.clone();
+                    }
+                }
+                for tag in &their.tags {
+                    if !our.tags.contains(tag) {
+                        our.tags.push(tag.clone());
+                    }
+                }
+            }
+        }
+    }
+    merged.sort_by(|a, b| a.name.cmp(&b.name));
+    added.sort();
+    (merged, added, conflicts)
+}
+
+pub fn merge_profiles(
+    source: &str,
+    destination: &str,
+    strategy: Strategy,
+) -> Result<()> {
+    let theirs = load(source)?;
+    let mut ours = profile::read_profile(destination)?;
+    let (packages, added, conflicts) =
+        merge_packages(&ours.packages, &theirs.packages, strategy);
+    log::info!(
+        "merging '{}' into '{}' ({:?}): {} added, {} conflicts",
+        source,
+        destination,
+        strategy,
+        added.len(),
+        conflicts.len()
+    );
+
+    let mut report = MergeReport {
+        source: source.to_string(),
+        destination: destination.to_string(),
+        strategy,
+        added,
+        conflicts,
+        applied: false,
+    };
+    let blocked = strategy == Strategy::Union && !report.conflicts.is_empty();
+    if !blocked {
+        // The source may name packages this registry doesn't have. Only what
+        // the merge brings in is checked; the destination's own packages are
+        // left as they were.
+        let incoming: Vec<PackageRef> = packages
+            .iter()
+            .filter(|p| {
+                report.added.contains(&p.name)
+                    || (strategy == Strategy::Theirs
+                        && report.conflicts.iter().any(|c| c.name == p.name))
+            })
+            .cloned()
+            .collect();
+        profile::validate_profile(&Profile {
+            name: ours.name.clone(),
+            origin: None,
+            packages: incoming,
+        })?;
+        ours.packages = packages;
+        profile::write_profile(&ours)?;
+        profile::warn_if_subscribed(&ours);
+        report.applied = true;
+    }
+
+    if output::is_structured() {
+        output::emit(&report)?;
+    } else {
+        print_merge(&report);
+    }
+    if blocked {
+        return Err(Error::MergeConflict {
+            destination: destination.to_string(),
+            conflicts: report.conflicts.len(),
+        });
+    }
+    Ok(())
+}
+
+fn print_merge(report: &MergeReport) {
+    say!(
+        "{} {} {} {}",
+        "Merging".bold(),
+        report.source.cyan().bold(),
+        "into".dimmed(),
+        report.destination.cyan().bold()
+    );
+    for name in &report.added {
+        say!("  {} {}", "+".green().bold(), name.cyan());
+    }
+    for conflict in &report.conflicts {
+        let kept = match report.strategy {
+            Strategy::Union => "unresolved",
+            Strategy::Theirs => "took theirs",
+            Strategy::Ours => "kept ours",
+        };
+        say!(
+            "  {} {} {}",
+            "!".red().bold(),
+            conflict.name.cyan(),
+            format!(
+                "(ours: {}, theirs: {}; {})",
+                pins(&conflict.ours),
+                pins(&conflict.theirs),
+                kept
+            )
+            .dimmed()
+        );
+    }
+    if report.applied {
+        say!(
+            "{}",
+            format!("Added {} packages.", report.added.len()).green()
+        );
+    }
+}
+
+// The pin two profiles agree on, or the one only one of them sets; None
+// when they disagree.
+fn agree(a: &Option<String>, b: &Option<String>) -> Option<Option<String>> {
+    match (a, b) {
+        (Some(a), Some(b)) if a != b => None,
+        _ => Some(a.clone().or_else(|| b.clone())),
+    }
+}
+
+// Every package of `profiles` once, under a name joining theirs. A pin set in
+// one profile applies to all of them; two profiles pinning a package
+// differently is an error.
+pub fn union_profiles(profiles: &[Profile]) -> Result<Profile> {
+    let mut packages: Vec<PackageRef> = Vec::new();
+    // The profiles each package's installer and version pins came from, for
+    // the error message.
+    let mut pinned_by: Vec<(&str, &str)> = Vec::new();
+    for profile in profiles {
+        for package in &profile.packages {
+            let Some(i) = packages.iter().position(|p| p.name == package.name)
+            else {
+                packages.push(package.clone());
+                pinned_by.push((&profile.name, &profile.name));
+                continue;
+            };
+            let existing = &mut packages[i];
+            let installer = agree(&existing.installer, &package.installer);
+            let version = agree(&existing.version, &package.version);
+            let installer_clash = installer.is_none();
+            let (Some(installer), Some(version)) = (installer, version) else {
+                let (installer_by, version_by) = pinned_by[i];
+                return Err(Error::PinConflict {
+                    package: package.name.clone(),
+                    first: if installer_clash {
+                        installer_by
+                    } else {
+                        version_by
+                    }
+                    .to_string(),
+                    first_pins: pins(existing),
+                    second: profile.name.clone(),
+                    second_pins: pins(package),
+                });
+            };
+            if existing.installer.is_none() && package.installer.is_some() {
+                pinned_by[i].0 = &profile.name;
+            }
+            if existing.version.is_none() && package.version.is_some() {
+                pinned_by[i].1 = &profile.name;
+            }
+            existing.installer = installer;
+            existing.version = version;
+            for tag in &package.tags {
+                if !existing.tags.contains(tag) {
+                    existing.tags.push(tag.clone());
+                }
+            }
+        }
+    }
+    let names: Vec<&str> = profiles.iter().map(|p| p.name.as_str()).collect();
+    Ok(Profile {
+        name: names.join("+"),
+        origin: None,
+        packages,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn package(name: &str, installer: Option<&str>) -> PackageRef {
+        PackageRef {
+            name: name.to_string(),
+            installer: installer.map(String::from),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_diff_reports_added_removed_and_repinned() {
+        let old = vec![
+            package("curl", None),
+            package("git", None),
+            package("node", Some("apt")),
+        ];
+        let new = vec![
+            package("git", None),
+            package("node", Some("brew")),
+            package("ripgrep", None),
+        ];
+        let changes = diff(&old, &new);
+        let summary: Vec<_> = changes
+            .iter()
+            .map(|c| (c.name.as_str(), c.from.is_some(), c.to.is_some()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("curl", true, false),
+                ("node", true, true),
+                ("ripgrep", false, true),
+            ]
+        );
+        assert!(diff(&old, &old).is_empty());
+    }
+
+    #[test]
+    fn test_merge_strategies() {
+        let ours = vec![package("git", None), package("node", Some("apt"))];
+        let theirs = vec![package("node", Some("brew")), package("jq", None)];
+
+        let (merged, added, conflicts) =
+            merge_packages(&ours, &theirs, Strategy::Ours);
+        assert_eq!(added, vec!["jq"]);
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(
+            merged,
+            vec![
+                package("git", None),
+                package("jq", None),
+                package("node", Some("apt")),
+            ]
+        );
+
+        let (merged, _, _) = merge_packages(&ours, &theirs, Strategy::Theirs);
+        assert_eq!(merged[2], package("node", Some("brew")));
+    }
+
+    #[test]
+    fn test_merge_keeps_tags_without_conflict() {
+        let tagged = |tags: &[&str]| PackageRef {
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+            ..package("git", Some("apt"))
+        };
+        let (merged, added, conflicts) = merge_packages(
+            &[tagged(&["cli"])],
+            &[tagged(&["cli", "vcs"])],
+            Strategy::Union,
+        );
+        assert!(added.is_empty());
+        assert!(conflicts.is_empty());
+        assert_eq!(merged, vec![tagged(&["cli", "vcs"])]);
+    }
+
+    #[test]
+    fn test_union_profiles_deduplicates_and_rejects_conflicting_pins() {
+        let profile = |name: &str, packages: Vec<PackageRef>| Profile {
+            name: name.to_string(),
+            origin: None,
+            packages,
+        };
+        let base = profile("base", vec![package("git", None)]);
+        let work = profile(
+            "work",
+            vec![package("git", Some("apt")), package("node", None)],
+        );
+        let personal = profile("personal", vec![package("git", Some("brew"))]);
+
+        let union = union_profiles(&[base, work]).unwrap();
+        assert_eq!(union.name, "base+work");
+        assert_eq!(
+            union.packages,
+            vec![package("git", Some("apt")), package("node", None)]
+        );
+
+        let result = union_profiles(&[union, personal]);
+        match result {
+            Err(Error::PinConflict {
+                package,
+                first,
+                second,
+                ..
+            }) => {
+                assert_eq!(package, "git");
+                assert_eq!(first, "base+work");
+                assert_eq!(second, "personal");
+            }
+            other => panic!("expected PinConflict, got {:?}", other),
+        }
+
+        // Installer and version pins are attributed separately.
+        let versioned = |version: &str| PackageRef {
+            version: Some(version.to_string()),
+            ..package("git", None)
+        };
+        let result = union_profiles(&[
+            profile("work", vec![package("git", Some("apt"))]),
+            profile("tools", vec![versioned("2.x")]),
+            profile("personal", vec![versioned("3.x")]),
+        ]);
+        match result {
+            Err(Error::PinConflict { first, second, .. }) => {
+                assert_eq!(first, "tools");
+                assert_eq!(second, "personal");
+            }
+            other => panic!("expected PinConflict, got {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,116 @@
+use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+
+use clap::ValueEnum;
+use serde::Serialize;
+
+use crate::error::{Error, Result};
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum OutputFormat {
+    #[default]
+    Text,
+    Json,
+    Yaml,
+}
+
+static FORMAT: AtomicU8 = AtomicU8::new(OutputFormat::Text as u8);
+
+pub fn set_format(format: OutputFormat) {
+    FORMAT.store(format as u8, Ordering::Relaxed);
+}
+
+pub fn format() -> OutputFormat {
+    match FORMAT.load(Ordering::Relaxed) {
+        x if x == OutputFormat::Json as u8 => OutputFormat::Json,
+        x if x == OutputFormat::Yaml as u8 => OutputFormat::Yaml,
+        _ => OutputFormat::Text,
+    }
+}
+
+pub fn is_structured() -> bool {
+    format() != OutputFormat::Text
+}
+
+static QUIET: AtomicBool = AtomicBool::new(false);
+
+// Library callers that only want the returned results can turn off every
+// human-readable line the library prints.
+pub fn set_quiet(quiet: bool) {
+    QUIET.store(quiet, Ordering::Relaxed);
+}
+
+pub fn is_quiet() -> bool {
+    QUIET.load(Ordering::Relaxed)
+}
+
+// Human-readable line: stdout in text mode, stderr when a structured document
+// owns stdout.
+macro_rules! say {
+    ($($arg:tt)*) => {
+        if $crate::output::is_quiet() {
+            // Turned off by the caller.
+        } else if $crate::output::is_structured() {
+            eprintln!($($arg)*)
+        } else {
+            println!($($arg)*)
+        }
+    };
+}
+pub(crate) use say;
+
+// Warning or note beside a command's result, always on stderr.
+macro_rules! note {
+    ($($arg:tt)*) => {
+        if !$crate::output::is_quiet() {
+            eprintln!($($arg)*)
+        }
+    };
+}
+pub(crate) use note;
+
+pub fn render<T: Serialize>(doc: &T, format: OutputFormat) -> Result<String> {
+    match format {
+        OutputFormat::Json => serde_json::to_string_pretty(doc)
+            .map(|s| s + "\n")
+            .map_err(Error::serialize("output")),
+        OutputFormat::Yaml => {
+            serde_yaml::to_string(doc).map_err(Error::serialize("output"))
+        }
+        OutputFormat::Text => Ok(String::new()),
+    }
+}
+
+pub fn emit<T: Serialize>(doc: &T) -> Result<()> {
+    let rendered = render(doc, format())?;
+    print!("{}", rendered);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Serialize)]
+    struct Doc {
+        name: String,
+        count: usize,
+    }
+
+    #[test]
+    fn test_render_json_and_yaml() {
+        let doc = Doc {
+            name: "work".to_string(),
+            count: 2,
+        };
+        let json = render(&doc, OutputFormat::Json).unwrap();
+        let parsed: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed["name"], "work");
+        assert_eq!(parsed["count"], 2);
+
+        let yaml = render(&doc, OutputFormat::Yaml).unwrap();
+        assert!(yaml.contains("name: work"));
+        assert!(yaml.contains("count: 2"));
+
+        assert_eq!(render(&doc, OutputFormat::Text).unwrap(), "");
+    }
+}
@@ -0,0 +1,234 @@
+use std::io;
+
+use colored::Colorize;
+use console::{Key, Term};
+
+use crate::{
+    error::{Error, Result},
+    suggest,
+};
+
+const MAX_ROWS: usize = 15;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Item {
+    pub name: String,
+    pub display: Option<String>,
+}
+
+enum Outcome {
+    Done,
+    Cancelled,
+}
+
+struct State<'a> {
+    items: &'a [Item],
+    checked: Vec<bool>,
+    query: String,
+    cursor: usize,
+}
+
+impl<'a> State<'a> {
+    fn new(items: &'a [Item], checked: &[String]) -> Self {
+        State {
+            items,
+            checked: items.iter().map(|i| checked.contains(&i.name)).collect(),
+            query: String::new(),
+            cursor: 0,
+        }
+    }
+
+    // Indices of the items whose name or display name contains the query.
+    fn visible(&self) -> Vec<usize> {
+        let query = self.query.to_lowercase();
+        (0..self.items.len())
+            .filter(|&i| {
+                let item = &self.items[i];
+                item.name.to_lowercase().contains(&query)
+                    || item
+                        .display
+                        .as_ref()
+                        .is_some_and(|d| d.to_lowercase().contains(&query))
+            })
+            .collect()
+    }
+
+    fn key(&mut self, key: Key) -> Option<Outcome> {
+        let visible = self.visible();
+        match key {
+            Key::Enter => return Some(Outcome::Done),
+            Key::Escape | Key::CtrlC => return Some(Outcome::Cancelled),
+            Key::ArrowUp => self.cursor = self.cursor.saturating_sub(1),
+            Key::ArrowDown => {
+                self.cursor = (self.cursor + 1).min(visible.len().max(1) - 1)
+            }
+            Key::Char(' ') | Key::Tab => {
+                if let Some(&i) = visible.get(self.cursor) {
+                    self.checked[i] = !self.checked[i];
+                }
+            }
+            Key::Backspace => {
+                self.query.pop();
+                self.cursor = 0;
+            }
+            Key::Char(c) if !c.is_control() => {
+                self.query.push(c);
+                self.cursor = 0;
+            }
+            _ => {}
+        }
+        None
+    }
+
+    fn selected(&self) -> Vec<String> {
+        self.items
+            .iter()
+            .zip(&self.checked)
+            .filter(|(_, checked)| **checked)
+            .map(|(item, _)| item.name.clone())
+            .collect()
+    }
+
+    fn lines(&self, title: &str, rows: usize) -> Vec<String> {
+        let visible = self.visible();
+        let count = self.checked.iter().filter(|c| **c).count();
+        let mut lines = vec![
+            format!(
+                "{} {}",
+                title.bold(),
+                format!("({} selected)", count).dimmed()
+            ),
+            format!("{} {}", "Search:".dimmed(), self.query),
+        ];
+        let start = self.cursor.saturating_sub(rows.saturating_sub(1));
+        for (row, &i) in visible.iter().enumerate().skip(start).take(rows) {
+            let item = &self.items[i];
+            let mark = if self.checked[i] {
+                "[x]".green()
+            } else {
+                "[ ]".normal()
+            };
+            let pointer = if row == self.cursor { ">" } else { " " };
+            let display = item
+                .display
+                .as_ref()
+                .filter(|d| **d != item.name)
+                .map(|d| format!(" {}", d.dimmed()))
+                .unwrap_or_default();
+            lines.push(format!(
+                "{} {} {}{}",
+                pointer,
+                mark,
+                item.name.cyan(),
+                display
+            ));
+        }
+        if visible.is_empty() {
+            lines.push(format!("  {}", "No matches.".dimmed()));
+        }
+        lines.push(
+            "up/down move, space toggle, type to search, enter confirm, esc cancel"
+                .dimmed()
+                .to_string(),
+        );
+        lines
+    }
+}
+
+fn run(term: &Term, title: &str, state: &mut State) -> io::Result<Outcome> {
+    let rows = (term.size().0 as usize)
+        .saturating_sub(5)
+        .clamp(3, MAX_ROWS);
+    let mut drawn = 0;
+    loop {
+        term.clear_last_lines(drawn)?;
+        let lines = state.lines(title, rows);
+        for line in &lines {
+            term.write_line(line)?;
+        }
+        drawn = lines.len();
+        if let Some(outcome) = state.key(term.read_key_raw()?) {
+            term.clear_last_lines(drawn)?;
+            return Ok(outcome);
+        }
+    }
+}
+
+// A checklist of `items` with `checked` ticked beforehand. Returns the ticked
+// names in item order, or None when the user cancels.
+pub fn multi_select(
+    title: &str,
+    items: &[Item],
+    checked: &[String],
+) -> Result<Option<Vec<String>>> {
+    let term = Term::stderr();
+    if !term.is_term() || !suggest::can_prompt() {
+        return Err(Error::Unsupported(
+            "Picking packages needs an interactive terminal".to_string(),
+        ));
+    }
+    let mut state = State::new(items, checked);
+    term.hide_cursor().ok();
+    let outcome = run(&term, title, &mut state);
+    term.show_cursor().ok();
+    match outcome.map_err(Error::io("Failed to read from the terminal"))? {
+        Outcome::Done => Ok(Some(state.selected())),
+        Outcome::Cancelled => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn items() -> Vec<Item> {
+        [
+            ("git", None),
+            ("neovim", Some("Neovim")),
+            ("ripgrep", Some("rg")),
+        ]
+        .into_iter()
+        .map(|(name, display)| Item {
+            name: name.to_string(),
+            display: display.map(String::from),
+        })
+        .collect()
+    }
+
+    #[test]
+    fn test_search_and_toggle() {
+        let items = items();
+        let mut state = State::new(&items, &["git".to_string()]);
+        assert_eq!(state.visible(), vec![0, 1, 2]);
+
+        for c in "RG".chars() {
+            state.key(Key::Char(c));
+        }
+        assert_eq!(state.visible(), vec![2]);
+        state.key(Key::Char(' '));
+        assert_eq!(state.selected(), vec!["git", "ripgrep"]);
+
+        state.key(Key::Backspace);
+        state.key(Key::Backspace);
+        state.key(Key::ArrowDown);
+        state.key(Key::Tab);
+        assert_eq!(state.selected(), vec!["git", "neovim", "ripgrep"]);
+        assert!(matches!(state.key(Key::Enter), Some(Outcome::Done)));
+        assert!(matches!(state.key(Key::Escape), Some(Outcome::Cancelled)));
+    }
+
+    #[test]
+    fn test_cursor_stays_in_view() {
+        let items = items();
+        let mut state = State::new(&items, &[]);
+        for _ in 0..5 {
+            state.key(Key::ArrowDown);
+        }
+        assert_eq!(state.cursor, 2);
+        state.key(Key::Char('z'));
+        state.key(Key::ArrowDown);
+        assert_eq!(state.cursor, 0);
+        assert!(state.key(Key::Char(' ')).is_none());
+        assert!(state.selected().is_empty());
+    }
+}
@@ -1,9 +1,25 @@
-use std::{collections::HashMap, fs, path::PathBuf};
+use std::{
+    collections::{BTreeMap, HashMap},
+    env, fs,
+    io::Write,
+    path::{Path, PathBuf},
+    process::Command,
+};
 
 use colored::Colorize;
 use serde::{Deserialize, Serialize};
 
-use crate::{config, config::profiles_dir};
+use crate::{
+    config,
+    config::profiles_dir,
+    error::{Error, Result},
+    installer::InstallReport,
+    output::{self, note, say},
+    release::ReleaseSpec,
+    reporter::{Event, Reporter},
+    runner::{CommandRunner, RecordingRunner},
+    version::VersionReq,
+};
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct ProfilePackage {
@@ -14,24 +30,38 @@ pub struct ProfilePackage {
     pub detect: Option<String>,
     #[serde(default)]
     pub dependencies: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub release: Option<ReleaseSpec>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version_command: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version_regex: Option<String>,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
 pub struct PackageRef {
     pub name: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub installer: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    // Labels to install a part of the profile by, e.g. `install --tag cli`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct Profile {
     pub name: String,
+    // Source a subscribed profile is kept in sync with.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub origin: Option<String>,
     pub packages: Vec<PackageRef>,
 }
 
 pub const PROFILE_DIRNAME: &str = "profiles";
 
-pub fn ensure_default_profile(profile_name: &str) -> Result<(), String> {
+pub fn ensure_default_profile(profile_name: &str) -> Result<()> {
     let path = profile_path(profile_name);
     if !path.exists() {
         log::debug!(
@@ -40,10 +70,10 @@ pub fn ensure_default_profile(profile_name: &str) -> Result<(), String> {
         );
         let profile = Profile {
             name: profile_name.to_string(),
+            origin: None,
             packages: Vec::new(),
         };
-        let toml_str = toml::to_string(&profile).map_err(|e| e.to_string())?;
-        fs::write(path, toml_str).map_err(|e| e.to_string())?;
+        write_profile(&profile)?;
         log::info!("created default profile '{}'", profile_name);
     } else {
         log::debug!("default profile '{}' already exists", profile_name);
@@ -57,16 +87,21 @@ pub fn profile_path(profile_name: &str) -> PathBuf {
     path
 }
 
-pub fn read_profile(profile_name: &str) -> Result<Profile, String> {
+pub fn read_profile(profile_name: &str) -> Result<Profile> {
     let path = profile_path(profile_name);
     log::debug!("reading profile '{}' from {:?}", profile_name, path);
     if !path.exists() {
         log::error!("profile '{}' does not exist at {:?}", profile_name, path);
-        return Err(format!("Profile '{}' does not exist.", profile_name));
+        return Err(profile_not_found(profile_name));
     }
-    let content = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let content = fs::read_to_string(&path)
+        .map_err(Error::io(format!("Failed to read profile {:?}", path)))?;
     let profile: Profile =
-        toml::from_str(&content).map_err(|e| e.to_string())?;
+        toml::from_str(&content).map_err(|source| Error::InvalidFile {
+            kind: "profile",
+            path,
+            source: Box::new(source),
+        })?;
     log::debug!(
         "profile '{}' loaded ({} packages)",
         profile_name,
@@ -75,7 +110,24 @@ pub fn read_profile(profile_name: &str) -> Result<Profile, String> {
     Ok(profile)
 }
 
-pub fn write_profile(profile: &Profile) -> Result<(), String> {
+// Profile names become file names, and `foo.lock` would share a file with
+// the lock of `foo`.
+pub fn check_profile_name(profile_name: &str) -> Result<()> {
+    crate::scaffold::path_in(&profiles_dir(), profile_name, ".toml")?;
+    if profile_name.ends_with(crate::lock::LOCK_SUFFIX) {
+        return Err(Error::InvalidName {
+            name: profile_name.to_string(),
+            reason: format!(
+                "profile names cannot end in '{}'",
+                crate::lock::LOCK_SUFFIX
+            ),
+        });
+    }
+    Ok(())
+}
+
+pub fn write_profile(profile: &Profile) -> Result<()> {
+    check_profile_name(&profile.name)?;
     let path = profile_path(&profile.name);
     log::debug!(
         "writing profile '{}' ({} packages) to {:?}",
@@ -83,149 +135,457 @@ pub fn write_profile(profile: &Profile) -> Result<(), String> {
         profile.packages.len(),
         path
     );
-    let toml_str = toml::to_string(profile).map_err(|e| e.to_string())?;
-    fs::write(path, toml_str).map_err(|e| e.to_string())
+    let toml_str =
+        toml::to_string(profile).map_err(Error::serialize("profile"))?;
+    fs::write(&path, toml_str)
+        .map_err(Error::io(format!("Failed to write profile {:?}", path)))
+}
+
+// Local edits to a subscribed profile only last until the next sync.
+pub(crate) fn warn_if_subscribed(profile: &Profile) {
+    if let Some(origin) = &profile.origin {
+        note!(
+            "{} '{}' is subscribed to {}; `blazinit sync` will overwrite this change",
+            "Warning:".yellow().bold(),
+            profile.name.cyan(),
+            origin
+        );
+    }
 }
 
-pub fn add_package_to_profile(
+#[derive(Debug, Clone, Default)]
+pub struct AddOptions {
+    pub installer: Option<String>,
+    pub version: Option<String>,
+    // Skip packages the profile already has instead of failing.
+    pub ignore_existing: bool,
+    // Also add every registry dependency of the packages as an entry.
+    pub with_deps: bool,
+    pub tags: Vec<String>,
+}
+
+// An unknown name is an error, unless the user is at a terminal and picks
+// one of the close matches instead.
+fn resolve_package(package_name: &str) -> Result<String> {
+    log::debug!("checking package '{}' in registry", package_name);
+    if crate::registry::is_package_in_registry(package_name)? {
+        return Ok(package_name.to_string());
+    }
+    log::error!("package '{}' not found in registry", package_name);
+    let error = crate::registry::package_not_found(package_name);
+    if let Error::PackageNotFound { suggestions, .. } = &error
+        && !suggestions.is_empty()
+        && crate::suggest::can_prompt()
+    {
+        let question = format!(
+            "Package '{}' is not in the registry. Did you mean:",
+            package_name
+        );
+        if let Some(choice) = crate::suggest::pick(&question, suggestions)? {
+            return Ok(choice);
+        }
+    }
+    Err(error)
+}
+
+// Either every package is added or, when one is unknown or already present,
+// none is.
+pub fn add_packages_to_profile(
     profile_name: &str,
-    package_name: &str,
-    installer: Option<String>,
-) -> Result<(), String> {
+    package_names: &[String],
+    options: &AddOptions,
+) -> Result<()> {
     log::debug!(
-        "adding package '{}' to profile '{}' (installer={:?})",
-        package_name,
+        "adding packages {:?} to profile '{}' ({:?})",
+        package_names,
         profile_name,
-        installer
+        options
     );
+    if let Some(req) = &options.version {
+        if package_names.len() > 1 {
+            return Err(Error::Unsupported(
+                "--version can only be given for a single package".to_string(),
+            ));
+        }
+        VersionReq::parse(req)?;
+    }
     let mut profile = read_profile(profile_name)?;
 
-    if profile.packages.iter().any(|p| p.name == package_name) {
-        log::warn!(
-            "package '{}' already present in profile '{}'",
-            package_name,
-            profile_name
-        );
-        return Err(format!(
-            "Package '{}' is already present in profile '{}'",
-            package_name, profile_name
-        ));
+    let mut names = Vec::new();
+    let mut added = Vec::new();
+    let mut skipped = Vec::new();
+    for requested in package_names {
+        let in_profile = |name: &str| {
+            profile.packages.iter().any(|p| p.name == name)
+                || added.iter().any(|a| a == name)
+        };
+        let package_name = &if in_profile(requested) {
+            requested.clone()
+        } else {
+            resolve_package(requested)?
+        };
+        let present = in_profile(package_name);
+        if present && !options.ignore_existing {
+            log::warn!(
+                "package '{}' already present in profile '{}'",
+                package_name,
+                profile_name
+            );
+            return Err(Error::PackageExists {
+                package: package_name.clone(),
+                profile: profile_name.to_string(),
+            });
+        }
+        names.push(package_name.clone());
+        if present {
+            skipped.push(package_name.clone());
+        } else {
+            added.push(package_name.clone());
+        }
     }
-
-    log::debug!("checking package '{}' in registry", package_name);
-    if !crate::registry::is_package_in_registry(package_name)? {
-        log::error!("package '{}' not found in registry", package_name);
-        return Err(format!(
-            "Package '{}' not found in registry",
-            package_name
-        ));
+    let mut deps = Vec::new();
+    if options.with_deps {
+        for dep in crate::registry::dependency_closure(&names)? {
+            let present = profile.packages.iter().any(|p| p.name == dep);
+            if !present && !names.contains(&dep) {
+                deps.push(dep);
+            }
+        }
     }
 
-    profile.packages.push(PackageRef {
-        name: package_name.to_string(),
-        installer,
-    });
+    for name in &added {
+        profile.packages.push(PackageRef {
+            name: name.clone(),
+            installer: options.installer.clone(),
+            version: options.version.clone(),
+            tags: options.tags.clone(),
+        });
+    }
+    for name in &deps {
+        profile.packages.push(PackageRef {
+            name: name.clone(),
+            ..Default::default()
+        });
+    }
+    // With --ignore-existing, entries already in the profile still pick up
+    // the requested tags.
+    let mut tagged = Vec::new();
+    for name in &skipped {
+        let Some(existing) =
+            profile.packages.iter_mut().find(|p| &p.name == name)
+        else {
+            continue;
+        };
+        let missing: Vec<String> = options
+            .tags
+            .iter()
+            .filter(|t| !existing.tags.contains(t))
+            .cloned()
+            .collect();
+        if !missing.is_empty() {
+            existing.tags.extend(missing);
+            tagged.push(name.clone());
+        }
+    }
     profile.packages.sort_by(|a, b| a.name.cmp(&b.name));
     write_profile(&profile)?;
+    warn_if_subscribed(&profile);
 
     log::info!(
-        "added package '{}' to profile '{}'",
-        package_name,
+        "added {:?} and dependencies {:?} to profile '{}'",
+        added,
+        deps,
         profile_name
     );
-    println!("Adding to profile {}:", profile_name.cyan().bold());
-    println!("  {} {}", "+".green().bold(), package_name.cyan());
-    println!("{}", "Successfully added 1 package.".green());
+    say!("Adding to profile {}:", profile_name.cyan().bold());
+    for name in &added {
+        say!("  {} {}", "+".green().bold(), name.cyan());
+    }
+    for name in &deps {
+        say!(
+            "  {} {} {}",
+            "+".green().bold(),
+            name.cyan(),
+            "(dependency)".dimmed()
+        );
+    }
+    for name in &skipped {
+        let note = if tagged.contains(name) {
+            "(already present, tagged)"
+        } else {
+            "(already present)"
+        };
+        say!("  {} {} {}", "=".dimmed(), name.cyan(), note.dimmed());
+    }
+    let count = added.len() + deps.len();
+    say!(
+        "{}",
+        format!(
+            "Successfully added {} package{}.",
+            count,
+            if count == 1 { "" } else { "s" }
+        )
+        .green()
+    );
 
     Ok(())
 }
 
-pub fn show_profile(profile_name: &str) -> Result<(), String> {
-    let p = read_profile(profile_name)
-        .map_err(|e| format!("Failed to read profile: {}", e))?;
-    println!("{} {}", "Profile:".bold(), p.name.cyan().bold());
-    if p.packages.is_empty() {
-        println!("  {}", "No packages in this profile.".dimmed());
+#[derive(Serialize, Debug, Clone)]
+pub struct PackageView {
+    pub name: String,
+    pub display: String,
+    pub installer: Option<String>,
+    pub version: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct ProfileView {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub origin: Option<String>,
+    pub packages: Vec<PackageView>,
+}
+
+pub fn profile_view(profile_name: &str) -> Result<ProfileView> {
+    let p = read_profile(profile_name)?;
+    Ok(ProfileView {
+        name: p.name.clone(),
+        origin: p.origin.clone(),
+        packages: p
+            .packages
+            .iter()
+            .map(|pkg_ref| PackageView {
+                name: pkg_ref.name.clone(),
+                display: crate::registry::get_package_details(&pkg_ref.name)
+                    .ok()
+                    .and_then(|d| d.display)
+                    .unwrap_or_else(|| pkg_ref.name.clone()),
+                installer: pkg_ref.installer.clone(),
+                version: pkg_ref.version.clone(),
+                tags: pkg_ref.tags.clone(),
+            })
+            .collect(),
+    })
+}
+
+pub fn show_profile(profile_name: &str) -> Result<()> {
+    let view = profile_view(profile_name)?;
+    if output::is_structured() {
+        return output::emit(&view);
+    }
+
+    say!("{} {}", "Profile:".bold(), view.name.cyan().bold());
+    if let Some(origin) = &view.origin {
+        say!(
+            "  {} {} {}",
+            "Subscribed to".bold(),
+            origin.cyan(),
+            "(managed remotely; local edits are overwritten by `sync`)"
+                .dimmed()
+        );
+    }
+    if view.packages.is_empty() {
+        say!("  {}", "No packages in this profile.".dimmed());
     } else {
-        println!("  {}", "Packages:".bold());
-        for pkg_ref in &p.packages {
-            let display = crate::registry::get_package_details(&pkg_ref.name)
-                .ok()
-                .and_then(|d| d.display)
-                .unwrap_or_else(|| pkg_ref.name.clone());
+        say!("  {}", "Packages:".bold());
+        for pkg_ref in &view.packages {
+            let display = &pkg_ref.display;
+            let mut pins = Vec::new();
+            if let Some(version) = &pkg_ref.version {
+                pins.push(format!("version: {}", version));
+            }
             if let Some(installer) = &pkg_ref.installer {
-                println!(
+                pins.push(format!("installer: {}", installer));
+            }
+            if !pkg_ref.tags.is_empty() {
+                pins.push(format!("tags: {}", pkg_ref.tags.join(", ")));
+            }
+            if pins.is_empty() {
+                say!("  - {}", display.cyan());
+            } else {
+                say!(
                     "  - {} {}",
                     display.cyan(),
-                    format!("(installer: {})", installer).dimmed()
+                    format!("({})", pins.join(", ")).dimmed()
                 );
-            } else {
-                println!("  - {}", display.cyan());
             }
         }
     }
     Ok(())
 }
 
-pub fn remove_package_from_profile(
+// Profile members that need one of `removed`, directly or through other
+// registry dependencies, mapped to the removed packages they need.
+fn dependents(
+    members: &[PackageRef],
+    removed: &[String],
+) -> BTreeMap<String, Vec<String>> {
+    members
+        .iter()
+        .filter_map(|member| {
+            let closure = crate::registry::dependency_closure(
+                std::slice::from_ref(&member.name),
+            )
+            .unwrap_or_default();
+            let needed: Vec<String> = removed
+                .iter()
+                .filter(|r| **r != member.name && closure.contains(*r))
+                .cloned()
+                .collect();
+            (!needed.is_empty()).then(|| (member.name.clone(), needed))
+        })
+        .collect()
+}
+
+// Removing a package other members depend on does not stop it from being
+// installed with them, which is worth a warning. With `cascade` those
+// members are removed as well.
+pub fn remove_packages_from_profile(
     profile_name: &str,
-    package_name: &str,
-) -> Result<(), String> {
+    package_names: &[String],
+    cascade: bool,
+) -> Result<()> {
     log::debug!(
-        "removing package '{}' from profile '{}'",
-        package_name,
-        profile_name
+        "removing packages {:?} from profile '{}' (cascade={})",
+        package_names,
+        profile_name,
+        cascade
     );
     let mut profile = read_profile(profile_name)?;
 
-    let initial_len = profile.packages.len();
-    profile.packages.retain(|pkg| pkg.name != package_name);
+    for package_name in package_names {
+        if !profile.packages.iter().any(|p| &p.name == package_name) {
+            log::warn!(
+                "package '{}' not found in profile '{}'",
+                package_name,
+                profile_name
+            );
+            return Err(Error::PackageNotInProfile {
+                package: package_name.clone(),
+                profile: profile_name.to_string(),
+            });
+        }
+    }
 
-    if profile.packages.len() == initial_len {
-        log::warn!(
-            "package '{}' not found in profile '{}'",
-            package_name,
-            profile_name
-        );
-        return Err(format!(
-            "Package '{}' is not present in profile '{}'",
-            package_name, profile_name
-        ));
+    let mut removed = package_names.to_vec();
+    let mut cascaded = Vec::new();
+    loop {
+        profile.packages.retain(|p| !removed.contains(&p.name));
+        let needing = dependents(&profile.packages, &removed);
+        if needing.is_empty() {
+            break;
+        }
+        if !cascade {
+            for (member, needed) in &needing {
+                note!(
+                    "{} '{}' depends on {}; it will still be installed as a dependency",
+                    "Warning:".yellow().bold(),
+                    member.cyan(),
+                    needed
+                        .iter()
+                        .map(|n| format!("'{}'", n))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                );
+            }
+            break;
+        }
+        for member in needing.into_keys() {
+            cascaded.push(member.clone());
+            removed.push(member);
+        }
     }
 
     write_profile(&profile)?;
+    warn_if_subscribed(&profile);
     log::info!(
-        "removed package '{}' from profile '{}'",
-        package_name,
+        "removed {:?} (cascaded {:?}) from profile '{}'",
+        package_names,
+        cascaded,
         profile_name
     );
-    println!(
-        "{} '{}' from profile '{}'",
-        "Successfully removed".green(),
-        package_name.cyan(),
-        profile_name.cyan()
+    say!("Removing from profile {}:", profile_name.cyan().bold());
+    for name in package_names {
+        say!("  {} {}", "-".red().bold(), name.cyan());
+    }
+    for name in &cascaded {
+        say!(
+            "  {} {} {}",
+            "-".red().bold(),
+            name.cyan(),
+            "(depends on a removed package)".dimmed()
+        );
+    }
+    let count = removed.len();
+    say!(
+        "{}",
+        format!(
+            "Successfully removed {} package{}.",
+            count,
+            if count == 1 { "" } else { "s" }
+        )
+        .green()
     );
 
     Ok(())
 }
 
+// A profile file with the custom registry definitions it needs embedded
+// under `[registry.<package>]`, so it can be imported where those packages
+// are unknown.
+fn bundle_content(profile_name: &str) -> Result<String> {
+    let profile = read_profile(profile_name)?;
+    let names: Vec<String> =
+        profile.packages.iter().map(|p| p.name.clone()).collect();
+    let definitions = crate::registry::custom_definitions(&names)?;
+    log::info!(
+        "bundling {} registry packages with profile '{}'",
+        definitions.len(),
+        profile_name
+    );
+
+    let mut bundle =
+        toml::Table::try_from(&profile).map_err(Error::serialize("profile"))?;
+    if !definitions.is_empty() {
+        let registry = definitions
+            .into_iter()
+            .map(|(name, table)| (name, toml::Value::Table(table)))
+            .collect();
+        bundle.insert("registry".to_string(), toml::Value::Table(registry));
+    }
+    toml::to_string(&bundle).map_err(Error::serialize("profile bundle"))
+}
+
 pub fn export_profile(
     profile_name: &str,
     file: &Option<String>,
-) -> Result<(), String> {
+    bundle: bool,
+) -> Result<()> {
     let src = profile_path(profile_name);
     log::debug!("exporting profile '{}' from {:?}", profile_name, src);
     if !src.exists() {
         log::error!("export failed: profile '{}' does not exist", profile_name);
-        return Err(format!("Profile '{}' does not exist", profile_name));
+        return Err(profile_not_found(profile_name));
     }
 
+    let bundled = if bundle {
+        Some(bundle_content(profile_name)?)
+    } else {
+        None
+    };
+
     match file {
         Some(dest) => {
-            fs::copy(&src, dest)
-                .map_err(|e| format!("Failed to export profile: {}", e))?;
+            match bundled {
+                Some(content) => fs::write(dest, content),
+                None => fs::copy(&src, dest).map(|_| ()),
+            }
+            .map_err(Error::io("Failed to export profile"))?;
             log::info!("exported profile '{}' to '{}'", profile_name, dest);
-            println!(
+            say!(
                 "{} '{}' exported to '{}'",
                 "Profile".green(),
                 profile_name.cyan(),
@@ -234,8 +594,11 @@ pub fn export_profile(
         }
         None => {
             log::debug!("exporting profile '{}' to stdout", profile_name);
-            let content = fs::read_to_string(&src)
-                .map_err(|e| format!("Failed to read profile: {}", e))?;
+            let content = match bundled {
+                Some(content) => content,
+                None => fs::read_to_string(&src)
+                    .map_err(Error::io("Failed to read profile"))?,
+            };
             print!("{}", content);
         }
     }
@@ -243,72 +606,425 @@ pub fn export_profile(
     Ok(())
 }
 
-pub fn import_profile(file: &str) -> Result<(), String> {
-    log::debug!("importing profile from '{}'", file);
-    let content = fs::read_to_string(file)
-        .map_err(|e| format!("Failed to read file '{}': {}", file, e))?;
+// Custom commands run as-is on every machine that installs the profile, so
+// the ones a bundle brings along are shown and must be trusted first.
+fn trust_custom_commands(
+    profile_name: &str,
+    registry: &toml::Table,
+    trust: bool,
+) -> Result<()> {
+    let commands: Vec<(&String, &str)> = registry
+        .iter()
+        .filter_map(|(name, value)| {
+            Some((name, value.get("packages")?.get("custom")?.as_str()?))
+        })
+        .collect();
+    if commands.is_empty() || trust {
+        return Ok(());
+    }
+    note!(
+        "{} '{}' bundles custom install commands:",
+        "Warning:".yellow().bold(),
+        profile_name.cyan()
+    );
+    for (name, command) in &commands {
+        note!("  {}: {}", name.cyan(), command);
+    }
+    if crate::suggest::can_prompt()
+        && crate::suggest::confirm("Trust these commands?")?
+    {
+        return Ok(());
+    }
+    Err(Error::UntrustedCommands {
+        profile: profile_name.to_string(),
+        packages: commands.iter().map(|(name, _)| name.to_string()).collect(),
+    })
+}
+
+// Bundled definitions go into a registry source named after the profile.
+// A package the local registry already defines differently keeps the local
+// definition, which is worth pointing out.
+pub(crate) fn import_definitions(
+    profile_name: &str,
+    registry: &toml::Table,
+    trust: bool,
+) -> Result<usize> {
+    trust_custom_commands(profile_name, registry, trust)?;
+    let mut definitions = BTreeMap::new();
+    for (name, value) in registry {
+        let table = value.as_table().ok_or_else(|| Error::RegistryParse {
+            file: format!("{}.toml", name),
+            line: None,
+            message: "bundled definition is not a table".to_string(),
+        })?;
+        let local = crate::scaffold::path_in(
+            &crate::registry::registry_dir(),
+            name,
+            ".toml",
+        )?;
+        let shadowed = fs::read_to_string(&local)
+            .ok()
+            .and_then(|c| toml::from_str::<toml::Table>(&c).ok())
+            .is_some_and(|existing| &existing != table);
+        if shadowed {
+            note!(
+                "{} the registry already defines '{}' differently; keeping the local definition",
+                "Warning:".yellow().bold(),
+                name.cyan()
+            );
+        }
+        definitions.insert(name.clone(), table.clone());
+    }
+    crate::registry::install_source(profile_name, &definitions)?;
+    Ok(definitions.len())
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ImportOptions {
+    // Expected SHA-256 of the file, checked before anything is written.
+    pub sha256: Option<String>,
+    // Import under this name instead of the one in the file.
+    pub name: Option<String>,
+    pub force: bool,
+    // Accept bundled custom commands without asking.
+    pub trust: bool,
+}
+
+// A profile file read from a path, URL or git repository, with the registry
+// definitions of a bundle split off.
+pub struct FetchedProfile {
+    pub profile: Profile,
+    pub registry: Option<toml::Table>,
+    pub content: String,
+}
+
+pub fn fetch_profile(
+    source: &str,
+    sha256: Option<&str>,
+) -> Result<FetchedProfile> {
+    let content = crate::fetch::fetch(&crate::fetch::Source::parse(source)?)?;
+    if let Some(expected) = sha256 {
+        crate::fetch::verify_sha256(source, &content, expected)?;
+    }
+
+    let invalid = |e| Error::InvalidFile {
+        kind: "profile",
+        path: PathBuf::from(source),
+        source: Box::new(e),
+    };
+    let profile: Profile = toml::from_str(&content).map_err(invalid)?;
+    let mut bundle: toml::Table = toml::from_str(&content).map_err(invalid)?;
+    let registry = match bundle.remove("registry") {
+        Some(toml::Value::Table(registry)) => Some(registry),
+        _ => None,
+    };
+    Ok(FetchedProfile {
+        profile,
+        registry,
+        content,
+    })
+}
 
-    let profile: Profile = toml::from_str(&content)
-        .map_err(|e| format!("Invalid profile file: {}", e))?;
+pub fn import_profile(file: &str, options: &ImportOptions) -> Result<()> {
+    log::debug!("importing profile from '{}'", file);
+    let FetchedProfile {
+        mut profile,
+        registry,
+        content,
+    } = fetch_profile(file, options.sha256.as_deref())?;
+    let renamed = options.name.as_ref().is_some_and(|n| *n != profile.name);
+    if let Some(name) = &options.name {
+        profile.name = name.clone();
+    }
 
+    check_profile_name(&profile.name)?;
     let dest = profile_path(&profile.name);
-    std::fs::OpenOptions::new()
-        .write(true)
-        .create_new(true)
-        .open(&dest)
-        .and_then(|mut f| std::io::Write::write_all(&mut f, content.as_bytes()))
-        .map_err(|_| {
-            log::error!("import failed: profile '{}' already exists", profile.name);
-            format!(
-                "Profile '{}' already exists. Delete it first or rename the import file.",
-                profile.name
-            )
-        })?;
+    if dest.exists() && !options.force {
+        log::error!("import failed: profile '{}' already exists", profile.name);
+        return Err(Error::ProfileExists(profile.name.clone()));
+    }
+    // The file is kept verbatim unless it has to change.
+    let content = if renamed || registry.is_some() {
+        toml::to_string(&profile).map_err(Error::serialize("profile"))?
+    } else {
+        content
+    };
+    let bundled = match registry {
+        Some(registry) => {
+            import_definitions(&profile.name, &registry, options.trust)?
+        }
+        None => 0,
+    };
+    fs::write(&dest, content)
+        .map_err(Error::io(format!("Failed to write profile {:?}", dest)))?;
+    // A lock of the replaced profile says nothing about the imported one.
+    let lock = crate::lock::lock_path(&profile.name);
+    if lock.exists() {
+        fs::remove_file(&lock).map_err(Error::io(format!(
+            "Failed to delete lock file {:?}",
+            lock
+        )))?;
+        note!(
+            "{} removed the lock file of the replaced profile; run `blazinit lock {}` to write a new one",
+            "Warning:".yellow().bold(),
+            profile.name
+        );
+    }
 
     log::info!("imported profile '{}' from '{}'", profile.name, file);
-    println!(
+    say!(
         "{} '{}'.",
         "Profile imported successfully:".green(),
         profile.name.cyan()
     );
+    if bundled > 0 {
+        say!(
+            "{} {} bundled packages to the registry.",
+            "Added".green(),
+            bundled
+        );
+    }
     Ok(())
 }
 
-pub fn install_profile(
-    profile_name: &str,
-    force: bool,
-    installer: &Option<String>,
-    dry_run: bool,
-) -> Result<(), String> {
+#[derive(Debug, Clone, Default)]
+pub struct InstallOptions {
+    // Reinstall packages that are already detected.
+    pub force: bool,
+    pub installer: Option<String>,
+    pub dry_run: bool,
+    pub locked: bool,
+    // Install only these profile packages, only those carrying one of
+    // `tags`, and none of `except`; what they depend on comes along.
+    pub only: Option<Vec<String>>,
+    pub except: Vec<String>,
+    pub tags: Vec<String>,
+}
+
+impl InstallOptions {
+    fn selects(&self) -> bool {
+        self.only.is_some() || !self.except.is_empty() || !self.tags.is_empty()
+    }
+}
+
+// The packages of `profile` the options select, followed by the
+// dependencies they need that were not selected themselves, and the names
+// of those dependencies.
+pub fn select_packages_for_install(
+    profile: &Profile,
+    options: &InstallOptions,
+) -> Result<(Vec<PackageRef>, Vec<String>)> {
+    if !options.selects() {
+        return Ok((profile.packages.clone(), Vec::new()));
+    }
+    let named = options.only.iter().flatten().chain(&options.except);
+    for name in named {
+        if !profile.packages.iter().any(|p| &p.name == name) {
+            return Err(Error::PackageNotInProfile {
+                package: name.clone(),
+                profile: profile.name.clone(),
+            });
+        }
+    }
+    let mut known: Vec<&String> =
+        profile.packages.iter().flat_map(|p| &p.tags).collect();
+    known.sort();
+    known.dedup();
+    for tag in &options.tags {
+        if !known.contains(&tag) {
+            return Err(Error::TagNotFound {
+                tag: tag.clone(),
+                profile: profile.name.clone(),
+                suggestions: crate::suggest::similar(
+                    tag,
+                    known.iter().map(|t| (*t, t.as_str())),
+                ),
+            });
+        }
+    }
+
+    let mut selected: Vec<PackageRef> = profile
+        .packages
+        .iter()
+        .filter(|p| options.only.as_ref().is_none_or(|o| o.contains(&p.name)))
+        .filter(|p| {
+            options.tags.is_empty()
+                || p.tags.iter().any(|t| options.tags.contains(t))
+        })
+        .filter(|p| !options.except.contains(&p.name))
+        .cloned()
+        .collect();
+    let dependencies: Vec<String> =
+        crate::installer::topological_sort(&selected)?
+            .into_iter()
+            .filter(|name| !selected.iter().any(|p| &p.name == name))
+            .collect();
+    // A dependency the profile lists keeps its pins.
+    for name in &dependencies {
+        selected.push(
+            profile
+                .packages
+                .iter()
+                .find(|p| &p.name == name)
+                .cloned()
+                .unwrap_or_else(|| PackageRef {
+                    name: name.clone(),
+                    ..Default::default()
+                }),
+        );
+    }
     log::info!(
-        "installing profile '{}' (force={}, dry_run={}, installer={:?})",
-        profile_name,
-        force,
-        dry_run,
-        installer
+        "selected {} packages of '{}', dependencies {:?}",
+        selected.len(),
+        profile.name,
+        dependencies
     );
+    Ok((selected, dependencies))
+}
+
+// Several profiles are installed as one: their packages are deduplicated
+// and ordered together, and the run ends with a single summary.
+pub fn install_profiles(
+    profile_names: &[String],
+    options: &InstallOptions,
+    runner: &dyn CommandRunner,
+    reporter: &dyn Reporter,
+) -> Result<InstallReport> {
+    log::info!("installing profiles {:?} ({:?})", profile_names, options);
+    let profiles = profile_names
+        .iter()
+        .map(|name| read_profile(name))
+        .collect::<Result<Vec<_>>>()?;
+    let lock = if options.locked {
+        Some(crate::lock::read_locks(&profiles)?)
+    } else {
+        None
+    };
+    let mut profile = crate::merge::union_profiles(&profiles)?;
+    let (packages, dependencies) =
+        select_packages_for_install(&profile, options)?;
+    profile.packages = packages;
+    if !dependencies.is_empty() {
+        reporter.report(&Event::DependenciesAdded {
+            names: &dependencies,
+        });
+    }
+    let recording;
+    let runner = if options.dry_run {
+        recording = RecordingRunner::new(runner);
+        &recording
+    } else {
+        runner
+    };
+    let detected = crate::installer::detect_available_installer(runner);
+    let mut report = crate::installer::run_install(
+        &profile,
+        options.force,
+        &options.installer,
+        detected.as_deref(),
+        lock.as_ref(),
+        runner,
+        reporter,
+    )?;
+    report.dependencies = dependencies;
+    Ok(report)
+}
+
+fn picker_items(names: &[String]) -> Result<Vec<crate::picker::Item>> {
+    let entries = crate::registry::find_packages(&None)?;
+    Ok(names
+        .iter()
+        .map(|name| crate::picker::Item {
+            name: name.clone(),
+            display: entries
+                .iter()
+                .find(|e| &e.name == name)
+                .and_then(|e| e.display.clone()),
+        })
+        .collect())
+}
+
+// The packages of the profiles the user keeps ticked, or None when
+// cancelled.
+pub fn pick_for_install(
+    profile_names: &[String],
+) -> Result<Option<Vec<String>>> {
+    let profiles = profile_names
+        .iter()
+        .map(|name| read_profile(name))
+        .collect::<Result<Vec<_>>>()?;
+    let profile = crate::merge::union_profiles(&profiles)?;
+    let names: Vec<String> =
+        profile.packages.iter().map(|p| p.name.clone()).collect();
+    crate::picker::multi_select(
+        &format!("Install from {}", profile.name),
+        &picker_items(&names)?,
+        &names,
+    )
+}
+
+// Every registry package with the profile's own ticked; ticking adds a
+// package and unticking removes it.
+pub fn pick_packages(profile_name: &str, options: &AddOptions) -> Result<()> {
     let profile = read_profile(profile_name)?;
-    crate::installer::run_install(&profile, force, installer, dry_run)
+    let current: Vec<String> =
+        profile.packages.iter().map(|p| p.name.clone()).collect();
+    let mut names: Vec<String> = crate::registry::find_packages(&None)?
+        .into_iter()
+        .map(|e| e.name)
+        .collect();
+    names.sort();
+    let Some(chosen) = crate::picker::multi_select(
+        &format!("Packages in {}", profile_name),
+        &picker_items(&names)?,
+        &current,
+    )?
+    else {
+        say!("{}", "Cancelled.".yellow());
+        return Ok(());
+    };
+
+    let added: Vec<String> = chosen
+        .iter()
+        .filter(|name| !current.contains(name))
+        .cloned()
+        .collect();
+    let removed: Vec<String> = current
+        .iter()
+        .filter(|name| !chosen.contains(name))
+        .cloned()
+        .collect();
+    log::info!("picked for '{}': +{:?} -{:?}", profile_name, added, removed);
+    if added.is_empty() && removed.is_empty() {
+        say!("{}", "No changes.".dimmed());
+        return Ok(());
+    }
+    if !added.is_empty() {
+        add_packages_to_profile(profile_name, &added, options)?;
+    }
+    if !removed.is_empty() {
+        remove_packages_from_profile(profile_name, &removed, false)?;
+    }
+    Ok(())
 }
 
-pub fn create_profile(profile_name: &str) -> Result<(), String> {
+pub fn create_profile(profile_name: &str) -> Result<()> {
+    check_profile_name(profile_name)?;
     let path = profile_path(profile_name);
     log::debug!("creating profile '{}' at {:?}", profile_name, path);
 
     if path.exists() {
         log::warn!("create failed: profile '{}' already exists", profile_name);
-        return Err(format!("Profile '{}' already exists", profile_name));
+        return Err(Error::ProfileExists(profile_name.to_string()));
     }
 
-    let profile = Profile {
+    write_profile(&Profile {
         name: profile_name.to_string(),
+        origin: None,
         packages: Vec::new(),
-    };
-
-    let toml_str = toml::to_string(&profile).map_err(|e| e.to_string())?;
-    fs::write(path, toml_str).map_err(|e| e.to_string())?;
+    })?;
     log::info!("profile '{}' created", profile_name);
-    println!(
+    say!(
         "{} '{}'.",
         "Successfully created profile".green(),
         profile_name.cyan()
@@ -317,14 +1033,11 @@ pub fn create_profile(profile_name: &str) -> Result<(), String> {
     Ok(())
 }
 
-pub fn delete_profile(profile_name: &str) -> Result<(), String> {
+pub fn delete_profile(profile_name: &str) -> Result<()> {
     let default_profile = config::get_default_profile();
     if profile_name == default_profile {
         log::error!("delete failed: '{}' is the default profile", profile_name);
-        return Err(format!(
-            "Cannot delete the default profile '{}'",
-            default_profile
-        ));
+        return Err(Error::DefaultProfile(default_profile));
     }
 
     let path = profile_path(profile_name);
@@ -332,12 +1045,20 @@ pub fn delete_profile(profile_name: &str) -> Result<(), String> {
 
     if !path.exists() {
         log::error!("delete failed: profile '{}' does not exist", profile_name);
-        return Err(format!("Profile '{}' does not exist", profile_name));
+        return Err(profile_not_found(profile_name));
     }
 
-    fs::remove_file(path).map_err(|e| e.to_string())?;
+    fs::remove_file(&path)
+        .map_err(Error::io(format!("Failed to delete profile {:?}", path)))?;
+    let lock = crate::lock::lock_path(profile_name);
+    if lock.exists() {
+        fs::remove_file(&lock).map_err(Error::io(format!(
+            "Failed to delete lock file {:?}",
+            lock
+        )))?;
+    }
     log::info!("profile '{}' deleted", profile_name);
-    println!(
+    say!(
         "{} '{}'.",
         "Successfully deleted profile".green(),
         profile_name.cyan()
@@ -345,37 +1066,273 @@ pub fn delete_profile(profile_name: &str) -> Result<(), String> {
     Ok(())
 }
 
-pub fn list_profiles() {
+fn move_lock(from: &str, to: &str, keep: bool) -> Result<()> {
+    if !crate::lock::lock_path(from).exists() {
+        return Ok(());
+    }
+    let mut lock = crate::lock::read_lock(from)?;
+    lock.profile = to.to_string();
+    crate::lock::write_lock(&lock)?;
+    if !keep {
+        let old = crate::lock::lock_path(from);
+        fs::remove_file(&old).map_err(Error::io(format!(
+            "Failed to delete lock file {:?}",
+            old
+        )))?;
+    }
+    Ok(())
+}
+
+// A copy is a plain local profile: it does not follow the source's origin.
+pub fn copy_profile(source: &str, destination: &str) -> Result<()> {
+    let mut profile = read_profile(source)?;
+    if profile_path(destination).exists() {
+        log::error!("copy failed: profile '{}' already exists", destination);
+        return Err(Error::ProfileExists(destination.to_string()));
+    }
+    profile.name = destination.to_string();
+    profile.origin = None;
+    write_profile(&profile)?;
+    move_lock(source, destination, true)?;
+
+    log::info!("profile '{}' copied to '{}'", source, destination);
+    say!(
+        "{} '{}' to '{}'.",
+        "Copied profile".green(),
+        source.cyan(),
+        destination.cyan()
+    );
+    Ok(())
+}
+
+// Everything keyed by the profile name moves along: its lock file, the
+// registry source of a bundle it was imported from, and the default pointer.
+pub fn rename_profile(old: &str, new: &str) -> Result<()> {
+    let mut profile = read_profile(old)?;
+    if profile_path(new).exists() {
+        log::error!("rename failed: profile '{}' already exists", new);
+        return Err(Error::ProfileExists(new.to_string()));
+    }
+    profile.name = new.to_string();
+    write_profile(&profile)?;
+    let old_path = profile_path(old);
+    fs::remove_file(&old_path).map_err(Error::io(format!(
+        "Failed to delete profile {:?}",
+        old_path
+    )))?;
+    move_lock(old, new, false)?;
+
+    let sources = crate::registry::sources_dir();
+    if sources.join(old).is_dir() && !sources.join(new).exists() {
+        fs::rename(sources.join(old), sources.join(new))
+            .map_err(Error::io("Failed to rename registry source"))?;
+    }
+
+    log::info!("profile '{}' renamed to '{}'", old, new);
+    say!(
+        "{} '{}' to '{}'.",
+        "Renamed profile".green(),
+        old.cyan(),
+        new.cyan()
+    );
+    if config::get_default_profile() == old {
+        config::set_default_profile(new)?;
+    }
+    Ok(())
+}
+
+// The checks `add` makes one package at a time, applied to a whole profile.
+pub fn validate_profile(profile: &Profile) -> Result<()> {
+    for (i, package) in profile.packages.iter().enumerate() {
+        if profile.packages[..i].iter().any(|p| p.name == package.name) {
+            return Err(Error::PackageExists {
+                package: package.name.clone(),
+                profile: profile.name.clone(),
+            });
+        }
+        if !crate::registry::is_package_in_registry(&package.name)? {
+            return Err(crate::registry::package_not_found(&package.name));
+        }
+        if let Some(req) = &package.version {
+            VersionReq::parse(req)?;
+        }
+    }
+    Ok(())
+}
+
+fn editor() -> String {
+    env::var("VISUAL")
+        .or_else(|_| env::var("EDITOR"))
+        .unwrap_or_else(|_| {
+            if cfg!(windows) { "notepad" } else { "vi" }.to_string()
+        })
+}
+
+fn check_draft(profile_name: &str, draft: &Path) -> Result<String> {
+    let content = fs::read_to_string(draft)
+        .map_err(Error::io(format!("Failed to read {:?}", draft)))?;
+    let profile: Profile =
+        toml::from_str(&content).map_err(|source| Error::InvalidFile {
+            kind: "profile",
+            path: draft.to_path_buf(),
+            source: Box::new(source),
+        })?;
+    if profile.name != profile_name {
+        return Err(Error::Unsupported(format!(
+            "The profile name cannot be changed while editing; use `blazinit rename {} {}`",
+            profile_name, profile.name
+        )));
+    }
+    validate_profile(&profile)?;
+    Ok(content)
+}
+
+// The editor works on a copy that only replaces the profile once it parses
+// and validates. A rejected copy is left in place so no edits are lost.
+pub fn edit_profile(profile_name: &str) -> Result<()> {
+    let path = profile_path(profile_name);
+    let original = fs::read_to_string(&path).map_err(|e| {
+        if e.kind() == std::io::ErrorKind::NotFound {
+            profile_not_found(profile_name)
+        } else {
+            Error::io(format!("Failed to read profile {:?}", path))(e)
+        }
+    })?;
+    let mut draft = tempfile::Builder::new()
+        .prefix("blazinit-")
+        .suffix(".toml")
+        .tempfile()
+        .map_err(Error::io("Failed to create a draft file"))?;
+    draft
+        .write_all(original.as_bytes())
+        .map_err(Error::io(format!("Failed to write {:?}", draft.path())))?;
+
+    // Through the shell so editors configured with arguments work.
+    let editor = editor();
+    log::debug!("editing {:?} with '{}'", draft.path(), editor);
+    let status = Command::new("sh")
+        .arg("-c")
+        .arg(format!("{} \"$1\"", editor))
+        .arg("sh")
+        .arg(draft.path())
+        .status()
+        .map_err(Error::io(format!("Failed to run editor '{}'", editor)))?;
+    if !status.success() {
+        return Err(Error::Other(format!(
+            "Editor '{}' exited with {}; profile left unchanged",
+            editor, status
+        )));
+    }
+
+    let content = match check_draft(profile_name, draft.path()) {
+        Ok(content) => content,
+        Err(source) => {
+            let (_, kept) = draft.keep().map_err(|e| {
+                Error::io("Failed to keep the rejected draft")(e.error)
+            })?;
+            return Err(Error::EditRejected {
+                profile: profile_name.to_string(),
+                draft: kept,
+                source: Box::new(source),
+            });
+        }
+    };
+    if content == original {
+        say!("{}", "No changes.".dimmed());
+        return Ok(());
+    }
+    fs::write(&path, content)
+        .map_err(Error::io(format!("Failed to write profile {:?}", path)))?;
+    log::info!("profile '{}' edited", profile_name);
+    say!("{} '{}'.", "Saved profile".green(), profile_name.cyan());
+    Ok(())
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct ProfileSummary {
+    pub name: String,
+    pub default: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub origin: Option<String>,
+}
+
+pub fn profile_summaries() -> Vec<ProfileSummary> {
+    let default_profile = config::get_default_profile();
+    profile_names()
+        .into_iter()
+        .map(|name| ProfileSummary {
+            default: name == default_profile,
+            origin: read_profile(&name).ok().and_then(|p| p.origin),
+            name,
+        })
+        .collect()
+}
+
+pub fn list_profiles() -> Result<()> {
+    if output::is_structured() {
+        return output::emit(&profile_summaries());
+    }
     let mut out = std::io::stdout();
     let _ = list_profiles_to(&mut out);
+    Ok(())
+}
+
+pub fn profile_names() -> Vec<String> {
+    let mut names: Vec<String> = fs::read_dir(profiles_dir())
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .filter_map(|entry| {
+                    let path = entry.path();
+                    if path.extension().and_then(|e| e.to_str()) != Some("toml")
+                    {
+                        return None;
+                    }
+                    path.file_stem()
+                        .and_then(|s| s.to_str())
+                        .filter(|n| !n.ends_with(crate::lock::LOCK_SUFFIX))
+                        .map(String::from)
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    names.sort();
+    names
+}
+
+pub fn profile_not_found(profile_name: &str) -> Error {
+    let names = profile_names();
+    Error::ProfileNotFound {
+        name: profile_name.to_string(),
+        suggestions: crate::suggest::similar(
+            profile_name,
+            names.iter().map(|n| (n, n.as_str())),
+        ),
+    }
 }
 
 pub fn list_profiles_to<W: std::io::Write>(
     writer: &mut W,
 ) -> std::io::Result<()> {
-    let path = profiles_dir();
     let default_profile = config::get_default_profile();
+    let names = profile_names();
 
-    let entries = match fs::read_dir(&path) {
-        Ok(entries) => entries,
-        Err(_) => {
-            writeln!(writer, "No profiles found.")?;
-            return Ok(());
-        }
-    };
+    if names.is_empty() {
+        writeln!(writer, "No profiles found.")?;
+        return Ok(());
+    }
 
     writeln!(writer, "Saved profiles:")?;
 
-    for entry in entries {
-        if let Ok(entry) = entry
-            && let Some(name) =
-                entry.path().file_stem().and_then(|s| s.to_str())
-        {
-            if name == default_profile {
-                writeln!(writer, "{} (default)", name.green())?;
-            } else {
-                writeln!(writer, "{}", name)?;
-            }
+    for name in &names {
+        let origin = read_profile(name).ok().and_then(|p| p.origin);
+        let subscribed = origin
+            .map(|o| format!(" {}", format!("(subscribed: {})", o).dimmed()))
+            .unwrap_or_default();
+        if *name == default_profile {
+            writeln!(writer, "{} (default){}", name.green(), subscribed)?;
+        } else {
+            writeln!(writer, "{}{}", name, subscribed)?;
         }
     }
     Ok(())
@@ -442,11 +1399,10 @@ mod tests {
         create_profile(profile_name).unwrap();
         let result = create_profile(profile_name);
 
-        assert!(result.is_err());
-        assert_eq!(
-            result.err().unwrap(),
-            format!("Profile '{}' already exists", profile_name)
-        );
+        assert!(matches!(
+            result,
+            Err(Error::ProfileExists(ref name)) if name == profile_name
+        ));
     }
 
     #[test]
@@ -471,12 +1427,7 @@ mod tests {
 
         let result = delete_profile("default");
         assert!(result.is_err());
-        assert!(
-            result
-                .err()
-                .unwrap()
-                .contains("Cannot delete the default profile")
-        );
+        assert!(matches!(result, Err(Error::DefaultProfile(_))));
 
         // Ensure it still exists
         assert!(profile_path("default").exists());
@@ -487,8 +1438,7 @@ mod tests {
     fn test_delete_non_existent_profile() {
         let _temp = setup_test_env();
         let result = delete_profile("ghost");
-        assert!(result.is_err());
-        assert!(result.err().unwrap().contains("does not exist"));
+        assert!(matches!(result, Err(Error::ProfileNotFound { .. })));
     }
 
     #[test]
@@ -530,8 +1480,22 @@ mod tests {
         let _temp = setup_test_env();
 
         let result = read_profile("non-existent");
-        assert!(result.is_err());
-        assert!(result.err().unwrap().contains("does not exist"));
+        assert!(matches!(result, Err(Error::ProfileNotFound { .. })));
+    }
+
+    #[test]
+    #[serial]
+    fn test_read_profile_suggests_similar_names() {
+        let _temp = setup_test_env();
+        create_profile("work").unwrap();
+        create_profile("personal").unwrap();
+
+        let result = read_profile("wrok");
+        assert!(matches!(
+            result,
+            Err(Error::ProfileNotFound { ref suggestions, .. })
+                if suggestions == &["work"]
+        ));
     }
 
     #[test]
@@ -541,9 +1505,12 @@ mod tests {
 
         let profile = Profile {
             name: "write-test".to_string(),
+            origin: None,
             packages: vec![PackageRef {
                 name: "test-package".to_string(),
                 installer: None,
+                version: None,
+                tags: Vec::new(),
             }],
         };
 
@@ -577,8 +1544,7 @@ mod tests {
         let _temp = setup_test_env();
 
         let result = show_profile("non-existent");
-        assert!(result.is_err());
-        assert!(result.err().unwrap().contains("Failed to read profile"));
+        assert!(matches!(result, Err(Error::ProfileNotFound { .. })));
     }
 
     #[test]
@@ -602,10 +1568,12 @@ mod tests {
 
         create_profile(profile_name).unwrap();
 
-        let result =
-            add_package_to_profile(profile_name, "nonexistent-pkg", None);
-        assert!(result.is_err());
-        assert!(result.err().unwrap().contains("not found in registry"));
+        let result = add_packages_to_profile(
+            profile_name,
+            &["nonexistent-pkg".to_string()],
+            &AddOptions::default(),
+        );
+        assert!(matches!(result, Err(Error::PackageNotFound { .. })));
     }
 
     #[test]
@@ -613,10 +1581,12 @@ mod tests {
     fn test_add_package_to_profile_non_existent_profile() {
         let _temp = setup_test_env();
 
-        let result =
-            add_package_to_profile("non-existent", "some-package", None);
-        assert!(result.is_err());
-        assert!(result.err().unwrap().contains("does not exist"));
+        let result = add_packages_to_profile(
+            "non-existent",
+            &["some-package".to_string()],
+            &AddOptions::default(),
+        );
+        assert!(matches!(result, Err(Error::ProfileNotFound { .. })));
     }
 
     #[test]
@@ -627,21 +1597,30 @@ mod tests {
 
         let profile = Profile {
             name: profile_name.to_string(),
+            origin: None,
             packages: vec![
                 PackageRef {
                     name: "package1".to_string(),
                     installer: None,
+                    version: None,
+                    tags: Vec::new(),
                 },
                 PackageRef {
                     name: "package2".to_string(),
                     installer: None,
+                    version: None,
+                    tags: Vec::new(),
                 },
             ],
         };
         write_profile(&profile).unwrap();
 
         // Remove one package
-        let result = remove_package_from_profile(profile_name, "package1");
+        let result = remove_packages_from_profile(
+            profile_name,
+            &["package1".to_string()],
+            false,
+        );
         assert!(result.is_ok());
 
         // Verify package was removed
@@ -660,12 +1639,18 @@ mod tests {
         create_profile(profile_name).unwrap();
 
         // Try to remove non-existent package
-        let result = remove_package_from_profile(profile_name, "non-existent");
-        assert!(result.is_err());
-        let error_msg = result.err().unwrap();
-        assert!(error_msg.contains("is not present in profile"));
-        assert!(error_msg.contains("non-existent"));
-        assert!(error_msg.contains(profile_name));
+        let result = remove_packages_from_profile(
+            profile_name,
+            &["non-existent".to_string()],
+            false,
+        );
+        match result {
+            Err(Error::PackageNotInProfile { package, profile }) => {
+                assert_eq!(package, "non-existent");
+                assert_eq!(profile, profile_name);
+            }
+            other => panic!("expected PackageNotInProfile, got {:?}", other),
+        }
     }
 
     #[test]
@@ -673,10 +1658,12 @@ mod tests {
     fn test_remove_package_from_non_existent_profile() {
         let _temp = setup_test_env();
 
-        let result =
-            remove_package_from_profile("non-existent", "some-package");
-        assert!(result.is_err());
-        assert!(result.err().unwrap().contains("does not exist"));
+        let result = remove_packages_from_profile(
+            "non-existent",
+            &["some-package".to_string()],
+            false,
+        );
+        assert!(matches!(result, Err(Error::ProfileNotFound { .. })));
     }
 
     #[test]
@@ -687,21 +1674,31 @@ mod tests {
 
         let profile = Profile {
             name: profile_name.to_string(),
+            origin: None,
             packages: vec![PackageRef {
                 name: "brew".to_string(),
                 installer: None,
+                version: None,
+                tags: Vec::new(),
             }],
         };
         write_profile(&profile).unwrap();
 
         // First removal should succeed
-        let result = remove_package_from_profile(profile_name, "brew");
+        let result = remove_packages_from_profile(
+            profile_name,
+            &["brew".to_string()],
+            false,
+        );
         assert!(result.is_ok());
 
         // Second removal should fail
-        let result = remove_package_from_profile(profile_name, "brew");
-        assert!(result.is_err());
-        assert!(result.err().unwrap().contains("is not present in profile"));
+        let result = remove_packages_from_profile(
+            profile_name,
+            &["brew".to_string()],
+            false,
+        );
+        assert!(matches!(result, Err(Error::PackageNotInProfile { .. })));
     }
 
     #[test]
@@ -712,20 +1709,29 @@ mod tests {
 
         let profile = Profile {
             name: profile_name.to_string(),
+            origin: None,
             packages: vec![PackageRef {
                 name: "git".to_string(),
                 installer: None,
+                version: None,
+                tags: Vec::new(),
             }],
         };
         write_profile(&profile).unwrap();
 
         // Try to add the same package again
-        let result = add_package_to_profile(profile_name, "git", None);
-        assert!(result.is_err());
-        let error_msg = result.err().unwrap();
-        assert!(error_msg.contains("is already present in profile"));
-        assert!(error_msg.contains("git"));
-        assert!(error_msg.contains(profile_name));
+        let result = add_packages_to_profile(
+            profile_name,
+            &["git".to_string()],
+            &AddOptions::default(),
+        );
+        match result {
+            Err(Error::PackageExists { package, profile }) => {
+                assert_eq!(package, "git");
+                assert_eq!(profile, profile_name);
+            }
+            other => panic!("expected PackageExists, got {:?}", other),
+        }
     }
 
     #[test]
@@ -737,7 +1743,7 @@ mod tests {
 
         let dest = _temp.path().join("exported.toml");
         let dest_str = dest.to_str().unwrap().to_string();
-        let result = export_profile(profile_name, &Some(dest_str));
+        let result = export_profile(profile_name, &Some(dest_str), false);
         assert!(result.is_ok());
         assert!(dest.exists());
 
@@ -749,9 +1755,8 @@ mod tests {
     #[serial]
     fn test_export_non_existent_profile() {
         let _temp = setup_test_env();
-        let result = export_profile("ghost", &None);
-        assert!(result.is_err());
-        assert!(result.err().unwrap().contains("does not exist"));
+        let result = export_profile("ghost", &None, false);
+        assert!(matches!(result, Err(Error::ProfileNotFound { .. })));
     }
 
     #[test]
@@ -763,7 +1768,7 @@ mod tests {
 
         let dest = _temp.path().join("roundtrip.toml");
         let dest_str = dest.to_str().unwrap().to_string();
-        export_profile(profile_name, &Some(dest_str.clone())).unwrap();
+        export_profile(profile_name, &Some(dest_str.clone()), false).unwrap();
 
         delete_profile(profile_name).unwrap_or_default();
         // Use a different profile name so delete doesn't block (default
@@ -773,7 +1778,7 @@ mod tests {
             fs::remove_file(profile_path(profile_name)).unwrap();
         }
 
-        let result = import_profile(&dest_str);
+        let result = import_profile(&dest_str, &ImportOptions::default());
         assert!(result.is_ok());
         assert!(profile_path(profile_name).exists());
     }
@@ -787,11 +1792,363 @@ mod tests {
 
         let dest = _temp.path().join("dup.toml");
         let dest_str = dest.to_str().unwrap().to_string();
-        export_profile(profile_name, &Some(dest_str.clone())).unwrap();
+        export_profile(profile_name, &Some(dest_str.clone()), false).unwrap();
 
-        let result = import_profile(&dest_str);
-        assert!(result.is_err());
-        assert!(result.err().unwrap().contains("already exists"));
+        let result = import_profile(&dest_str, &ImportOptions::default());
+        assert!(matches!(result, Err(Error::ProfileExists(_))));
+    }
+
+    #[test]
+    #[serial]
+    fn test_import_rejects_names_outside_their_directory() {
+        let _temp = setup_test_env();
+        let file = _temp.path().join("evil.toml");
+        let file_str = file.to_str().unwrap();
+        let trusted = ImportOptions {
+            trust: true,
+            ..Default::default()
+        };
+        let bundle = |name: &str, package: &str| {
+            format!(
+                "name = {:?}\npackages = []\n\n[registry.{:?}.packages]\ncustom = \"true\"\n",
+                name, package
+            )
+        };
+
+        fs::write(&file, bundle("../..", "tool")).unwrap();
+        let result = import_profile(file_str, &trusted);
+        assert!(matches!(result, Err(Error::InvalidName { .. })));
+        assert!(crate::config::profiles_dir().exists());
+
+        fs::write(&file, bundle("team", "../../evil")).unwrap();
+        let result = import_profile(file_str, &trusted);
+        assert!(matches!(result, Err(Error::InvalidName { .. })));
+        assert!(!profile_path("team").exists());
+        assert!(!crate::registry::sources_dir().join("team").exists());
+
+        // Without --trust and without a terminal to ask on, bundled custom
+        // commands are refused before anything is written.
+        fs::write(&file, bundle("team", "tool")).unwrap();
+        let result = import_profile(file_str, &ImportOptions::default());
+        match result {
+            Err(Error::UntrustedCommands { packages, .. }) => {
+                assert_eq!(packages, vec!["tool"])
+            }
+            other => panic!("expected untrusted commands, got {:?}", other),
+        }
+        assert!(!profile_path("team").exists());
+        import_profile(file_str, &trusted).unwrap();
+        assert!(profile_path("team").exists());
+    }
+
+    #[test]
+    #[serial]
+    fn test_import_rename_and_force() {
+        let _temp = setup_test_env();
+        create_profile("shared").unwrap();
+        let dest = _temp.path().join("shared.toml");
+        let dest_str = dest.to_str().unwrap().to_string();
+        export_profile("shared", &Some(dest_str.clone()), false).unwrap();
+
+        let renamed = ImportOptions {
+            name: Some("mine".to_string()),
+            ..Default::default()
+        };
+        import_profile(&dest_str, &renamed).unwrap();
+        assert_eq!(read_profile("mine").unwrap().name, "mine");
+
+        let forced = ImportOptions {
+            force: true,
+            ..Default::default()
+        };
+        fs::write(crate::lock::lock_path("shared"), "").unwrap();
+        assert!(import_profile(&dest_str, &forced).is_ok());
+        assert!(!crate::lock::lock_path("shared").exists());
+
+        let pinned = ImportOptions {
+            sha256: Some("0".repeat(64)),
+            force: true,
+            ..Default::default()
+        };
+        let result = import_profile(&dest_str, &pinned);
+        assert!(matches!(result, Err(Error::ChecksumMismatch { .. })));
+    }
+
+    #[test]
+    #[serial]
+    fn test_rename_moves_lock_and_default() {
+        let _temp = setup_test_env();
+        create_profile("old").unwrap();
+        config::set_default_profile("old").unwrap();
+        crate::lock::write_lock(&crate::lock::Lockfile {
+            profile: "old".to_string(),
+            packages: Vec::new(),
+        })
+        .unwrap();
+
+        rename_profile("old", "new").unwrap();
+        assert!(!profile_path("old").exists());
+        assert_eq!(read_profile("new").unwrap().name, "new");
+        assert_eq!(crate::lock::read_lock("new").unwrap().profile, "new");
+        assert!(!crate::lock::lock_path("old").exists());
+        assert_eq!(config::get_default_profile(), "new");
+
+        copy_profile("new", "other").unwrap();
+        assert!(profile_path("new").exists());
+        assert!(matches!(
+            copy_profile("new", "other"),
+            Err(Error::ProfileExists(_))
+        ));
+        assert!(matches!(
+            rename_profile("ghost", "x"),
+            Err(Error::ProfileNotFound { .. })
+        ));
+    }
+
+    #[test]
+    #[serial]
+    fn test_edit_keeps_rejected_draft() {
+        let _temp = setup_test_env();
+        write_registry(&[
+            ("git", "[packages]\napt = \"git\"\n"),
+            ("curl", "[packages]\napt = \"curl\"\n"),
+        ]);
+        create_profile("work").unwrap();
+        add_packages_to_profile(
+            "work",
+            &["git".to_string()],
+            &AddOptions::default(),
+        )
+        .unwrap();
+        let edit_with = |editor: &str| {
+            unsafe { env::set_var("VISUAL", editor) };
+            let result = edit_profile("work");
+            unsafe { env::remove_var("VISUAL") };
+            result
+        };
+
+        match edit_with("sed -i s/git/ghost/") {
+            Err(Error::EditRejected { draft, source, .. }) => {
+                assert!(matches!(*source, Error::PackageNotFound { .. }));
+                let kept = fs::read_to_string(&draft).unwrap();
+                assert!(kept.contains("ghost"));
+                fs::remove_file(draft).unwrap();
+            }
+            other => panic!("expected EditRejected, got {:?}", other),
+        }
+        assert_eq!(names("work"), vec!["git"]);
+
+        edit_with("true").unwrap();
+        assert_eq!(names("work"), vec!["git"]);
+        edit_with("sed -i s/git/curl/").unwrap();
+        assert_eq!(names("work"), vec!["curl"]);
+    }
+
+    fn write_registry(packages: &[(&str, &str)]) {
+        let reg_dir = crate::registry::registry_dir();
+        fs::create_dir_all(&reg_dir).unwrap();
+        for (name, content) in packages {
+            fs::write(reg_dir.join(format!("{}.toml", name)), content).unwrap();
+        }
+    }
+
+    fn names(profile_name: &str) -> Vec<String> {
+        read_profile(profile_name)
+            .unwrap()
+            .packages
+            .into_iter()
+            .map(|p| p.name)
+            .collect()
+    }
+
+    #[test]
+    #[serial]
+    fn test_bulk_add_with_deps_and_ignore_existing() {
+        let _temp = setup_test_env();
+        write_registry(&[
+            ("base", "[packages]\napt = \"base\"\n"),
+            (
+                "lib",
+                "dependencies = [\"base\"]\n[packages]\napt = \"lib\"\n",
+            ),
+            (
+                "app",
+                "dependencies = [\"lib\"]\n[packages]\napt = \"app\"\n",
+            ),
+            ("tool", "[packages]\napt = \"tool\"\n"),
+        ]);
+        create_profile("bulk").unwrap();
+        let list = |names: &[&str]| -> Vec<String> {
+            names.iter().map(|n| n.to_string()).collect()
+        };
+
+        let options = AddOptions {
+            with_deps: true,
+            ..Default::default()
+        };
+        add_packages_to_profile("bulk", &list(&["app", "tool"]), &options)
+            .unwrap();
+        assert_eq!(names("bulk"), vec!["app", "base", "lib", "tool"]);
+
+        let result = add_packages_to_profile(
+            "bulk",
+            &list(&["tool"]),
+            &AddOptions::default(),
+        );
+        assert!(matches!(result, Err(Error::PackageExists { .. })));
+        // Nothing is written when one of the packages is unknown.
+        let result = add_packages_to_profile(
+            "bulk",
+            &list(&["ghost", "tool"]),
+            &AddOptions {
+                ignore_existing: true,
+                ..Default::default()
+            },
+        );
+        assert!(matches!(result, Err(Error::PackageNotFound { .. })));
+        assert_eq!(names("bulk").len(), 4);
+
+        // Existing entries pick up the tags instead of being left alone.
+        add_packages_to_profile(
+            "bulk",
+            &list(&["tool"]),
+            &AddOptions {
+                ignore_existing: true,
+                tags: vec!["cli".to_string()],
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let profile = read_profile("bulk").unwrap();
+        let tool = profile.packages.iter().find(|p| p.name == "tool").unwrap();
+        assert_eq!(tool.tags, vec!["cli"]);
+        assert_eq!(profile.packages.len(), 4);
+    }
+
+    #[test]
+    #[serial]
+    fn test_remove_warns_or_cascades_to_dependents() {
+        let _temp = setup_test_env();
+        write_registry(&[
+            ("base", "[packages]\napt = \"base\"\n"),
+            (
+                "lib",
+                "dependencies = [\"base\"]\n[packages]\napt = \"lib\"\n",
+            ),
+            (
+                "app",
+                "dependencies = [\"lib\"]\n[packages]\napt = \"app\"\n",
+            ),
+            ("tool", "[packages]\napt = \"tool\"\n"),
+        ]);
+        create_profile("deps").unwrap();
+        let all: Vec<String> = ["app", "base", "tool"]
+            .iter()
+            .map(|n| n.to_string())
+            .collect();
+        add_packages_to_profile("deps", &all, &AddOptions::default()).unwrap();
+
+        remove_packages_from_profile("deps", &["base".to_string()], false)
+            .unwrap();
+        assert_eq!(names("deps"), vec!["app", "tool"]);
+
+        add_packages_to_profile(
+            "deps",
+            &["base".to_string()],
+            &AddOptions::default(),
+        )
+        .unwrap();
+        remove_packages_from_profile("deps", &["base".to_string()], true)
+            .unwrap();
+        assert_eq!(names("deps"), vec!["tool"]);
+    }
+
+    #[test]
+    #[serial]
+    fn test_install_selection_pulls_in_dependencies() {
+        let _temp = setup_test_env();
+        write_registry(&[
+            ("base", "[packages]\napt = \"base\"\n"),
+            (
+                "app",
+                "dependencies = [\"base\"]\n[packages]\napt = \"app\"\n",
+            ),
+            ("tool", "[packages]\napt = \"tool\"\n"),
+        ]);
+        let package = |name: &str, tags: &[&str]| PackageRef {
+            name: name.to_string(),
+            installer: Some("apt".to_string()),
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+            ..Default::default()
+        };
+        let profile = Profile {
+            name: "work".to_string(),
+            origin: None,
+            packages: vec![
+                package("app", &["gui"]),
+                package("base", &[]),
+                package("tool", &["cli"]),
+            ],
+        };
+        let select = |options: InstallOptions| {
+            let (packages, dependencies) =
+                select_packages_for_install(&profile, &options).unwrap();
+            let names: Vec<String> =
+                packages.into_iter().map(|p| p.name).collect();
+            (names, dependencies)
+        };
+
+        let (all, dependencies) = select(InstallOptions::default());
+        assert_eq!(all, vec!["app", "base", "tool"]);
+        assert!(dependencies.is_empty());
+
+        let only = select(InstallOptions {
+            only: Some(vec!["app".to_string()]),
+            ..Default::default()
+        });
+        assert_eq!(
+            only,
+            (vec!["app".into(), "base".into()], vec!["base".into()])
+        );
+
+        let (tagged, _) = select(InstallOptions {
+            tags: vec!["cli".to_string()],
+            ..Default::default()
+        });
+        assert_eq!(tagged, vec!["tool"]);
+
+        // An excluded package still comes along when a selected one needs it.
+        let except = select(InstallOptions {
+            except: vec!["base".to_string(), "tool".to_string()],
+            ..Default::default()
+        });
+        assert_eq!(
+            except,
+            (vec!["app".into(), "base".into()], vec!["base".into()])
+        );
+
+        let result = select_packages_for_install(
+            &profile,
+            &InstallOptions {
+                only: Some(vec!["ghost".to_string()]),
+                ..Default::default()
+            },
+        );
+        assert!(matches!(result, Err(Error::PackageNotInProfile { .. })));
+
+        let result = select_packages_for_install(
+            &profile,
+            &InstallOptions {
+                tags: vec!["cil".to_string()],
+                ..Default::default()
+            },
+        );
+        match result {
+            Err(Error::TagNotFound { suggestions, .. }) => {
+                assert_eq!(suggestions, vec!["cli"]);
+            }
+            other => panic!("expected TagNotFound, got {:?}", other),
+        }
     }
 
     #[test]
@@ -800,9 +2157,17 @@ mod tests {
         let _temp = setup_test_env();
         let bad_file = _temp.path().join("bad.toml");
         fs::write(&bad_file, "not valid toml ][[[").unwrap();
-        let result = import_profile(bad_file.to_str().unwrap());
-        assert!(result.is_err());
-        assert!(result.err().unwrap().contains("Invalid profile file"));
+        let result = import_profile(
+            bad_file.to_str().unwrap(),
+            &ImportOptions::default(),
+        );
+        assert!(matches!(
+            result,
+            Err(Error::InvalidFile {
+                kind: "profile",
+                ..
+            })
+        ));
     }
 
     #[test]
@@ -813,22 +2178,24 @@ mod tests {
 
         let profile = Profile {
             name: profile_name.to_string(),
+            origin: None,
             packages: vec![PackageRef {
                 name: "docker".to_string(),
                 installer: None,
+                version: None,
+                tags: Vec::new(),
             }],
         };
         write_profile(&profile).unwrap();
 
         // First attempt should fail
-        let result = add_package_to_profile(profile_name, "docker", None);
-        assert!(result.is_err());
-        assert!(
-            result
-                .err()
-                .unwrap()
-                .contains("is already present in profile")
+        let result = add_packages_to_profile(
+            profile_name,
+            &["docker".to_string()],
+            &AddOptions::default(),
         );
+        assert!(result.is_err());
+        assert!(matches!(result, Err(Error::PackageExists { .. })));
 
         // Verify the profile still has only one package
         let updated_profile = read_profile(profile_name).unwrap();
@@ -1,17 +1,27 @@
-use std::{cell::RefCell, collections::HashMap, fs, path::PathBuf};
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fs,
+    path::{Path, PathBuf},
+};
 
 use colored::Colorize;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
 use toml::Table;
 
 use crate::{
     config::{ASSETS, config_dir},
-    profile,
+    error::{Error, Result},
+    output::{self, note, say},
+    profile, suggest,
 };
 
 pub const REGISTRY_DIRNAME: &str = "registry";
 const METADATA_FILENAME: &str = "metadata.toml";
+const SOURCES_DIRNAME: &str = "sources";
 
-fn registry_dir() -> PathBuf {
+pub fn registry_dir() -> PathBuf {
     config_dir().join(REGISTRY_DIRNAME)
 }
 
@@ -19,6 +29,35 @@ fn metadata_path() -> PathBuf {
     registry_dir().join(METADATA_FILENAME)
 }
 
+// Definitions installed from profile bundles, one directory per source. They
+// only provide packages the main registry does not have, and registry
+// updates never touch them.
+pub fn sources_dir() -> PathBuf {
+    registry_dir().join(SOURCES_DIRNAME)
+}
+
+fn source_dirs() -> Vec<PathBuf> {
+    let mut dirs: Vec<PathBuf> = fs::read_dir(sources_dir())
+        .into_iter()
+        .flatten()
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.is_dir())
+        .collect();
+    dirs.sort();
+    dirs
+}
+
+// The file a package is read from, following the same precedence as
+// `read_registry`.
+pub fn package_file(package_name: &str) -> Option<PathBuf> {
+    let filename = format!("{}.toml", package_name);
+    std::iter::once(registry_dir())
+        .chain(source_dirs())
+        .map(|dir| dir.join(&filename))
+        .find(|path| path.is_file())
+}
+
 // Cache keyed by path so test env changes (HOME override) are handled
 // correctly.
 thread_local! {
@@ -30,29 +69,28 @@ fn invalidate_registry_cache() {
     REGISTRY_CACHE.with(|c| *c.borrow_mut() = None);
 }
 
-fn copy_bundled_registry() -> Result<(), String> {
+fn copy_bundled_registry() -> Result<()> {
     log::debug!("copying bundled registry assets to {:?}", registry_dir());
     let bundled_dir = ASSETS.get_dir(REGISTRY_DIRNAME).ok_or_else(|| {
-        format!(
+        Error::Other(format!(
             "Bundled registry directory '{}' not found",
             REGISTRY_DIRNAME
-        )
+        ))
     })?;
 
     let target_dir = registry_dir();
     fs::create_dir_all(&target_dir)
-        .map_err(|e| format!("Failed to create registry directory: {}", e))?;
+        .map_err(Error::io("Failed to create registry directory"))?;
 
     let mut count = 0usize;
     for file in bundled_dir.files() {
-        let filename = file
-            .path()
-            .file_name()
-            .ok_or("Invalid bundled registry file path")?;
+        let Some(filename) = file.path().file_name() else {
+            continue;
+        };
         let target_path = target_dir.join(filename);
-        fs::write(&target_path, file.contents()).map_err(|e| {
-            format!("Failed to write registry file {:?}: {}", filename, e)
-        })?;
+        fs::write(&target_path, file.contents()).map_err(Error::io(
+            format!("Failed to write registry file {:?}", filename),
+        ))?;
         count += 1;
     }
 
@@ -61,7 +99,7 @@ fn copy_bundled_registry() -> Result<(), String> {
     Ok(())
 }
 
-pub fn ensure_registry() -> Result<(), String> {
+pub fn ensure_registry() -> Result<()> {
     let dir = registry_dir();
     let needs_init = !dir.exists()
         || fs::read_dir(&dir)
@@ -80,18 +118,16 @@ pub fn ensure_registry() -> Result<(), String> {
     Ok(())
 }
 
-fn read_registry_from_disk() -> Result<toml::Value, String> {
-    let dir = registry_dir();
-    log::debug!("reading registry from disk at {:?}", dir);
-    let mut packages = Table::new();
-
-    let entries = fs::read_dir(&dir)
-        .map_err(|e| format!("Failed to read registry directory: {}", e))?;
+// Reads every package file in `dir` into `packages`. Names already present
+// are kept, so earlier directories take precedence.
+fn read_package_files(dir: &Path, packages: &mut Table) -> Result<usize> {
+    let entries = fs::read_dir(dir)
+        .map_err(Error::io("Failed to read registry directory"))?;
 
     let mut count = 0usize;
     for entry in entries {
-        let entry = entry
-            .map_err(|e| format!("Failed to read directory entry: {}", e))?;
+        let entry =
+            entry.map_err(Error::io("Failed to read registry directory"))?;
         let path = entry.path();
 
         let filename = match path.file_name().and_then(|f| f.to_str()) {
@@ -104,16 +140,32 @@ fn read_registry_from_disk() -> Result<toml::Value, String> {
         }
 
         let stem = filename.trim_end_matches(".toml");
-        let content = fs::read_to_string(&path).map_err(|e| {
-            format!("Failed to read registry file '{}': {}", filename, e)
-        })?;
-        let value: toml::Value = toml::from_str(&content).map_err(|e| {
-            format!("Failed to parse registry file '{}': {}", filename, e)
-        })?;
+        if packages.contains_key(stem) {
+            log::debug!("'{}' in {:?} is shadowed, skipping", stem, dir);
+            continue;
+        }
+        let content = fs::read_to_string(&path).map_err(Error::io(format!(
+            "Failed to read registry file '{}'",
+            filename
+        )))?;
+        let value: toml::Value = toml::from_str(&content)
+            .map_err(|e| Error::registry_parse(&filename, &content, &e))?;
 
         packages.insert(stem.to_string(), value);
         count += 1;
     }
+    Ok(count)
+}
+
+fn read_registry_from_disk() -> Result<toml::Value> {
+    let dir = registry_dir();
+    log::debug!("reading registry from disk at {:?}", dir);
+    let mut packages = Table::new();
+
+    let mut count = read_package_files(&dir, &mut packages)?;
+    for source in source_dirs() {
+        count += read_package_files(&source, &mut packages)?;
+    }
 
     log::debug!("registry loaded from disk: {} packages", count);
     let mut root = Table::new();
@@ -121,7 +173,7 @@ fn read_registry_from_disk() -> Result<toml::Value, String> {
     Ok(toml::Value::Table(root))
 }
 
-pub fn read_registry() -> Result<toml::Value, String> {
+pub fn read_registry() -> Result<toml::Value> {
     let dir = registry_dir();
     REGISTRY_CACHE.with(|cache| {
         {
@@ -140,14 +192,16 @@ pub fn read_registry() -> Result<toml::Value, String> {
     })
 }
 
-fn get_packages_table(registry: &toml::Value) -> Result<&toml::Table, String> {
+fn get_packages_table(registry: &toml::Value) -> Result<&toml::Table> {
     registry
         .get("package")
         .and_then(|p| p.as_table())
-        .ok_or_else(|| "Registry is missing the '[package]' table".to_string())
+        .ok_or_else(|| {
+            Error::Other("Registry is missing the '[package]' table".into())
+        })
 }
 
-pub fn is_package_in_registry(package_name: &str) -> Result<bool, String> {
+pub fn is_package_in_registry(package_name: &str) -> Result<bool> {
     log::debug!("checking if '{}' is in registry", package_name);
     let registry = read_registry()?;
     let packages = get_packages_table(&registry)?;
@@ -156,52 +210,142 @@ pub fn is_package_in_registry(package_name: &str) -> Result<bool, String> {
     Ok(found)
 }
 
-pub fn list_packages(query: &Option<String>) -> Result<(), String> {
+// Registry names close to `query`, matched against each package's name,
+// display name and `aliases`.
+pub fn similar_packages(query: &str) -> Vec<String> {
+    let Ok(registry) = read_registry() else {
+        return Vec::new();
+    };
+    let Ok(packages) = get_packages_table(&registry) else {
+        return Vec::new();
+    };
+    let mut candidates = Vec::new();
+    for (name, details) in packages {
+        candidates.push((name.clone(), name.clone()));
+        if let Some(display) = details.get("display").and_then(|d| d.as_str()) {
+            candidates.push((display.to_string(), name.clone()));
+        }
+        let aliases = details.get("aliases").and_then(|a| a.as_array());
+        for alias in aliases.into_iter().flatten().filter_map(|a| a.as_str()) {
+            candidates.push((alias.to_string(), name.clone()));
+        }
+    }
+    suggest::similar(query, candidates)
+}
+
+pub fn package_not_found(package_name: &str) -> Error {
+    Error::PackageNotFound {
+        name: package_name.to_string(),
+        suggestions: similar_packages(package_name),
+    }
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct PackageEntry {
+    pub name: String,
+    pub display: Option<String>,
+    pub installers: Vec<String>,
+    pub custom: Option<String>,
+    pub release: Option<String>,
+}
+
+fn package_entry(name: &str, details: &toml::Value) -> PackageEntry {
+    let installers = details.get("packages").and_then(|i| i.as_table());
+    PackageEntry {
+        name: name.to_string(),
+        display: details
+            .get("display")
+            .and_then(|d| d.as_str())
+            .map(String::from),
+        installers: installers
+            .map(|t| t.keys().cloned().collect())
+            .unwrap_or_default(),
+        custom: installers
+            .and_then(|t| t.get("custom"))
+            .and_then(|c| c.as_str())
+            .map(String::from),
+        release: details
+            .get("release")
+            .and_then(|r| r.get("asset"))
+            .and_then(|a| a.as_str())
+            .map(String::from),
+    }
+}
+
+pub fn find_packages(query: &Option<String>) -> Result<Vec<PackageEntry>> {
+    let registry = read_registry()?;
+    let packages_table = get_packages_table(&registry)?;
+    Ok(packages_table
+        .iter()
+        .filter(|(name, _)| {
+            query
+                .as_ref()
+                .is_none_or(|q| name.to_lowercase().contains(&q.to_lowercase()))
+        })
+        .map(|(name, details)| package_entry(name, details))
+        .collect())
+}
+
+// Native package name -> registry package, for one installer. When several
+// packages share a native name, the first one by name wins.
+pub fn native_index(installer: &str) -> Result<HashMap<String, String>> {
     let registry = read_registry()?;
     let packages_table = get_packages_table(&registry)?;
+    let mut names: Vec<&String> = packages_table.keys().collect();
+    names.sort();
+
+    let mut index = HashMap::new();
+    for name in names {
+        let Some(value) = packages_table[name]
+            .get("packages")
+            .and_then(|p| p.get(installer))
+            .and_then(|v| v.as_str())
+        else {
+            continue;
+        };
+        index
+            .entry(value.to_string())
+            .or_insert_with(|| name.clone());
+    }
+    Ok(index)
+}
 
-    let mut found = false;
-    println!("{}", "Available packages:".bold());
+pub fn list_packages(query: &Option<String>) -> Result<()> {
+    let entries = find_packages(query)?;
+    if output::is_structured() {
+        return output::emit(&entries);
+    }
 
-    for (name, details) in packages_table {
-        if let Some(q) = query
-            && !name.to_lowercase().contains(&q.to_lowercase())
-        {
+    say!("{}", "Available packages:".bold());
+    for entry in &entries {
+        say!("- {}", entry.name.cyan().bold());
+        if entry.installers.is_empty() && entry.release.is_none() {
+            say!("  {}", "No installers specified.".dimmed());
             continue;
         }
-        found = true;
-
-        println!("- {}", name.cyan().bold());
-        if let Some(installers) =
-            details.get("packages").and_then(|i| i.as_table())
-        {
-            println!("  {}", "Installers:".dimmed());
-            for (installer_name, value) in installers {
-                if installer_name == "custom" {
-                    if let Some(cmd) = value.as_str() {
-                        println!(
-                            "    - {}: {}",
-                            "custom".yellow(),
-                            cmd.dimmed()
-                        );
-                    }
-                } else {
-                    println!("    - {}", installer_name.green());
+        say!("  {}", "Installers:".dimmed());
+        for installer_name in &entry.installers {
+            match (installer_name.as_str(), &entry.custom) {
+                ("custom", Some(cmd)) => {
+                    say!("    - {}: {}", "custom".yellow(), cmd.dimmed())
                 }
+                ("custom", None) => {}
+                _ => say!("    - {}", installer_name.green()),
             }
-        } else {
-            println!("  {}", "No installers specified.".dimmed());
+        }
+        if let Some(asset) = &entry.release {
+            say!("    - {}: {}", "release".yellow(), asset.dimmed());
         }
     }
 
-    if !found {
-        println!("{}", "No packages found matching your query.".yellow());
+    if entries.is_empty() {
+        say!("{}", "No packages found matching your query.".yellow());
     }
 
     Ok(())
 }
 
-fn get_raw_package_table(package_name: &str) -> Result<Table, String> {
+fn get_raw_package_table(package_name: &str) -> Result<Table> {
     log::debug!("fetching raw table for package '{}'", package_name);
     let lookup = |registry: &toml::Value| -> Option<Table> {
         get_packages_table(registry)
@@ -227,13 +371,13 @@ fn get_raw_package_table(package_name: &str) -> Result<Table, String> {
             "package '{}' not found in registry after refresh",
             package_name
         );
-        format!("Package '{}' not found in registry", package_name)
+        package_not_found(package_name)
     })
 }
 
 pub fn get_package_details(
     package_name: &str,
-) -> Result<profile::ProfilePackage, String> {
+) -> Result<profile::ProfilePackage> {
     log::debug!("getting details for package '{}'", package_name);
     let package_table = get_raw_package_table(package_name)?;
 
@@ -245,6 +389,14 @@ pub fn get_package_details(
         .get("detect")
         .and_then(|v| v.as_str())
         .map(String::from);
+    let version_command = package_table
+        .get("version_command")
+        .and_then(|v| v.as_str())
+        .map(String::from);
+    let version_regex = package_table
+        .get("version_regex")
+        .and_then(|v| v.as_str())
+        .map(String::from);
 
     let mut installers = HashMap::new();
     if let Some(packages_section) =
@@ -257,6 +409,21 @@ pub fn get_package_details(
         }
     }
 
+    let release =
+        match package_table.get("release") {
+            Some(value) => Some(value.clone().try_into().map_err(
+                |e: toml::de::Error| Error::RegistryParse {
+                    file: format!("{}.toml", package_name),
+                    line: None,
+                    message: format!(
+                        "invalid 'release' section: {}",
+                        e.message()
+                    ),
+                },
+            )?),
+            None => None,
+        };
+
     let dependencies = get_dependencies(package_name)?;
 
     log::debug!(
@@ -272,27 +439,28 @@ pub fn get_package_details(
         installers,
         detect,
         dependencies,
+        release,
+        version_command,
+        version_regex,
     })
 }
 
-pub fn get_dependencies(package_name: &str) -> Result<Vec<String>, String> {
+pub fn get_dependencies(package_name: &str) -> Result<Vec<String>> {
     let registry = read_registry()?;
     let packages_table = get_packages_table(&registry)?;
 
     let package_table = packages_table
         .get(package_name)
         .and_then(|v| v.as_table())
-        .ok_or_else(|| {
-            format!("Package '{}' not found in registry", package_name)
-        })?;
+        .ok_or_else(|| package_not_found(package_name))?;
 
     if let Some(deps_value) = package_table.get("dependencies") {
-        let deps_array = deps_value.as_array().ok_or_else(|| {
-            format!(
-                "'dependencies' field for '{}' is not an array",
-                package_name
-            )
-        })?;
+        let deps_array =
+            deps_value.as_array().ok_or_else(|| Error::RegistryParse {
+                file: format!("{}.toml", package_name),
+                line: None,
+                message: "'dependencies' is not an array".to_string(),
+            })?;
 
         let deps = deps_array
             .iter()
@@ -304,42 +472,136 @@ pub fn get_dependencies(package_name: &str) -> Result<Vec<String>, String> {
     Ok(Vec::new())
 }
 
+pub fn package_hash(package_name: &str) -> Result<String> {
+    let path = package_file(package_name)
+        .ok_or_else(|| package_not_found(package_name))?;
+    let content = fs::read(&path).map_err(Error::io(format!(
+        "Failed to read registry file for '{}'",
+        package_name
+    )))?;
+    Ok(format!("{:x}", Sha256::digest(&content)))
+}
+
+pub fn registry_version() -> Option<String> {
+    let content = fs::read_to_string(metadata_path()).ok()?;
+    let meta: toml::Value = toml::from_str(&content).ok()?;
+    meta.get("version")
+        .and_then(|v| v.as_str())
+        .map(String::from)
+}
+
+pub fn bundled_registry_version() -> Option<String> {
+    let file = ASSETS
+        .get_file(format!("{}/{}", REGISTRY_DIRNAME, METADATA_FILENAME))?;
+    let meta: toml::Value = toml::from_str(file.contents_utf8()?).ok()?;
+    meta.get("version")
+        .and_then(|v| v.as_str())
+        .map(String::from)
+}
+
+// Parses every registry file on its own so one broken definition doesn't hide
+// problems in the others. Returns (filename, error) pairs.
+pub fn validate_registry_files() -> Vec<(String, String)> {
+    let Ok(entries) = fs::read_dir(registry_dir()) else {
+        return vec![(
+            REGISTRY_DIRNAME.to_string(),
+            "Failed to read registry directory".to_string(),
+        )];
+    };
+
+    let mut errors = Vec::new();
+    for entry in entries.filter_map(|e| e.ok()) {
+        let path = entry.path();
+        let Some(filename) = path.file_name().and_then(|f| f.to_str()) else {
+            continue;
+        };
+        if !filename.ends_with(".toml") {
+            continue;
+        }
+        let result = fs::read_to_string(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|c| {
+                toml::from_str::<toml::Value>(&c).map_err(|e| e.to_string())
+            });
+        if let Err(e) = result {
+            errors.push((filename.to_string(), e.trim().to_string()));
+        }
+    }
+    errors.sort();
+    errors
+}
+
+pub fn check_remote_registry(timeout_secs: u64) -> Result<String> {
+    let url = format!(
+        "{}/{}",
+        crate::config::get_registry_url(),
+        METADATA_FILENAME
+    );
+    log::debug!("checking remote registry at {}", url);
+    let body = ureq::AgentBuilder::new()
+        .timeout(std::time::Duration::from_secs(timeout_secs))
+        .build()
+        .get(&url)
+        .call()
+        .map_err(Error::network(&url))?
+        .into_string()
+        .map_err(Error::network(&url))?;
+    let meta: toml::Value =
+        toml::from_str(&body).map_err(|e| Error::InvalidResponse {
+            url: url.clone(),
+            message: format!("invalid metadata: {}", e.message()),
+        })?;
+    meta.get("version")
+        .and_then(|v| v.as_str())
+        .map(String::from)
+        .ok_or_else(|| Error::InvalidResponse {
+            url,
+            message: "metadata is missing the 'version' field".to_string(),
+        })
+}
+
 pub fn try_update_registry_silent() {
     log::debug!("checking remote registry for updates");
     let _ = update_registry_inner(true);
 }
 
-fn update_registry_inner(silent: bool) -> Result<(), String> {
+fn update_registry_inner(silent: bool) -> Result<()> {
     let base_url = crate::config::get_registry_url();
     let metadata_url = format!("{}/{}", base_url, METADATA_FILENAME);
 
     log::info!("fetching registry metadata from {}", metadata_url);
     let remote_body = match ureq::get(&metadata_url).call() {
-        Ok(r) => r.into_string().map_err(|e| {
-            format!("Failed to read registry metadata response: {}", e)
-        })?,
+        Ok(r) => r.into_string().map_err(Error::network(&metadata_url))?,
         Err(e) => {
             if silent {
                 log::warn!("silent registry update failed: {}", e);
                 return Ok(());
             }
             log::error!("failed to fetch registry metadata: {}", e);
-            return Err(format!("Failed to fetch registry metadata: {}", e));
+            return Err(Error::network(&metadata_url)(e));
         }
     };
 
+    let invalid = |message: String| Error::InvalidResponse {
+        url: metadata_url.clone(),
+        message,
+    };
     let remote_meta: toml::Value = toml::from_str(&remote_body)
-        .map_err(|e| format!("Failed to parse remote metadata: {}", e))?;
+        .map_err(|e| invalid(format!("invalid metadata: {}", e.message())))?;
 
     let remote_version = remote_meta
         .get("version")
         .and_then(|v| v.as_str())
-        .ok_or("Remote metadata missing 'version' field")?;
+        .ok_or_else(|| {
+            invalid("metadata is missing the 'version' field".to_string())
+        })?;
 
     let remote_packages: Vec<String> = remote_meta
         .get("packages")
         .and_then(|v| v.as_array())
-        .ok_or("Remote metadata missing 'packages' array")?
+        .ok_or_else(|| {
+            invalid("metadata is missing the 'packages' array".to_string())
+        })?
         .iter()
         .filter_map(|v| v.as_str().map(String::from))
         .collect();
@@ -353,7 +615,7 @@ fn update_registry_inner(silent: bool) -> Result<(), String> {
     let local_meta_path = metadata_path();
     if local_meta_path.exists() {
         let local_body = fs::read_to_string(&local_meta_path)
-            .map_err(|e| format!("Failed to read local metadata: {}", e))?;
+            .map_err(Error::io("Failed to read local metadata"))?;
         if let Ok(local_meta) = toml::from_str::<toml::Value>(&local_body)
             && local_meta.get("version").and_then(|v| v.as_str())
                 == Some(remote_version)
@@ -363,7 +625,7 @@ fn update_registry_inner(silent: bool) -> Result<(), String> {
                 remote_version
             );
             if !silent {
-                println!(
+                say!(
                     "{} (version {}).",
                     "Registry is already up to date".green(),
                     remote_version.bold()
@@ -375,7 +637,7 @@ fn update_registry_inner(silent: bool) -> Result<(), String> {
 
     let dir = registry_dir();
     fs::create_dir_all(&dir)
-        .map_err(|e| format!("Failed to create registry directory: {}", e))?;
+        .map_err(Error::io("Failed to create registry directory"))?;
 
     let mut fetched = 0usize;
     for pkg_name in &remote_packages {
@@ -383,18 +645,18 @@ fn update_registry_inner(silent: bool) -> Result<(), String> {
         log::debug!("fetching package '{}' from {}", pkg_name, pkg_url);
         match ureq::get(&pkg_url).call() {
             Ok(response) => {
-                let content = response.into_string().map_err(|e| {
-                    format!("Failed to read response for '{}': {}", pkg_name, e)
-                })?;
+                let content =
+                    response.into_string().map_err(Error::network(&pkg_url))?;
                 let dest = dir.join(format!("{}.toml", pkg_name));
-                fs::write(&dest, &content).map_err(|e| {
-                    format!("Failed to write package '{}': {}", pkg_name, e)
-                })?;
+                fs::write(&dest, &content).map_err(Error::io(format!(
+                    "Failed to write package '{}'",
+                    pkg_name
+                )))?;
                 fetched += 1;
             }
             Err(e) => {
                 log::warn!("failed to fetch package '{}': {}", pkg_name, e);
-                eprintln!(
+                note!(
                     "{} failed to fetch package '{}': {}",
                     "Warning:".yellow().bold(),
                     pkg_name.cyan(),
@@ -405,7 +667,7 @@ fn update_registry_inner(silent: bool) -> Result<(), String> {
     }
 
     fs::write(&local_meta_path, &remote_body)
-        .map_err(|e| format!("Failed to write metadata: {}", e))?;
+        .map_err(Error::io("Failed to write metadata"))?;
 
     invalidate_registry_cache();
 
@@ -415,7 +677,7 @@ fn update_registry_inner(silent: bool) -> Result<(), String> {
         fetched,
         remote_packages.len()
     );
-    println!(
+    say!(
         "{} version {} ({} packages).",
         "Registry updated to".green(),
         remote_version.bold(),
@@ -424,27 +686,127 @@ fn update_registry_inner(silent: bool) -> Result<(), String> {
     Ok(())
 }
 
-pub fn add_custom_package(file: &str) -> Result<(), String> {
+pub fn write_package(name: &str, content: &str) -> Result<PathBuf> {
+    let dir = registry_dir();
+    fs::create_dir_all(&dir)
+        .map_err(Error::io("Failed to create registry directory"))?;
+    let dest = dir.join(format!("{}.toml", name));
+    fs::write(&dest, content)
+        .map_err(Error::io(format!("Failed to write {:?}", dest)))?;
+    invalidate_registry_cache();
+    log::info!("package '{}' written to {:?}", name, dest);
+    Ok(dest)
+}
+
+// Package names published upstream, either bundled with this binary or
+// listed by the remote registry's metadata.
+fn upstream_names() -> BTreeSet<String> {
+    let mut names: BTreeSet<String> = ASSETS
+        .get_dir(REGISTRY_DIRNAME)
+        .into_iter()
+        .flat_map(|dir| dir.files())
+        .filter_map(|f| f.path().file_stem()?.to_str().map(String::from))
+        .collect();
+    let remote = fs::read_to_string(metadata_path())
+        .ok()
+        .and_then(|c| toml::from_str::<toml::Value>(&c).ok());
+    if let Some(packages) = remote
+        .as_ref()
+        .and_then(|m| m.get("packages"))
+        .and_then(|p| p.as_array())
+    {
+        names.extend(
+            packages.iter().filter_map(|p| p.as_str()).map(String::from),
+        );
+    }
+    names.remove("metadata");
+    names
+}
+
+// `names` and everything they depend on, directly or not.
+pub fn dependency_closure(names: &[String]) -> Result<BTreeSet<String>> {
+    let mut seen = BTreeSet::new();
+    let mut pending: Vec<String> = names.to_vec();
+    while let Some(name) = pending.pop() {
+        if seen.insert(name.clone()) {
+            pending.extend(get_dependencies(&name)?);
+        }
+    }
+    Ok(seen)
+}
+
+// Definitions a profile needs that another machine's registry will not have:
+// every package in the dependency closure of `names` that is not upstream.
+pub fn custom_definitions(names: &[String]) -> Result<BTreeMap<String, Table>> {
+    let upstream = upstream_names();
+    let mut definitions = BTreeMap::new();
+    for name in dependency_closure(names)? {
+        if !upstream.contains(&name) {
+            let table = get_raw_package_table(&name)?;
+            definitions.insert(name, table);
+        }
+    }
+    log::debug!("custom definitions: {:?}", definitions.keys());
+    Ok(definitions)
+}
+
+// Replaces the definitions of bundle `source` with `definitions`.
+pub fn install_source(
+    source: &str,
+    definitions: &BTreeMap<String, Table>,
+) -> Result<PathBuf> {
+    // Every name is checked before anything is removed.
+    let dir = crate::scaffold::path_in(&sources_dir(), source, "")?;
+    let files = definitions
+        .iter()
+        .map(|(name, table)| {
+            Ok((crate::scaffold::path_in(&dir, name, ".toml")?, name, table))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    if dir.exists() {
+        fs::remove_dir_all(&dir)
+            .map_err(Error::io(format!("Failed to replace {:?}", dir)))?;
+    }
+    fs::create_dir_all(&dir)
+        .map_err(Error::io("Failed to create registry source directory"))?;
+    for (path, name, table) in files {
+        let content = toml::to_string(table)
+            .map_err(Error::serialize("registry package"))?;
+        fs::write(path, content).map_err(Error::io(format!(
+            "Failed to write registry package '{}'",
+            name
+        )))?;
+    }
+    invalidate_registry_cache();
+    log::info!(
+        "registry source '{}' installed with {} packages at {:?}",
+        source,
+        definitions.len(),
+        dir
+    );
+    Ok(dir)
+}
+
+pub fn add_custom_package(file: &str) -> Result<()> {
     log::debug!("adding custom package from '{}'", file);
     let content = fs::read_to_string(file)
-        .map_err(|e| format!("Failed to read file '{}': {}", file, e))?;
+        .map_err(Error::io(format!("Failed to read file '{}'", file)))?;
 
     toml::from_str::<toml::Value>(&content)
-        .map_err(|e| format!("Invalid package file: {}", e))?;
+        .map_err(|e| Error::registry_parse(file, &content, &e))?;
 
     let filename = PathBuf::from(file)
         .file_name()
-        .ok_or("Invalid file path")?
+        .ok_or_else(|| Error::Other(format!("Invalid file path '{}'", file)))?
         .to_os_string();
 
     let dest = registry_dir().join(&filename);
-    fs::copy(file, &dest)
-        .map_err(|e| format!("Failed to copy package file: {}", e))?;
+    fs::copy(file, &dest).map_err(Error::io("Failed to copy package file"))?;
 
     invalidate_registry_cache();
 
     log::info!("custom package added from '{}' -> {:?}", file, dest);
-    println!(
+    say!(
         "{} from '{}'.",
         "Package added to registry".green(),
         file.cyan()
@@ -613,8 +975,29 @@ apt = "git"
         );
 
         let result = get_package_details("non_existent");
-        assert!(result.is_err());
-        assert!(result.err().unwrap().contains("not found in registry"));
+        assert!(matches!(result, Err(Error::PackageNotFound { .. })));
+    }
+
+    #[test]
+    #[serial]
+    fn test_not_found_suggests_names_displays_and_aliases() {
+        let _temp = setup_test_env();
+        create_dummy_registry(
+            &_temp,
+            &[
+                ("neovim", "display = \"Neovim\"\naliases = [\"nvim\"]\n"),
+                ("ripgrep", "display = \"rg\"\n"),
+                ("git", ""),
+            ],
+        );
+
+        assert_eq!(similar_packages("nvim"), vec!["neovim"]);
+        assert_eq!(similar_packages("rgg"), vec!["ripgrep"]);
+        assert!(similar_packages("kubectl").is_empty());
+        assert_eq!(
+            get_package_details("gti").unwrap_err().to_string(),
+            "Package 'gti' not found in registry. Did you mean 'git'?"
+        );
     }
 
     #[test]
@@ -782,8 +1165,7 @@ apt = "git"
         create_dummy_registry(&_temp, &[("git", "display = \"Git\"\n")]);
 
         let result = get_dependencies("nonexistent");
-        assert!(result.is_err());
-        assert!(result.err().unwrap().contains("not found"));
+        assert!(matches!(result, Err(Error::PackageNotFound { .. })));
     }
 
     #[test]
@@ -823,8 +1205,7 @@ apt = "git"
         fs::write(&pkg_file, "not valid ][[[").unwrap();
 
         let result = add_custom_package(pkg_file.to_str().unwrap());
-        assert!(result.is_err());
-        assert!(result.err().unwrap().contains("Invalid package file"));
+        assert!(matches!(result, Err(Error::RegistryParse { .. })));
     }
 
     #[test]
@@ -834,7 +1215,86 @@ apt = "git"
         create_dummy_registry(&_temp, &[]);
 
         let result = add_custom_package("/nonexistent/path/pkg.toml");
-        assert!(result.is_err());
-        assert!(result.err().unwrap().contains("Failed to read file"));
+        assert!(matches!(result, Err(Error::Io { .. })));
+    }
+
+    #[test]
+    #[serial]
+    fn test_sources_fill_in_missing_packages() {
+        let _temp = setup_test_env();
+        create_dummy_registry(
+            &_temp,
+            &[("git", "[packages]\napt = \"git\"\n")],
+        );
+        let mut definitions = BTreeMap::new();
+        for (name, apt) in [("git", "git-other"), ("mytool", "my-tool")] {
+            let table: Table =
+                toml::from_str(&format!("[packages]\napt = \"{}\"\n", apt))
+                    .unwrap();
+            definitions.insert(name.to_string(), table);
+        }
+        install_source("work", &definitions).unwrap();
+
+        let git = get_package_details("git").unwrap();
+        assert_eq!(git.installers.get("apt").unwrap(), "git");
+        let mytool = get_package_details("mytool").unwrap();
+        assert_eq!(mytool.installers.get("apt").unwrap(), "my-tool");
+        assert_eq!(
+            package_file("mytool"),
+            Some(sources_dir().join("work/mytool.toml"))
+        );
+        assert!(package_hash("mytool").is_ok());
+    }
+
+    #[test]
+    #[serial]
+    fn test_custom_definitions_follow_dependencies() {
+        let _temp = setup_test_env();
+        create_dummy_registry(
+            &_temp,
+            &[
+                ("git", "[packages]\napt = \"git\"\n"),
+                (
+                    "mylib",
+                    "dependencies = [\"git\"]\n[packages]\ncustom = \"x\"\n",
+                ),
+                (
+                    "mytool",
+                    "dependencies = [\"mylib\"]\n[packages]\napt = \"t\"\n",
+                ),
+            ],
+        );
+
+        let definitions =
+            custom_definitions(&["mytool".to_string(), "git".to_string()])
+                .unwrap();
+        let names: Vec<_> = definitions.keys().collect();
+        assert_eq!(names, vec!["mylib", "mytool"]);
+    }
+
+    #[test]
+    #[serial]
+    fn test_native_index_prefers_first_name() {
+        let _temp = setup_test_env();
+        create_dummy_registry(
+            &_temp,
+            &[
+                (
+                    "fd-find",
+                    "[packages]
+apt = \"fd-find\"\n",
+                ),
+                (
+                    "fd",
+                    "[packages]
+apt = \"fd-find\"\nbrew = \"fd\"\n",
+                ),
+            ],
+        );
+
+        let apt = native_index("apt").unwrap();
+        assert_eq!(apt.len(), 1);
+        assert_eq!(apt["fd-find"], "fd");
+        assert_eq!(native_index("brew").unwrap()["fd"], "fd");
     }
 }
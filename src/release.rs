@@ -0,0 +1,539 @@
+use std::{fs, io::Read, path::PathBuf};
+
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use toml::Table;
+
+use crate::{
+    config::config_dir,
+    error::{Error, Result},
+    output::note,
+    updater::{GithubAsset, GithubRelease, current_target},
+};
+
+const RELEASES_FILENAME: &str = "releases.toml";
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ReleaseSpec {
+    pub api: String,
+    pub asset: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub checksum: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bin: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tag: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct InstalledRelease {
+    pub version: String,
+    pub asset: String,
+    pub path: String,
+}
+
+fn releases_path() -> PathBuf {
+    config_dir().join(RELEASES_FILENAME)
+}
+
+fn user_agent() -> String {
+    format!("blazinit/{}", env!("CARGO_PKG_VERSION"))
+}
+
+pub fn expand_pattern(pattern: &str, version: &str) -> String {
+    pattern
+        .replace("{os}", std::env::consts::OS)
+        .replace("{arch}", std::env::consts::ARCH)
+        .replace("{target}", current_target().unwrap_or("unknown"))
+        .replace("{version}", version)
+}
+
+fn release_url(spec: &ReleaseSpec, pinned: Option<&str>) -> String {
+    let api = spec.api.trim_end_matches('/');
+    match pinned.or(spec.version.as_deref()) {
+        Some(version) => {
+            let tag = spec
+                .tag
+                .as_deref()
+                .unwrap_or("v{version}")
+                .replace("{version}", version);
+            format!("{}/tags/{}", api, tag)
+        }
+        None => format!("{}/latest", api),
+    }
+}
+
+fn fetch_release(url: &str) -> Result<GithubRelease> {
+    log::debug!("fetching release info from {}", url);
+    ureq::get(url)
+        .set("User-Agent", &user_agent())
+        .call()
+        .map_err(Error::network(url))?
+        .into_json()
+        .map_err(|e| Error::InvalidResponse {
+            url: url.to_string(),
+            message: format!("invalid release info: {}", e),
+        })
+}
+
+fn download(url: &str) -> Result<Vec<u8>> {
+    log::debug!("downloading {}", url);
+    let mut bytes = Vec::new();
+    ureq::get(url)
+        .set("User-Agent", &user_agent())
+        .call()
+        .map_err(Error::network(url))?
+        .into_reader()
+        .read_to_end(&mut bytes)
+        .map_err(Error::network(url))?;
+    Ok(bytes)
+}
+
+fn find_asset<'a>(
+    release: &'a GithubRelease,
+    release_url: &str,
+    name: &str,
+) -> Result<&'a GithubAsset> {
+    release
+        .assets
+        .iter()
+        .find(|a| a.name == name)
+        .ok_or_else(|| Error::InvalidResponse {
+            url: release_url.to_string(),
+            message: format!(
+                "release {} has no asset named '{}'",
+                release.tag_name, name
+            ),
+        })
+}
+
+// Checksum files come either as a bare digest or as `sha256sum` output
+// listing several assets; pick the line naming our asset if there is one.
+fn expected_digest(checksums: &str, asset_name: &str) -> Option<String> {
+    let line = checksums
+        .lines()
+        .find(|l| {
+            l.split_whitespace()
+                .any(|t| t.trim_start_matches('*') == asset_name)
+        })
+        .or_else(|| checksums.lines().find(|l| !l.trim().is_empty()))?;
+    line.split_whitespace().next().map(|d| d.to_lowercase())
+}
+
+fn verify_checksum(
+    release: &GithubRelease,
+    release_url: &str,
+    spec: &ReleaseSpec,
+    asset_name: &str,
+    bytes: &[u8],
+    version: &str,
+) -> Result<()> {
+    let Some(pattern) = &spec.checksum else {
+        log::warn!("no checksum configured for '{}'", asset_name);
+        note!(
+            "{} no checksum is configured for '{}'; installing it unverified",
+            "Warning:".yellow().bold(),
+            asset_name.cyan()
+        );
+        return Ok(());
+    };
+    let checksum_name =
+        expand_pattern(pattern, version).replace("{asset}", asset_name);
+    let checksum_asset = find_asset(release, release_url, &checksum_name)?;
+    let body = download(&checksum_asset.browser_download_url)?;
+    let body = String::from_utf8_lossy(&body);
+    let expected = expected_digest(&body, asset_name).ok_or_else(|| {
+        Error::InvalidResponse {
+            url: checksum_asset.browser_download_url.clone(),
+            message: format!("checksum file '{}' is empty", checksum_name),
+        }
+    })?;
+    let actual = format!("{:x}", Sha256::digest(bytes));
+    if actual != expected {
+        log::error!(
+            "checksum mismatch for '{}': expected {}, got {}",
+            asset_name,
+            expected,
+            actual
+        );
+        return Err(Error::ChecksumMismatch {
+            asset: asset_name.to_string(),
+            expected,
+            actual,
+        });
+    }
+    log::debug!("checksum verified for '{}'", asset_name);
+    Ok(())
+}
+
+const ARCHIVE_SUFFIXES: &[&str] = &[
+    ".tar.gz", ".tgz", ".tar.xz", ".tar.bz2", ".tar", ".zip", ".gz", ".xz",
+];
+
+// Archives are not unpacked, so an asset has to be the executable itself.
+// Checked by name before downloading and by magic bytes afterwards.
+fn reject_archive(asset_name: &str, bytes: Option<&[u8]>) -> Result<()> {
+    let named = ARCHIVE_SUFFIXES.iter().any(|s| asset_name.ends_with(s));
+    let packed = bytes.is_some_and(|b| {
+        b.starts_with(&[0x1f, 0x8b])
+            || b.starts_with(b"PK\x03\x04")
+            || b.starts_with(&[0xfd, b'7', b'z', b'X', b'Z'])
+            || b.starts_with(b"BZh")
+            || b.get(257..262) == Some(b"ustar".as_slice())
+    });
+    if named || packed {
+        return Err(Error::Unsupported(format!(
+            "Release asset '{}' is an archive; point `asset` at a standalone binary",
+            asset_name
+        )));
+    }
+    Ok(())
+}
+
+fn bin_path(package_name: &str, spec: &ReleaseSpec) -> Result<PathBuf> {
+    let name = spec.bin.as_deref().unwrap_or(package_name);
+    let name = if cfg!(windows) && !name.ends_with(".exe") {
+        format!("{}.exe", name)
+    } else {
+        name.to_string()
+    };
+    Ok(crate::config::get_bin_dir()?.join(name))
+}
+
+fn write_binary(path: &PathBuf, bytes: &[u8]) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(Error::io(format!("Failed to create {:?}", parent)))?;
+    }
+    fs::write(path, bytes)
+        .map_err(Error::io(format!("Failed to write {:?}", path)))?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(path, fs::Permissions::from_mode(0o755))
+            .map_err(Error::io("Failed to set permissions"))?;
+    }
+    Ok(())
+}
+
+fn read_releases() -> Table {
+    fs::read_to_string(releases_path())
+        .ok()
+        .and_then(|c| toml::from_str(&c).ok())
+        .unwrap_or_default()
+}
+
+fn record_release(
+    package_name: &str,
+    installed: &InstalledRelease,
+) -> Result<()> {
+    let mut releases = read_releases();
+    let value = toml::Value::try_from(installed)
+        .map_err(Error::serialize("installed release"))?;
+    releases.insert(package_name.to_string(), value);
+    let toml_str = toml::to_string(&releases)
+        .map_err(Error::serialize("installed releases"))?;
+    fs::write(releases_path(), toml_str)
+        .map_err(Error::io("Failed to record installed release"))
+}
+
+pub fn installed_release(package_name: &str) -> Option<InstalledRelease> {
+    let installed: InstalledRelease =
+        read_releases().get(package_name)?.clone().try_into().ok()?;
+    PathBuf::from(&installed.path).exists().then_some(installed)
+}
+
+pub fn describe(spec: &ReleaseSpec, pinned: Option<&str>) -> String {
+    format!("download {} from {}", spec.asset, release_url(spec, pinned))
+}
+
+pub fn install(
+    package_name: &str,
+    spec: &ReleaseSpec,
+    pinned: Option<&str>,
+) -> Result<String> {
+    let url = release_url(spec, pinned);
+    let release = fetch_release(&url)?;
+    let version = release.tag_name.trim_start_matches('v').to_string();
+    let asset_name = expand_pattern(&spec.asset, &version);
+    log::info!(
+        "release '{}': resolved version {} (asset '{}')",
+        package_name,
+        version,
+        asset_name
+    );
+
+    reject_archive(&asset_name, None)?;
+    let asset = find_asset(&release, &url, &asset_name)?;
+    let bytes = download(&asset.browser_download_url)?;
+    verify_checksum(&release, &url, spec, &asset_name, &bytes, &version)?;
+    reject_archive(&asset_name, Some(&bytes))?;
+
+    let path = bin_path(package_name, spec)?;
+    write_binary(&path, &bytes)?;
+    record_release(
+        package_name,
+        &InstalledRelease {
+            version: version.clone(),
+            asset: asset_name,
+            path: path.to_string_lossy().to_string(),
+        },
+    )?;
+
+    log::info!(
+        "release '{}' {} installed to {:?}",
+        package_name,
+        version,
+        path
+    );
+    Ok(version)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::HashMap,
+        env,
+        io::{BufRead, BufReader, Write},
+        net::TcpListener,
+        thread,
+    };
+
+    use serial_test::serial;
+    use tempfile::TempDir;
+
+    use super::*;
+
+    fn setup_test_env() -> TempDir {
+        let temp_dir = TempDir::new().expect("Failed to create temp dir");
+        unsafe {
+            env::set_var("HOME", temp_dir.path());
+            env::set_var("XDG_CONFIG_HOME", temp_dir.path().join(".config"));
+            env::set_var("XDG_BIN_HOME", temp_dir.path().join("bin"));
+        }
+        fs::create_dir_all(config_dir()).expect("Failed to create config dir");
+        temp_dir
+    }
+
+    // Minimal HTTP stand-in: serves fixed bodies by path, 404 otherwise. The
+    // routes are built from the server base URL so release JSON can point
+    // download links back at it.
+    fn serve_with(
+        routes: impl FnOnce(&str) -> HashMap<String, Vec<u8>>,
+    ) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        let routes = routes(&base);
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(mut stream) = stream else { continue };
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut request_line = String::new();
+                reader.read_line(&mut request_line).unwrap();
+                let mut line = String::new();
+                while reader.read_line(&mut line).unwrap() > 2 {
+                    line.clear();
+                }
+                let path = request_line
+                    .split_whitespace()
+                    .nth(1)
+                    .unwrap_or("/")
+                    .to_string();
+                let (status, body) = match routes.get(&path) {
+                    Some(b) => ("200 OK", b.clone()),
+                    None => ("404 Not Found", Vec::new()),
+                };
+                let _ = write!(
+                    stream,
+                    "HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                    status,
+                    body.len()
+                );
+                let _ = stream.write_all(&body);
+            }
+        });
+        base
+    }
+
+    fn release_json(base: &str, tag: &str, assets: &[&str]) -> Vec<u8> {
+        let assets: Vec<String> = assets
+            .iter()
+            .map(|a| {
+                format!(
+                    r#"{{"name":"{a}","browser_download_url":"{base}/dl/{a}"}}"#
+                )
+            })
+            .collect();
+        format!(
+            r#"{{"tag_name":"{}","assets":[{}]}}"#,
+            tag,
+            assets.join(",")
+        )
+        .into_bytes()
+    }
+
+    fn spec(base: &str, checksum: Option<&str>) -> ReleaseSpec {
+        ReleaseSpec {
+            api: format!("{}/repos/acme/tool/releases", base),
+            asset: "tool-{version}-{os}-{arch}".to_string(),
+            checksum: checksum.map(String::from),
+            bin: None,
+            version: None,
+            tag: None,
+        }
+    }
+
+    #[test]
+    fn test_expand_pattern_placeholders() {
+        let expanded = expand_pattern("tool-{version}-{os}-{arch}", "1.2.3");
+        assert_eq!(
+            expanded,
+            format!(
+                "tool-1.2.3-{}-{}",
+                std::env::consts::OS,
+                std::env::consts::ARCH
+            )
+        );
+    }
+
+    #[test]
+    fn test_release_url_latest_and_pinned() {
+        let s = spec("http://x", None);
+        assert_eq!(
+            release_url(&s, None),
+            "http://x/repos/acme/tool/releases/latest"
+        );
+        assert_eq!(
+            release_url(&s, Some("1.0.0")),
+            "http://x/repos/acme/tool/releases/tags/v1.0.0"
+        );
+    }
+
+    #[test]
+    fn test_expected_digest_picks_matching_line() {
+        let body = "aaa  other-asset\nbbb *tool-asset\n";
+        assert_eq!(expected_digest(body, "tool-asset"), Some("bbb".into()));
+        assert_eq!(expected_digest("CCC\n", "x"), Some("ccc".into()));
+    }
+
+    #[test]
+    #[serial]
+    fn test_install_latest_records_version() {
+        let temp = setup_test_env();
+        let asset = expand_pattern("tool-{version}-{os}-{arch}", "2.0.0");
+        let payload = b"#!/bin/sh\necho tool\n".to_vec();
+        let digest = format!("{:x}", Sha256::digest(&payload));
+
+        let server = serve_with(|base| {
+            let mut r = HashMap::new();
+            r.insert(
+                "/repos/acme/tool/releases/latest".to_string(),
+                release_json(
+                    base,
+                    "v2.0.0",
+                    &[&asset, &format!("{}.sha256", asset)],
+                ),
+            );
+            r.insert(format!("/dl/{}", asset), payload.clone());
+            r.insert(
+                format!("/dl/{}.sha256", asset),
+                format!("{}  {}\n", digest, asset).into_bytes(),
+            );
+            r
+        });
+
+        let s = spec(&server, Some("{asset}.sha256"));
+        let version = install("tool", &s, None).unwrap();
+        assert_eq!(version, "2.0.0");
+
+        let bin = temp.path().join("bin").join("tool");
+        assert_eq!(fs::read(&bin).unwrap(), payload);
+        let installed = installed_release("tool").unwrap();
+        assert_eq!(installed.version, "2.0.0");
+        assert_eq!(installed.asset, asset);
+    }
+
+    #[test]
+    #[serial]
+    fn test_install_pinned_version() {
+        let _temp = setup_test_env();
+        let asset = expand_pattern("tool-{version}-{os}-{arch}", "1.0.0");
+        let server = serve_with(|base| {
+            let mut r = HashMap::new();
+            r.insert(
+                "/repos/acme/tool/releases/tags/v1.0.0".to_string(),
+                release_json(base, "v1.0.0", &[&asset]),
+            );
+            r.insert(format!("/dl/{}", asset), b"old".to_vec());
+            r
+        });
+
+        let version =
+            install("tool", &spec(&server, None), Some("1.0.0")).unwrap();
+        assert_eq!(version, "1.0.0");
+        assert_eq!(installed_release("tool").unwrap().version, "1.0.0");
+    }
+
+    #[test]
+    #[serial]
+    fn test_install_checksum_mismatch_fails() {
+        let _temp = setup_test_env();
+        let asset = expand_pattern("tool-{version}-{os}-{arch}", "2.0.0");
+        let server = serve_with(|base| {
+            let mut r = HashMap::new();
+            r.insert(
+                "/repos/acme/tool/releases/latest".to_string(),
+                release_json(
+                    base,
+                    "v2.0.0",
+                    &[&asset, &format!("{}.sha256", asset)],
+                ),
+            );
+            r.insert(format!("/dl/{}", asset), b"tampered".to_vec());
+            r.insert(format!("/dl/{}.sha256", asset), b"deadbeef\n".to_vec());
+            r
+        });
+
+        let result =
+            install("tool", &spec(&server, Some("{asset}.sha256")), None);
+        assert!(matches!(result, Err(Error::ChecksumMismatch { .. })));
+        assert!(installed_release("tool").is_none());
+    }
+
+    #[test]
+    fn test_archives_are_rejected() {
+        assert!(reject_archive("rg-14.1.0-x86_64.tar.gz", None).is_err());
+        assert!(reject_archive("tool.zip", None).is_err());
+        assert!(reject_archive("tool", Some(b"\x1f\x8b\x08rest")).is_err());
+        assert!(reject_archive("tool", Some(b"PK\x03\x04rest")).is_err());
+        assert!(reject_archive("tool-linux-x86_64", Some(b"\x7fELF")).is_ok());
+    }
+
+    #[test]
+    #[serial]
+    fn test_install_missing_asset_fails() {
+        let _temp = setup_test_env();
+        let server = serve_with(|base| {
+            let mut r = HashMap::new();
+            r.insert(
+                "/repos/acme/tool/releases/latest".to_string(),
+                release_json(base, "v2.0.0", &["tool-other-platform"]),
+            );
+            r
+        });
+
+        let result = install("tool", &spec(&server, None), None);
+        assert!(result.is_err());
+        assert!(
+            result
+                .err()
+                .unwrap()
+                .to_string()
+                .contains("has no asset named")
+        );
+    }
+}
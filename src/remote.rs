@@ -0,0 +1,332 @@
+use std::{
+    env, io,
+    process::{Command, Stdio},
+    thread,
+};
+
+use colored::Colorize;
+use serde::Serialize;
+
+use crate::{
+    error::{self, Error, Result},
+    installer::{self, InstallReport},
+    output::{self, say},
+    profile::{self, InstallOptions},
+    reporter::{Event, Reporter},
+    runner::{CommandOutput, CommandRunner},
+};
+
+// Overrides the ssh binary, e.g. to point at a wrapper with extra options.
+pub const SSH_ENV: &str = "BLAZINIT_SSH";
+
+const OS_PROBE: &str =
+    ". /etc/os-release 2>/dev/null && echo \"$PRETTY_NAME\" || uname -s";
+
+// ssh exits with 255 when it could not connect or authenticate.
+const SSH_CONNECTION_FAILED: i32 = 255;
+
+fn ssh_program() -> String {
+    env::var(SSH_ENV).unwrap_or_else(|_| "ssh".to_string())
+}
+
+// Runs commands on `host` through the system ssh binary. Batch mode makes an
+// unreachable or password-protected host fail instead of prompting, and
+// output is always captured since several hosts run at once.
+pub struct SshRunner {
+    host: String,
+    program: String,
+}
+
+impl SshRunner {
+    pub fn new(host: &str) -> Self {
+        SshRunner {
+            host: host.to_string(),
+            program: ssh_program(),
+        }
+    }
+}
+
+impl CommandRunner for SshRunner {
+    fn output(&self, command: &str) -> io::Result<CommandOutput> {
+        log::debug!("ssh {}: {}", self.host, command);
+        let output = Command::new(&self.program)
+            .args(["-o", "BatchMode=yes", &self.host, "--", command])
+            .stdin(Stdio::null())
+            .output()?;
+        Ok(CommandOutput {
+            code: output.status.code(),
+            stdout: String::from_utf8_lossy(&output.stdout).to_string(),
+            stderr: String::from_utf8_lossy(&output.stderr).to_string(),
+        })
+    }
+
+    fn run(&self, command: &str) -> io::Result<CommandOutput> {
+        let output = self.output(command)?;
+        if !output.success() {
+            log::debug!(
+                "ssh {}: '{}' failed: {}",
+                self.host,
+                command,
+                output.stderr.trim()
+            );
+        }
+        Ok(output)
+    }
+
+    fn is_available(&self, program: &str) -> bool {
+        self.output(&format!("command -v {}", program))
+            .map(|o| o.success())
+            .unwrap_or(false)
+    }
+
+    fn is_local(&self) -> bool {
+        false
+    }
+}
+
+// Also serves as the connectivity check, so any failure here means the host
+// cannot be used at all.
+fn detect_os(host: &str, runner: &dyn CommandRunner) -> Result<String> {
+    let unreachable = |message: String| Error::HostUnreachable {
+        host: host.to_string(),
+        message,
+    };
+    let output = runner
+        .output(OS_PROBE)
+        .map_err(|e| unreachable(e.to_string()))?;
+    match output.code {
+        Some(0) => Ok(output.stdout.trim().to_string()),
+        Some(SSH_CONNECTION_FAILED) => {
+            Err(unreachable(output.stderr.trim().to_string()))
+        }
+        code => Err(unreachable(format!(
+            "OS detection failed with {:?}: {}",
+            code,
+            output.stderr.trim()
+        ))),
+    }
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct HostReport {
+    pub host: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub os: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub installer: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub report: Option<InstallReport>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl HostReport {
+    pub fn succeeded(&self) -> bool {
+        self.error.is_none()
+            && self.report.as_ref().is_some_and(|r| r.failed == 0)
+    }
+}
+
+// Progress lines prefixed with the host, since hosts report concurrently.
+// The overall summary is printed once all hosts are done.
+struct HostReporter<'a> {
+    host: &'a str,
+}
+
+impl Reporter for HostReporter<'_> {
+    fn report(&self, event: &Event) {
+        let host = format!("[{}]", self.host).bold();
+        match *event {
+            Event::Skipped { display } => {
+                say!("{} {} {}", host, "[skip]".yellow().bold(), display.cyan())
+            }
+            Event::WouldRun { display, command } => say!(
+                "{} {} {} — would run: {}",
+                host,
+                "[dry-run]".cyan().bold(),
+                display.cyan(),
+                command.dimmed()
+            ),
+            Event::Installing { display, .. } => {
+                say!(
+                    "{} {} {}",
+                    host,
+                    "[install]".blue().bold(),
+                    display.cyan()
+                )
+            }
+            Event::Installed { display, .. } => {
+                say!("{} {} {}", host, "[ok]".green().bold(), display.cyan())
+            }
+            Event::Failed { display, error } => eprintln!(
+                "{} {} {} — {}",
+                host,
+                "[fail]".red().bold(),
+                display.cyan(),
+                error::chain(error)
+            ),
+            _ => {}
+        }
+    }
+}
+
+fn install_on_host(
+    host: &str,
+    profile_names: &[String],
+    options: &InstallOptions,
+) -> HostReport {
+    let runner = SshRunner::new(host);
+    let mut host_report = HostReport {
+        host: host.to_string(),
+        os: None,
+        installer: None,
+        report: None,
+        error: None,
+    };
+
+    match detect_os(host, &runner) {
+        Ok(os) => host_report.os = Some(os),
+        Err(e) => {
+            log::error!("{}", e);
+            host_report.error = Some(error::chain(&e));
+            return host_report;
+        }
+    }
+    host_report.installer = installer::detect_available_installer(&runner);
+    log::info!(
+        "host '{}': os={:?}, installer={:?}",
+        host,
+        host_report.os,
+        host_report.installer
+    );
+
+    match profile::install_profiles(
+        profile_names,
+        options,
+        &runner,
+        &HostReporter { host },
+    ) {
+        Ok(report) => host_report.report = Some(report),
+        Err(e) => host_report.error = Some(error::chain(&e)),
+    }
+    host_report
+}
+
+pub fn install_on_hosts(
+    hosts: &[String],
+    profile_names: &[String],
+    options: &InstallOptions,
+) -> Result<Vec<HostReport>> {
+    // Fail on a missing profile once rather than once per host.
+    for profile_name in profile_names {
+        profile::read_profile(profile_name)?;
+    }
+    log::info!(
+        "installing profiles {:?} on {} hosts",
+        profile_names,
+        hosts.len()
+    );
+
+    let reports: Vec<HostReport> = thread::scope(|scope| {
+        let handles: Vec<_> = hosts
+            .iter()
+            .map(|host| {
+                scope.spawn(move || {
+                    install_on_host(host, profile_names, options)
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .zip(hosts)
+            .map(|(handle, host)| {
+                handle.join().unwrap_or_else(|_| HostReport {
+                    host: host.clone(),
+                    os: None,
+                    installer: None,
+                    report: None,
+                    error: Some("install panicked".to_string()),
+                })
+            })
+            .collect()
+    });
+
+    if output::is_structured() {
+        output::emit(&reports)?;
+    } else {
+        print_summary(&reports);
+    }
+    Ok(reports)
+}
+
+fn print_summary(reports: &[HostReport]) {
+    say!("\n{}", "Summary:".bold());
+    let width = reports.iter().map(|r| r.host.len()).max().unwrap_or(0);
+    for host in reports {
+        let label = if host.succeeded() {
+            "[ok]  ".green().bold()
+        } else {
+            "[fail]".red().bold()
+        };
+        let details = match (&host.report, &host.error) {
+            (_, Some(e)) => e.clone(),
+            (Some(r), None) => format!(
+                "{} {}  {} skipped  {} failed",
+                r.installed,
+                if r.dry_run {
+                    "would install"
+                } else {
+                    "installed"
+                },
+                r.skipped,
+                r.failed
+            ),
+            (None, None) => String::new(),
+        };
+        say!(
+            "  {} {:<width$}  {}  {}",
+            label,
+            host.host.cyan(),
+            host.os.as_deref().unwrap_or("").dimmed(),
+            details,
+            width = width
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runner::FakeRunner;
+
+    #[test]
+    fn test_detect_os_reports_unreachable_host() {
+        let runner = FakeRunner::new();
+        let result = detect_os("web1", &runner);
+        assert!(matches!(result, Err(Error::HostUnreachable { .. })));
+    }
+
+    #[test]
+    fn test_host_report_succeeded() {
+        let mut report = HostReport {
+            host: "web1".to_string(),
+            os: None,
+            installer: None,
+            report: None,
+            error: Some("unreachable".to_string()),
+        };
+        assert!(!report.succeeded());
+
+        report.error = None;
+        report.report = Some(InstallReport {
+            profile: "work".to_string(),
+            dry_run: false,
+            packages: Vec::new(),
+            installed: 2,
+            skipped: 0,
+            failed: 0,
+            dependencies: Vec::new(),
+        });
+        assert!(report.succeeded());
+    }
+}
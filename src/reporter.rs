@@ -0,0 +1,127 @@
+use colored::Colorize;
+
+use crate::{
+    error::{self, Error},
+    installer::InstallReport,
+    output::say,
+};
+
+// Progress of a long-running operation, as it happens. The final result is
+// returned to the caller separately, so a reporter only has to render.
+#[derive(Debug, Clone, Copy)]
+pub enum Event<'a> {
+    NothingToInstall,
+    // Packages installed only because the selected ones need them.
+    DependenciesAdded {
+        names: &'a [String],
+    },
+    Skipped {
+        display: &'a str,
+    },
+    Outdated {
+        display: &'a str,
+        installed: &'a str,
+        wanted: &'a str,
+    },
+    WouldRun {
+        display: &'a str,
+        command: &'a str,
+    },
+    Installing {
+        display: &'a str,
+        command: &'a str,
+    },
+    Installed {
+        display: &'a str,
+        version: Option<&'a str>,
+    },
+    Failed {
+        display: &'a str,
+        error: &'a Error,
+    },
+    Finished {
+        report: &'a InstallReport,
+    },
+}
+
+pub trait Reporter {
+    fn report(&self, event: &Event);
+}
+
+// Coloured terminal output used by the CLI.
+pub struct TextReporter;
+
+impl Reporter for TextReporter {
+    fn report(&self, event: &Event) {
+        match *event {
+            Event::NothingToInstall => {
+                say!("{}", "No packages to install.".yellow())
+            }
+            Event::DependenciesAdded { names } => say!(
+                "{} {} {}",
+                "Also installing".bold(),
+                names.join(", ").cyan(),
+                "(required by the selected packages)".dimmed()
+            ),
+            Event::Skipped { display } => say!(
+                "{} {} — already installed",
+                "[skip]".yellow().bold(),
+                display.cyan()
+            ),
+            Event::Outdated {
+                display,
+                installed,
+                wanted,
+            } => say!(
+                "{} {} — installed {}, wants {}",
+                "[outdated]".magenta().bold(),
+                display.cyan(),
+                installed,
+                wanted
+            ),
+            Event::WouldRun { display, command } => say!(
+                "{} {} — would run: {}",
+                "[dry-run]".cyan().bold(),
+                display.cyan(),
+                command.dimmed()
+            ),
+            Event::Installing { display, command } => say!(
+                "{} {} — {}",
+                "[install]".blue().bold(),
+                display.cyan(),
+                command.dimmed()
+            ),
+            Event::Installed { display, version } => say!(
+                "{} {} {}",
+                "[ok]".green().bold(),
+                display.cyan(),
+                version.unwrap_or_default().dimmed()
+            ),
+            Event::Failed { display, error } => eprintln!(
+                "{} {} — {}",
+                "[fail]".red().bold(),
+                display.cyan(),
+                error::chain(error)
+            ),
+            Event::Finished { report } => say!(
+                "\n{} {} {}  {} skipped  {} failed",
+                "Summary:".bold(),
+                report.installed.to_string().green().bold(),
+                if report.dry_run {
+                    "would install"
+                } else {
+                    "installed"
+                },
+                report.skipped.to_string().yellow().bold(),
+                report.failed.to_string().red().bold()
+            ),
+        }
+    }
+}
+
+// Discards every event, for library callers that only want the results.
+pub struct SilentReporter;
+
+impl Reporter for SilentReporter {
+    fn report(&self, _event: &Event) {}
+}
@@ -0,0 +1,186 @@
+use std::{
+    collections::{HashMap, HashSet},
+    io,
+    process::{Command, Stdio},
+    sync::Mutex,
+};
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CommandOutput {
+    pub code: Option<i32>,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+impl CommandOutput {
+    pub fn success(&self) -> bool {
+        self.code == Some(0)
+    }
+}
+
+// Everything the installer executes goes through a runner: `output` for
+// read-only probes (detect and version commands) whose output is captured,
+// `run` for install commands, and `is_available` to look up installer
+// binaries.
+pub trait CommandRunner: Send + Sync {
+    fn output(&self, command: &str) -> io::Result<CommandOutput>;
+    fn run(&self, command: &str) -> io::Result<CommandOutput>;
+    fn is_available(&self, program: &str) -> bool;
+
+    // Release downloads and their install records live on this machine, so
+    // they only apply when commands run here too.
+    fn is_local(&self) -> bool {
+        true
+    }
+
+    // A runner that only records install commands turns the run into a dry
+    // run.
+    fn is_dry_run(&self) -> bool {
+        false
+    }
+}
+
+// Runs commands on this machine through `sh -c`.
+pub struct SystemRunner;
+
+impl CommandRunner for SystemRunner {
+    fn output(&self, command: &str) -> io::Result<CommandOutput> {
+        let output = Command::new("sh").arg("-c").arg(command).output()?;
+        Ok(CommandOutput {
+            code: output.status.code(),
+            stdout: String::from_utf8_lossy(&output.stdout).to_string(),
+            stderr: String::from_utf8_lossy(&output.stderr).to_string(),
+        })
+    }
+
+    // Installers may prompt or show progress, so their output is not
+    // captured. With a structured document on stdout it must not interleave
+    // with it either.
+    fn run(&self, command: &str) -> io::Result<CommandOutput> {
+        let mut cmd = Command::new("sh");
+        cmd.arg("-c").arg(command);
+        if crate::output::is_structured() {
+            cmd.stdout(Stdio::null());
+        }
+        let status = cmd.status()?;
+        Ok(CommandOutput {
+            code: status.code(),
+            ..Default::default()
+        })
+    }
+
+    fn is_available(&self, program: &str) -> bool {
+        self.output(&format!("command -v {}", program))
+            .is_ok_and(|o| o.success())
+    }
+}
+
+// Probes go to the wrapped runner so detection still reflects the machine,
+// but install commands are only recorded and reported as successful.
+pub struct RecordingRunner<'a> {
+    inner: &'a dyn CommandRunner,
+    recorded: Mutex<Vec<String>>,
+}
+
+impl<'a> RecordingRunner<'a> {
+    pub fn new(inner: &'a dyn CommandRunner) -> Self {
+        RecordingRunner {
+            inner,
+            recorded: Mutex::new(Vec::new()),
+        }
+    }
+
+    pub fn recorded(&self) -> Vec<String> {
+        self.recorded.lock().unwrap().clone()
+    }
+}
+
+impl CommandRunner for RecordingRunner<'_> {
+    fn output(&self, command: &str) -> io::Result<CommandOutput> {
+        self.inner.output(command)
+    }
+
+    fn run(&self, command: &str) -> io::Result<CommandOutput> {
+        log::debug!("recording instead of running: {}", command);
+        self.recorded.lock().unwrap().push(command.to_string());
+        Ok(CommandOutput {
+            code: Some(0),
+            ..Default::default()
+        })
+    }
+
+    fn is_available(&self, program: &str) -> bool {
+        self.inner.is_available(program)
+    }
+
+    fn is_local(&self) -> bool {
+        self.inner.is_local()
+    }
+
+    fn is_dry_run(&self) -> bool {
+        true
+    }
+}
+
+// Answers from a script instead of executing anything. Commands that were
+// not scripted exit with 127, like an unknown command in `sh`.
+#[derive(Default)]
+pub struct FakeRunner {
+    responses: HashMap<String, CommandOutput>,
+    programs: HashSet<String>,
+    calls: Mutex<Vec<String>>,
+}
+
+impl FakeRunner {
+    pub fn new() -> Self {
+        FakeRunner::default()
+    }
+
+    pub fn with_program(mut self, program: &str) -> Self {
+        self.programs.insert(program.to_string());
+        self
+    }
+
+    pub fn respond(mut self, command: &str, code: i32, stdout: &str) -> Self {
+        self.responses.insert(
+            command.to_string(),
+            CommandOutput {
+                code: Some(code),
+                stdout: stdout.to_string(),
+                stderr: String::new(),
+            },
+        );
+        self
+    }
+
+    // Every command executed through `output` or `run`, in order.
+    pub fn calls(&self) -> Vec<String> {
+        self.calls.lock().unwrap().clone()
+    }
+
+    fn answer(&self, command: &str) -> CommandOutput {
+        self.calls.lock().unwrap().push(command.to_string());
+        self.responses
+            .get(command)
+            .cloned()
+            .unwrap_or_else(|| CommandOutput {
+                code: Some(127),
+                stdout: String::new(),
+                stderr: format!("{}: command not scripted", command),
+            })
+    }
+}
+
+impl CommandRunner for FakeRunner {
+    fn output(&self, command: &str) -> io::Result<CommandOutput> {
+        Ok(self.answer(command))
+    }
+
+    fn run(&self, command: &str) -> io::Result<CommandOutput> {
+        Ok(self.answer(command))
+    }
+
+    fn is_available(&self, program: &str) -> bool {
+        self.programs.contains(program)
+    }
+}
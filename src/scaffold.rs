@@ -0,0 +1,413 @@
+use std::{
+    collections::BTreeMap,
+    io::{self, BufRead, IsTerminal, Write},
+    path::{Path, PathBuf},
+};
+
+use colored::Colorize;
+use serde::Serialize;
+
+use crate::{
+    error::{Error, Result},
+    output::{self, note, say},
+    registry,
+    runner::CommandRunner,
+};
+
+// Field order matches the hand-written definitions in the registry.
+#[derive(Serialize, Debug, Clone, Default, PartialEq)]
+pub struct Definition {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub display: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detect: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub dependencies: Vec<String>,
+    pub packages: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct NewPackage {
+    pub name: String,
+    pub display: Option<String>,
+    pub detect: Option<String>,
+    // `installer=package` pairs given on the command line.
+    pub installers: Vec<String>,
+    pub custom: Option<String>,
+    pub dependencies: Vec<String>,
+    pub probe: bool,
+    pub force: bool,
+}
+
+// Each probe succeeds only when the package manager knows the package, and
+// is skipped when that package manager is not installed.
+fn probe_apt(name: &str, runner: &dyn CommandRunner) -> Option<String> {
+    if !runner.is_available("apt-cache") {
+        return None;
+    }
+    let output = runner.output(&format!("apt-cache show {}", name)).ok()?;
+    (output.success() && !output.stdout.trim().is_empty())
+        .then(|| name.to_string())
+}
+
+fn probe_pacman(name: &str, runner: &dyn CommandRunner) -> Option<String> {
+    if !runner.is_available("pacman") {
+        return None;
+    }
+    let output = runner.output(&format!("pacman -Si {}", name)).ok()?;
+    output.success().then(|| name.to_string())
+}
+
+// Also returns the formula's description, the closest thing to a display
+// name any of the package managers offers.
+fn probe_brew(
+    name: &str,
+    runner: &dyn CommandRunner,
+) -> Option<(String, Option<String>)> {
+    if !runner.is_available("brew") {
+        return None;
+    }
+    let output = runner
+        .output(&format!("brew info --json=v2 {}", name))
+        .ok()?;
+    if !output.success() {
+        return None;
+    }
+    let info: serde_json::Value = serde_json::from_str(&output.stdout).ok()?;
+    if let Some(formula) = info["formulae"].get(0) {
+        let full_name = formula["full_name"].as_str().unwrap_or(name);
+        let desc = formula["desc"].as_str().map(String::from);
+        return Some((full_name.to_string(), desc));
+    }
+    let cask = info["casks"].get(0)?;
+    let token = cask["token"].as_str().unwrap_or(name);
+    let display = cask["name"].get(0).and_then(|n| n.as_str());
+    Some((token.to_string(), display.map(String::from)))
+}
+
+// `<name> --version` doubles as a version probe, so it is preferred when the
+// command is already installed here and answers it.
+fn suggest_detect(name: &str, runner: &dyn CommandRunner) -> String {
+    let version = format!("{} --version", name);
+    if runner.is_available(name)
+        && runner.output(&version).is_ok_and(|o| o.success())
+    {
+        version
+    } else {
+        format!("command -v {}", name)
+    }
+}
+
+pub(crate) const NAME_RULE: &str =
+    "names may only contain letters, digits, '-', '_' and '.'";
+
+pub(crate) fn valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+        && !name.starts_with('.')
+}
+
+// `dir/<name><suffix>` for a name read from a file or the command line. The
+// name has to be valid so the path cannot end up outside `dir`.
+pub(crate) fn path_in(dir: &Path, name: &str, suffix: &str) -> Result<PathBuf> {
+    let path = dir.join(format!("{}{}", name, suffix));
+    if !valid_name(name) || path.parent() != Some(dir) {
+        return Err(Error::InvalidName {
+            name: name.to_string(),
+            reason: NAME_RULE.to_string(),
+        });
+    }
+    Ok(path)
+}
+
+fn invalid(name: &str, message: impl Into<String>) -> Error {
+    Error::InvalidDefinition {
+        name: name.to_string(),
+        message: message.into(),
+    }
+}
+
+// Flags win over probe results; probes only fill what was left open.
+pub fn build_definition(
+    new: &NewPackage,
+    runner: &dyn CommandRunner,
+) -> Result<Definition> {
+    if !valid_name(&new.name) {
+        return Err(invalid(&new.name, NAME_RULE));
+    }
+
+    let mut def = Definition {
+        display: new.display.clone(),
+        detect: new.detect.clone(),
+        dependencies: new.dependencies.clone(),
+        packages: BTreeMap::new(),
+    };
+    for pair in &new.installers {
+        let (installer, value) = pair
+            .split_once('=')
+            .filter(|(i, v)| !i.is_empty() && !v.is_empty())
+            .ok_or_else(|| {
+                invalid(
+                    &new.name,
+                    format!("expected INSTALLER=PACKAGE, got '{}'", pair),
+                )
+            })?;
+        def.packages
+            .insert(installer.to_string(), value.to_string());
+    }
+    if let Some(custom) = &new.custom {
+        def.packages.insert("custom".to_string(), custom.clone());
+    }
+
+    if new.probe {
+        log::debug!("probing package managers for '{}'", new.name);
+        if let Some(value) = probe_apt(&new.name, runner) {
+            def.packages.entry("apt".to_string()).or_insert(value);
+        }
+        if let Some(value) = probe_pacman(&new.name, runner) {
+            def.packages.entry("pacman".to_string()).or_insert(value);
+        }
+        if let Some((value, desc)) = probe_brew(&new.name, runner) {
+            def.packages.entry("brew".to_string()).or_insert(value);
+            if def.display.is_none() {
+                def.display = desc;
+            }
+        }
+        if def.detect.is_none() {
+            def.detect = Some(suggest_detect(&new.name, runner));
+        }
+    }
+    Ok(def)
+}
+
+pub fn validate(name: &str, def: &Definition) -> Result<String> {
+    if def.packages.is_empty() {
+        return Err(invalid(
+            name,
+            "no installer found; pass --installer or --custom",
+        ));
+    }
+    for dep in &def.dependencies {
+        if dep == name {
+            return Err(invalid(name, "a package cannot depend on itself"));
+        }
+        if !registry::is_package_in_registry(dep)? {
+            return Err(registry::package_not_found(dep));
+        }
+    }
+    let content = toml::to_string(def).map_err(Error::serialize("package"))?;
+    // Read it back the way the registry will, so a definition that cannot be
+    // loaded never gets written.
+    toml::from_str::<toml::Value>(&content)
+        .map_err(|e| Error::registry_parse(name, &content, &e))?;
+    Ok(content)
+}
+
+fn prompt(label: &str, current: Option<&str>) -> Result<Option<String>> {
+    match current {
+        Some(value) => eprint!("{} [{}]: ", label.bold(), value.dimmed()),
+        None => eprint!("{}: ", label.bold()),
+    }
+    io::stderr().flush().ok();
+    let mut line = String::new();
+    io::stdin()
+        .lock()
+        .read_line(&mut line)
+        .map_err(Error::io("Failed to read answer"))?;
+    Ok(match line.trim() {
+        "" => current.map(String::from),
+        "-" => None,
+        answer => Some(answer.to_string()),
+    })
+}
+
+// Walks through every field with the flag or probe result as default. An
+// empty answer keeps it and `-` clears it.
+fn ask(name: &str, def: &mut Definition) -> Result<()> {
+    note!(
+        "{} '{}' (Enter keeps the suggestion, '-' clears it)",
+        "New registry package".bold(),
+        name.cyan()
+    );
+    def.display = prompt("Display name", def.display.as_deref())?;
+    def.detect = prompt("Detect command", def.detect.as_deref())?;
+    for installer in ["apt", "brew", "pacman", "dnf", "winget", "custom"] {
+        let current = def.packages.get(installer).cloned();
+        match prompt(&format!("{} package", installer), current.as_deref())? {
+            Some(value) => def.packages.insert(installer.to_string(), value),
+            None => def.packages.remove(installer),
+        };
+    }
+    let deps = def.dependencies.join(", ");
+    let answer =
+        prompt("Dependencies", (!deps.is_empty()).then_some(&deps[..]))?;
+    def.dependencies = answer
+        .map(|a| {
+            a.split(',')
+                .map(|d| d.trim().to_string())
+                .filter(|d| !d.is_empty())
+                .collect()
+        })
+        .unwrap_or_default();
+    Ok(())
+}
+
+pub fn new_package(
+    new: &NewPackage,
+    interactive: bool,
+    runner: &dyn CommandRunner,
+) -> Result<Definition> {
+    let existing = registry::registry_dir().join(format!("{}.toml", new.name));
+    if existing.exists() && !new.force {
+        return Err(Error::RegistryEntryExists(new.name.clone()));
+    }
+
+    let mut def = build_definition(new, runner)?;
+    if interactive && io::stdin().is_terminal() && !output::is_structured() {
+        ask(&new.name, &mut def)?;
+    }
+    let content = validate(&new.name, &def)?;
+    let path = registry::write_package(&new.name, &content)?;
+
+    if output::is_structured() {
+        output::emit(&def)?;
+    } else {
+        say!(
+            "{} '{}' at {:?}\n",
+            "Package added to registry:".green(),
+            new.name.cyan(),
+            path
+        );
+        say!("{}", content.trim_end().dimmed());
+    }
+    Ok(def)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs};
+
+    use serial_test::serial;
+    use tempfile::TempDir;
+
+    use super::*;
+    use crate::runner::FakeRunner;
+
+    fn setup_test_env() -> TempDir {
+        let temp_dir = TempDir::new().expect("Failed to create temp dir");
+        unsafe {
+            env::set_var("HOME", temp_dir.path());
+            env::set_var("XDG_CONFIG_HOME", temp_dir.path().join(".config"));
+        }
+        let reg_dir = registry::registry_dir();
+        fs::create_dir_all(&reg_dir).unwrap();
+        fs::write(reg_dir.join("curl.toml"), "[packages]\napt = \"curl\"\n")
+            .unwrap();
+        temp_dir
+    }
+
+    fn new(name: &str) -> NewPackage {
+        NewPackage {
+            name: name.to_string(),
+            probe: true,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_build_definition_from_probes() {
+        let runner = FakeRunner::new()
+            .with_program("apt-cache")
+            .with_program("brew")
+            .with_program("fd")
+            .respond("apt-cache show fd", 0, "Package: fd-find\n")
+            .respond(
+                "brew info --json=v2 fd",
+                0,
+                r#"{"formulae":[{"full_name":"fd","desc":"Simple, fast alternative to find"}],"casks":[]}"#,
+            )
+            .respond("fd --version", 0, "fd 10.1.0");
+
+        let def = build_definition(&new("fd"), &runner).unwrap();
+        assert_eq!(def.detect.as_deref(), Some("fd --version"));
+        assert_eq!(
+            def.display.as_deref(),
+            Some("Simple, fast alternative to find")
+        );
+        assert_eq!(def.packages.get("apt").map(String::as_str), Some("fd"));
+        assert_eq!(def.packages.get("brew").map(String::as_str), Some("fd"));
+        assert!(!def.packages.contains_key("pacman"));
+    }
+
+    #[test]
+    fn test_flags_override_probes() {
+        let runner = FakeRunner::new().with_program("apt-cache").respond(
+            "apt-cache show fd",
+            0,
+            "Package: fd\n",
+        );
+        let def = build_definition(
+            &NewPackage {
+                installers: vec!["apt=fd-find".to_string()],
+                detect: Some("fdfind --version".to_string()),
+                ..new("fd")
+            },
+            &runner,
+        )
+        .unwrap();
+        assert_eq!(
+            def.packages.get("apt").map(String::as_str),
+            Some("fd-find")
+        );
+        assert_eq!(def.detect.as_deref(), Some("fdfind --version"));
+
+        let bad = build_definition(
+            &NewPackage {
+                installers: vec!["apt".to_string()],
+                ..new("fd")
+            },
+            &runner,
+        );
+        assert!(matches!(bad, Err(Error::InvalidDefinition { .. })));
+        let bad = build_definition(&new("../fd"), &runner);
+        assert!(matches!(bad, Err(Error::InvalidDefinition { .. })));
+    }
+
+    #[test]
+    #[serial]
+    fn test_new_package_validates_and_writes() {
+        let _temp = setup_test_env();
+        let runner = FakeRunner::new();
+
+        let empty = new_package(&new("tool"), false, &runner);
+        assert!(matches!(empty, Err(Error::InvalidDefinition { .. })));
+
+        let unknown_dep = NewPackage {
+            custom: Some("install-tool".to_string()),
+            dependencies: vec!["ghost".to_string()],
+            ..new("tool")
+        };
+        let result = new_package(&unknown_dep, false, &runner);
+        assert!(matches!(result, Err(Error::PackageNotFound { .. })));
+
+        let tool = NewPackage {
+            custom: Some("install-tool".to_string()),
+            dependencies: vec!["curl".to_string()],
+            ..new("tool")
+        };
+        new_package(&tool, false, &runner).unwrap();
+        let details = registry::get_package_details("tool").unwrap();
+        assert_eq!(details.dependencies, vec!["curl"]);
+        assert_eq!(details.detect.as_deref(), Some("command -v tool"));
+
+        let again = new_package(&tool, false, &runner);
+        assert!(matches!(again, Err(Error::RegistryEntryExists(_))));
+        let forced = NewPackage {
+            force: true,
+            ..tool
+        };
+        assert!(new_package(&forced, false, &runner).is_ok());
+    }
+}
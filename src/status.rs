@@ -0,0 +1,463 @@
+use std::{sync::Mutex, thread};
+
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::{self, Error, Result},
+    installer,
+    output::{self, say},
+    profile::{self, PackageRef, Profile, ProfilePackage},
+    registry,
+    runner::SystemRunner,
+    version::VersionReq,
+};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum PackageState {
+    Installed,
+    Missing,
+    Outdated,
+    Unknown,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PackageStatus {
+    pub name: String,
+    pub display: String,
+    pub state: PackageState,
+    pub installer: Option<String>,
+    pub version: Option<String>,
+    pub requirement: Option<String>,
+    pub error: Option<String>,
+    // Not in the profile itself, but installed along with it.
+    #[serde(default)]
+    pub dependency: bool,
+}
+
+// At most this many detect commands run at once.
+const MAX_DETECT_THREADS: usize = 8;
+
+// Looks the package up in the registry and picks its installer. This reads
+// the registry, so it runs on the calling thread.
+fn resolve_package(
+    pkg_ref: &PackageRef,
+    detected: Option<&str>,
+) -> (PackageStatus, Option<ProfilePackage>) {
+    let mut status = PackageStatus {
+        name: pkg_ref.name.clone(),
+        display: pkg_ref.name.clone(),
+        state: PackageState::Unknown,
+        installer: None,
+        version: None,
+        requirement: pkg_ref.version.clone(),
+        error: None,
+        dependency: false,
+    };
+
+    let pkg = match registry::get_package_details(&pkg_ref.name) {
+        Ok(p) => p,
+        Err(e) => {
+            status.error = Some(error::chain(&e));
+            return (status, None);
+        }
+    };
+    if let Some(display) = &pkg.display {
+        status.display = display.clone();
+    }
+    match installer::select_installer(&pkg, &pkg_ref.installer, detected) {
+        Ok((name, _)) => status.installer = Some(name),
+        Err(e) => status.error = Some(error::chain(&e)),
+    }
+    (status, Some(pkg))
+}
+
+fn detect_package(
+    status: &mut PackageStatus,
+    pkg: &ProfilePackage,
+) -> Result<()> {
+    if !installer::is_installed(pkg, &SystemRunner) {
+        status.state = PackageState::Missing;
+        return Ok(());
+    }
+
+    status.version = installer::detect_version(pkg, &SystemRunner);
+    status.state = match (&status.requirement, &status.version) {
+        (Some(req), Some(found)) if !VersionReq::parse(req)?.matches(found) => {
+            PackageState::Outdated
+        }
+        _ => PackageState::Installed,
+    };
+    Ok(())
+}
+
+// The profile's packages followed by the registry dependencies `install`
+// would bring along with them.
+fn with_dependencies(profile: &Profile) -> Result<Vec<(PackageRef, bool)>> {
+    let names: Vec<String> = profile
+        .packages
+        .iter()
+        .filter(|p| registry::get_package_details(&p.name).is_ok())
+        .map(|p| p.name.clone())
+        .collect();
+    let mut packages: Vec<(PackageRef, bool)> = profile
+        .packages
+        .iter()
+        .map(|p| (p.clone(), false))
+        .collect();
+    for name in registry::dependency_closure(&names)? {
+        if !profile.packages.iter().any(|p| p.name == name) {
+            let dependency = PackageRef {
+                name,
+                ..Default::default()
+            };
+            packages.push((dependency, true));
+        }
+    }
+    Ok(packages)
+}
+
+// Detect commands can be slow (they often spawn the tool itself), so they run
+// on a small pool of threads once every package has been resolved.
+pub fn check_profile(profile: &Profile) -> Result<Vec<PackageStatus>> {
+    let packages = with_dependencies(profile)?;
+    log::debug!(
+        "checking status of {} packages in '{}'",
+        packages.len(),
+        profile.name
+    );
+    let detected = installer::detect_available_installer(&SystemRunner);
+    let (mut statuses, packages): (Vec<_>, Vec<_>) = packages
+        .iter()
+        .map(|(pkg_ref, dependency)| {
+            let (status, pkg) = resolve_package(pkg_ref, detected.as_deref());
+            (
+                PackageStatus {
+                    dependency: *dependency,
+                    ..status
+                },
+                pkg,
+            )
+        })
+        .unzip();
+
+    let threads = thread::available_parallelism()
+        .map_or(1, |n| n.get())
+        .min(MAX_DETECT_THREADS);
+    let queue = Mutex::new(
+        statuses
+            .iter_mut()
+            .zip(&packages)
+            .filter_map(|(status, pkg)| Some((status, pkg.as_ref()?))),
+    );
+    thread::scope(|scope| {
+        for _ in 0..threads {
+            scope.spawn(|| {
+                loop {
+                    let next =
+                        queue.lock().unwrap_or_else(|e| e.into_inner()).next();
+                    let Some((status, pkg)) = next else { break };
+                    if let Err(e) = detect_package(status, pkg) {
+                        status.state = PackageState::Unknown;
+                        status.error = Some(error::chain(&e));
+                    }
+                }
+            });
+        }
+    });
+    Ok(statuses)
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct StatusSummary {
+    pub installed: usize,
+    pub missing: usize,
+    pub outdated: usize,
+    pub unknown: usize,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct StatusReport {
+    pub profile: String,
+    pub packages: Vec<PackageStatus>,
+    pub summary: StatusSummary,
+}
+
+impl StatusReport {
+    pub fn failing(&self) -> usize {
+        self.summary.missing + self.summary.outdated + self.summary.unknown
+    }
+}
+
+pub fn profile_status(profile_name: &str) -> Result<StatusReport> {
+    let profile = profile::read_profile(profile_name)?;
+    let statuses = check_profile(&profile)?;
+
+    let count = |state| statuses.iter().filter(|s| s.state == state).count();
+    let summary = StatusSummary {
+        installed: count(PackageState::Installed),
+        missing: count(PackageState::Missing),
+        outdated: count(PackageState::Outdated),
+        unknown: count(PackageState::Unknown),
+    };
+    log::info!(
+        "status '{}': {} installed, {} missing, {} outdated, {} unknown",
+        profile_name,
+        summary.installed,
+        summary.missing,
+        summary.outdated,
+        summary.unknown
+    );
+    Ok(StatusReport {
+        profile: profile_name.to_string(),
+        packages: statuses,
+        summary,
+    })
+}
+
+pub fn show_status(profile_name: &str) -> Result<()> {
+    let report = profile_status(profile_name)?;
+    if output::is_structured() {
+        output::emit(&report)?;
+    } else {
+        print_status(&report);
+    }
+
+    let failing = report.failing();
+    if failing > 0 {
+        return Err(Error::Unsatisfied {
+            profile: profile_name.to_string(),
+            failing,
+            total: report.packages.len(),
+        });
+    }
+    Ok(())
+}
+
+fn print_status(report: &StatusReport) {
+    let profile_name = &report.profile;
+    let statuses = &report.packages;
+    let summary = &report.summary;
+    say!(
+        "{} {}",
+        "Status of profile".bold(),
+        profile_name.cyan().bold()
+    );
+    if statuses.is_empty() {
+        say!("  {}", "No packages in this profile.".dimmed());
+    }
+
+    let width = statuses.iter().map(|s| s.display.len()).max().unwrap_or(0);
+    for status in statuses {
+        let label = match status.state {
+            PackageState::Installed => "[ok]      ".green().bold(),
+            PackageState::Missing => "[missing] ".red().bold(),
+            PackageState::Outdated => "[outdated]".magenta().bold(),
+            PackageState::Unknown => "[unknown] ".yellow().bold(),
+        };
+        let mut details = Vec::new();
+        if let Some(installer) = &status.installer {
+            details.push(installer.clone());
+        }
+        if let Some(version) = &status.version {
+            details.push(version.clone());
+        }
+        if status.state == PackageState::Outdated
+            && let Some(req) = &status.requirement
+        {
+            details.push(format!("wants {}", req));
+        }
+        if let Some(e) = &status.error {
+            details.push(e.clone());
+        }
+        if status.dependency {
+            details.push("(dependency)".to_string());
+        }
+        say!(
+            "  {} {:<width$}  {}",
+            label,
+            status.display.cyan(),
+            details.join("  ").dimmed(),
+            width = width
+        );
+    }
+
+    say!(
+        "\n{} {} installed  {} missing  {} outdated  {} unknown",
+        "Summary:".bold(),
+        summary.installed.to_string().green().bold(),
+        summary.missing.to_string().red().bold(),
+        summary.outdated.to_string().magenta().bold(),
+        summary.unknown.to_string().yellow().bold()
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs};
+
+    use serial_test::serial;
+    use tempfile::TempDir;
+
+    use super::*;
+
+    fn setup_test_env() -> TempDir {
+        let temp_dir = TempDir::new().expect("Failed to create temp dir");
+        unsafe {
+            env::set_var("HOME", temp_dir.path());
+            env::set_var("XDG_CONFIG_HOME", temp_dir.path().join(".config"));
+        }
+
+        let reg_dir = crate::config::config_dir().join("registry");
+        fs::create_dir_all(&reg_dir).unwrap();
+        fs::write(reg_dir.join("metadata.toml"), "version = \"1\"\n").unwrap();
+        for (name, detect) in
+            [("present", "echo present 1.4.2"), ("absent", "false")]
+        {
+            fs::write(
+                reg_dir.join(format!("{}.toml", name)),
+                format!(
+                    "detect = \"{}\"\n\n[packages]\ncustom = \"install.sh\"\n",
+                    detect
+                ),
+            )
+            .unwrap();
+        }
+        temp_dir
+    }
+
+    fn pkg_ref(name: &str, version: Option<&str>) -> PackageRef {
+        PackageRef {
+            name: name.to_string(),
+            version: version.map(String::from),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    #[serial]
+    fn test_check_profile_states() {
+        let _temp = setup_test_env();
+        let profile = Profile {
+            name: "status".to_string(),
+            origin: None,
+            packages: vec![
+                pkg_ref("present", None),
+                pkg_ref("absent", None),
+                pkg_ref("ghost", None),
+            ],
+        };
+
+        let statuses = check_profile(&profile).unwrap();
+        assert_eq!(statuses.len(), 3);
+        assert_eq!(statuses[0].state, PackageState::Installed);
+        assert_eq!(statuses[0].version, Some("1.4.2".to_string()));
+        assert_eq!(statuses[0].installer, Some("custom".to_string()));
+        assert_eq!(statuses[1].state, PackageState::Missing);
+        assert_eq!(statuses[2].state, PackageState::Unknown);
+        assert!(statuses[2].error.is_some());
+    }
+
+    #[test]
+    #[serial]
+    fn test_check_profile_reports_dependencies() {
+        let _temp = setup_test_env();
+        let reg_dir = crate::config::config_dir().join("registry");
+        fs::write(
+            reg_dir.join("app.toml"),
+            "detect = \"true\"\ndependencies = [\"absent\", \"present\"]\n\n[packages]\ncustom = \"install.sh\"\n",
+        )
+        .unwrap();
+        let profile = Profile {
+            name: "status".to_string(),
+            origin: None,
+            packages: vec![pkg_ref("app", None), pkg_ref("present", None)],
+        };
+
+        let statuses = check_profile(&profile).unwrap();
+        let names: Vec<(&str, bool)> = statuses
+            .iter()
+            .map(|s| (s.name.as_str(), s.dependency))
+            .collect();
+        assert_eq!(
+            names,
+            vec![("app", false), ("present", false), ("absent", true)]
+        );
+        assert_eq!(statuses[2].state, PackageState::Missing);
+    }
+
+    #[test]
+    #[serial]
+    fn test_check_profile_outdated() {
+        let _temp = setup_test_env();
+        let profile = Profile {
+            name: "status".to_string(),
+            origin: None,
+            packages: vec![
+                pkg_ref("present", Some("1.5.x")),
+                pkg_ref("present", Some(">=1.4")),
+            ],
+        };
+
+        let statuses = check_profile(&profile).unwrap();
+        assert_eq!(statuses[0].state, PackageState::Outdated);
+        assert_eq!(statuses[1].state, PackageState::Installed);
+    }
+
+    #[test]
+    #[serial]
+    fn test_check_profile_keeps_order_beyond_pool_size() {
+        let _temp = setup_test_env();
+        let names = ["present", "absent", "ghost"];
+        let profile = Profile {
+            name: "status".to_string(),
+            origin: None,
+            packages: (0..3 * MAX_DETECT_THREADS)
+                .map(|i| pkg_ref(names[i % 3], None))
+                .collect(),
+        };
+
+        let statuses = check_profile(&profile).unwrap();
+        assert_eq!(statuses.len(), profile.packages.len());
+        for (i, status) in statuses.iter().enumerate() {
+            assert_eq!(status.name, names[i % 3]);
+            let expected = [
+                PackageState::Installed,
+                PackageState::Missing,
+                PackageState::Unknown,
+            ];
+            assert_eq!(status.state, expected[i % 3]);
+        }
+    }
+
+    #[test]
+    #[serial]
+    fn test_show_status_fails_when_missing() {
+        let _temp = setup_test_env();
+        profile::write_profile(&Profile {
+            name: "status".to_string(),
+            origin: None,
+            packages: vec![pkg_ref("present", None), pkg_ref("absent", None)],
+        })
+        .unwrap();
+
+        let result = show_status("status");
+        assert!(matches!(
+            result,
+            Err(Error::Unsatisfied {
+                failing: 1,
+                total: 2,
+                ..
+            })
+        ));
+
+        profile::write_profile(&Profile {
+            name: "status".to_string(),
+            origin: None,
+            packages: vec![pkg_ref("present", None)],
+        })
+        .unwrap();
+        assert!(show_status("status").is_ok());
+    }
+}
@@ -0,0 +1,280 @@
+use std::{
+    fs,
+    io::{self, IsTerminal},
+};
+
+use colored::Colorize;
+use serde::Serialize;
+
+use crate::{
+    error::{self, Error, Result},
+    fetch::Source,
+    merge::{self, PackageChange},
+    output::{self, say},
+    profile::{self, FetchedProfile, Profile},
+    suggest,
+};
+
+// Relative paths are resolved so `sync` finds the file from any directory.
+fn origin_of(source: &str) -> Result<String> {
+    match Source::parse(source)? {
+        Source::Path(path) => Ok(fs::canonicalize(&path)
+            .map_err(Error::io(format!("Failed to resolve '{}'", path)))?
+            .display()
+            .to_string()),
+        _ => Ok(source.to_string()),
+    }
+}
+
+pub fn subscribe(source: &str, name: Option<&str>, trust: bool) -> Result<()> {
+    log::debug!("subscribing to '{}'", source);
+    let FetchedProfile {
+        mut profile,
+        registry,
+        ..
+    } = profile::fetch_profile(source, None)?;
+    if let Some(name) = name {
+        profile.name = name.to_string();
+    }
+    profile::check_profile_name(&profile.name)?;
+    if profile::profile_path(&profile.name).exists() {
+        log::error!("subscribe failed: profile '{}' exists", profile.name);
+        return Err(Error::ProfileExists(profile.name));
+    }
+    profile.origin = Some(origin_of(source)?);
+    if let Some(registry) = &registry {
+        profile::import_definitions(&profile.name, registry, trust)?;
+    }
+    profile::write_profile(&profile)?;
+
+    log::info!("profile '{}' subscribed to '{}'", profile.name, source);
+    say!(
+        "{} '{}' to {} ({} packages).",
+        "Subscribed".green(),
+        profile.name.cyan(),
+        source.cyan(),
+        profile.packages.len()
+    );
+    Ok(())
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct SyncReport {
+    pub profile: String,
+    pub origin: String,
+    pub changes: Vec<PackageChange>,
+    pub applied: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+fn sync_profile(
+    local: Profile,
+    origin: &str,
+    dry_run: bool,
+    yes: bool,
+    trust: bool,
+) -> Result<SyncReport> {
+    log::info!("syncing profile '{}' from '{}'", local.name, origin);
+    let FetchedProfile {
+        profile: remote,
+        registry,
+        ..
+    } = profile::fetch_profile(origin, None)?;
+    let changes = merge::diff(&local.packages, &remote.packages);
+
+    let mut report = SyncReport {
+        profile: local.name.clone(),
+        origin: origin.to_string(),
+        changes,
+        applied: false,
+        error: None,
+    };
+    say!(
+        "{} {} {}",
+        "Syncing".bold(),
+        report.profile.cyan().bold(),
+        format!("from {}", origin).dimmed()
+    );
+    if report.changes.is_empty() {
+        say!("  {}", "Already up to date.".dimmed());
+        return Ok(report);
+    }
+    merge::print_changes(&report.changes);
+    if dry_run {
+        return Ok(report);
+    }
+    let interactive = !yes && io::stdin().is_terminal();
+    if interactive
+        && !suggest::confirm(&format!("Apply to '{}'?", report.profile))?
+    {
+        say!("  {}", "Skipped.".yellow());
+        return Ok(report);
+    }
+
+    // The bundle's definitions are refreshed along with the package list.
+    if let Some(registry) = &registry {
+        profile::import_definitions(&local.name, registry, trust)?;
+    }
+    profile::write_profile(&Profile {
+        name: local.name,
+        origin: local.origin,
+        packages: remote.packages,
+    })?;
+    report.applied = true;
+    say!("  {}", "Applied.".green());
+    Ok(report)
+}
+
+// Syncs one profile, or every subscribed profile when none is named.
+pub fn sync(
+    profile_name: Option<&str>,
+    dry_run: bool,
+    yes: bool,
+    trust: bool,
+) -> Result<()> {
+    let names = match profile_name {
+        Some(name) => vec![name.to_string()],
+        None => profile::profile_names(),
+    };
+    let mut reports = Vec::new();
+    for name in names {
+        let local = match profile::read_profile(&name) {
+            Ok(local) => local,
+            Err(e) if profile_name.is_none() => {
+                log::warn!("skipping unreadable profile '{}': {}", name, e);
+                continue;
+            }
+            Err(e) => return Err(e),
+        };
+        let Some(origin) = local.origin.clone() else {
+            if profile_name.is_some() {
+                return Err(Error::Unsupported(format!(
+                    "Profile '{}' is not subscribed to a remote profile",
+                    name
+                )));
+            }
+            continue;
+        };
+        // When syncing everything, one unreachable source doesn't hold up
+        // the rest.
+        match sync_profile(local, &origin, dry_run, yes, trust) {
+            Ok(report) => reports.push(report),
+            Err(e) if profile_name.is_some() => return Err(e),
+            Err(e) => {
+                log::error!("sync of '{}' failed: {}", name, e);
+                say!(
+                    "{} {}: {}",
+                    "Failed to sync".red().bold(),
+                    name.cyan(),
+                    error::chain(&e)
+                );
+                reports.push(SyncReport {
+                    profile: name,
+                    origin,
+                    changes: Vec::new(),
+                    applied: false,
+                    error: Some(error::chain(&e)),
+                });
+            }
+        }
+    }
+
+    if output::is_structured() {
+        output::emit(&reports)?;
+    } else if reports.is_empty() {
+        say!("{}", "No subscribed profiles.".yellow());
+    }
+
+    let failed = reports.iter().filter(|r| r.error.is_some()).count();
+    if failed > 0 {
+        return Err(Error::SyncIncomplete {
+            failed,
+            total: reports.len(),
+        });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs};
+
+    use serial_test::serial;
+    use tempfile::TempDir;
+
+    use super::*;
+
+    fn setup_test_env() -> TempDir {
+        let temp_dir = TempDir::new().expect("Failed to create temp dir");
+        unsafe {
+            env::set_var("HOME", temp_dir.path());
+            env::set_var("XDG_CONFIG_HOME", temp_dir.path().join(".config"));
+        }
+        fs::create_dir_all(crate::config::profiles_dir()).unwrap();
+        temp_dir
+    }
+
+    #[test]
+    #[serial]
+    fn test_subscribe_and_sync() {
+        let temp = setup_test_env();
+        let remote = temp.path().join("team.toml");
+        fs::write(&remote, "name = \"team\"\n\n[[packages]]\nname = \"git\"\n")
+            .unwrap();
+        let source = remote.to_str().unwrap();
+
+        subscribe(source, Some("work"), false).unwrap();
+        let work = profile::read_profile("work").unwrap();
+        let origin = fs::canonicalize(&remote).unwrap();
+        assert_eq!(work.origin.as_deref(), origin.to_str());
+        assert!(matches!(
+            subscribe(source, Some("work"), false),
+            Err(Error::ProfileExists(_))
+        ));
+
+        fs::write(
+            &remote,
+            "name = \"team\"\n\n[[packages]]\nname = \"curl\"\n\n[[packages]]\nname = \"git\"\n",
+        )
+        .unwrap();
+        sync(Some("work"), true, true, false).unwrap();
+        assert_eq!(profile::read_profile("work").unwrap().packages.len(), 1);
+
+        sync(None, false, true, false).unwrap();
+        let work = profile::read_profile("work").unwrap();
+        assert_eq!(work.packages.len(), 2);
+        assert_eq!(work.origin.as_deref(), origin.to_str());
+    }
+
+    #[test]
+    #[serial]
+    fn test_sync_continues_past_failing_sources() {
+        let temp = setup_test_env();
+        for name in ["a", "b"] {
+            let remote = temp.path().join(format!("{}.toml", name));
+            fs::write(&remote, "name = \"team\"\npackages = []\n").unwrap();
+            subscribe(remote.to_str().unwrap(), Some(name), false).unwrap();
+        }
+        fs::remove_file(temp.path().join("a.toml")).unwrap();
+        fs::write(
+            temp.path().join("b.toml"),
+            "name = \"team\"\n\n[[packages]]\nname = \"git\"\n",
+        )
+        .unwrap();
+
+        let result = sync(None, false, true, false);
+        assert!(matches!(
+            result,
+            Err(Error::SyncIncomplete {
+                failed: 1,
+                total: 2
+            })
+        ));
+        assert_eq!(profile::read_profile("b").unwrap().packages.len(), 1);
+        assert!(matches!(
+            sync(Some("a"), false, true, false),
+            Err(Error::Io { .. })
+        ));
+    }
+}
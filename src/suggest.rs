@@ -0,0 +1,124 @@
+use std::io::{self, BufRead, IsTerminal, Write};
+
+use colored::Colorize;
+
+use crate::{
+    error::{Error, Result},
+    output,
+};
+
+const MAX_SUGGESTIONS: usize = 3;
+
+// Names whose label is within a third of its length (at least one edit) of
+// `query`, closest first. Candidates are (label, name) pairs so display names
+// and aliases can point back at the name they belong to.
+pub fn similar<I, L, N>(query: &str, candidates: I) -> Vec<String>
+where
+    I: IntoIterator<Item = (L, N)>,
+    L: AsRef<str>,
+    N: Into<String>,
+{
+    let query = query.to_lowercase();
+    let mut scored: Vec<(usize, String)> = Vec::new();
+    for (label, name) in candidates {
+        let label = label.as_ref().to_lowercase();
+        let distance = strsim::damerau_levenshtein(&query, &label);
+        let limit =
+            (query.chars().count().max(label.chars().count()) / 3).max(1);
+        if distance <= limit {
+            scored.push((distance, name.into()));
+        }
+    }
+    scored.sort();
+
+    let mut names: Vec<String> = Vec::new();
+    for (_, name) in scored {
+        if !names.contains(&name) {
+            names.push(name);
+        }
+    }
+    names.truncate(MAX_SUGGESTIONS);
+    names
+}
+
+// The tail of a "not found" message: ". Did you mean 'a' or 'b'?".
+pub fn hint(suggestions: &[String]) -> String {
+    let quoted: Vec<String> =
+        suggestions.iter().map(|s| format!("'{}'", s)).collect();
+    match quoted.as_slice() {
+        [] => String::new(),
+        [only] => format!(". Did you mean {}?", only),
+        [rest @ .., last] => {
+            format!(". Did you mean {} or {}?", rest.join(", "), last)
+        }
+    }
+}
+
+pub fn can_prompt() -> bool {
+    io::stdin().is_terminal() && !output::is_structured()
+}
+
+pub fn confirm(question: &str) -> Result<bool> {
+    eprint!("{} [y/N] ", question);
+    io::stderr().flush().ok();
+    let mut answer = String::new();
+    io::stdin()
+        .lock()
+        .read_line(&mut answer)
+        .map_err(Error::io("Failed to read answer"))?;
+    Ok(matches!(answer.trim(), "y" | "Y" | "yes"))
+}
+
+// Lets the user choose one of `options` by number; an empty answer or
+// anything else picks nothing.
+pub fn pick(question: &str, options: &[String]) -> Result<Option<String>> {
+    eprintln!("{}", question);
+    for (i, option) in options.iter().enumerate() {
+        eprintln!("  {}) {}", i + 1, option.cyan());
+    }
+    eprint!("Pick a number (Enter to cancel): ");
+    io::stderr().flush().ok();
+    let mut answer = String::new();
+    io::stdin()
+        .lock()
+        .read_line(&mut answer)
+        .map_err(Error::io("Failed to read answer"))?;
+    Ok(answer
+        .trim()
+        .parse::<usize>()
+        .ok()
+        .and_then(|n| options.get(n.checked_sub(1)?))
+        .cloned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_similar_ranks_and_limits() {
+        let candidates = [
+            ("work", "work"),
+            ("personal", "personal"),
+            ("worker", "worker"),
+        ];
+        assert_eq!(similar("wrok", candidates), vec!["work"]);
+        assert_eq!(similar("works", candidates), vec!["work", "worker"]);
+        assert!(similar("zzz", candidates).is_empty());
+
+        let aliased =
+            [("neovim", "neovim"), ("nvim", "neovim"), ("vim", "vim")];
+        assert_eq!(similar("nvim", aliased), vec!["neovim", "vim"]);
+        assert_eq!(similar("NeoVim", aliased), vec!["neovim"]);
+    }
+
+    #[test]
+    fn test_hint() {
+        assert_eq!(hint(&[]), "");
+        assert_eq!(hint(&["git".to_string()]), ". Did you mean 'git'?");
+        assert_eq!(
+            hint(&["a".to_string(), "b".to_string(), "c".to_string()]),
+            ". Did you mean 'a', 'b' or 'c'?"
+        );
+    }
+}
@@ -1,23 +1,35 @@
 use std::io::Read;
 
 use colored::Colorize;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::{Error, Result},
+    output::{self, say},
+};
 
 const GITHUB_REPO: &str = "launay12u/blazinit";
 
+#[derive(Serialize)]
+struct UpdateCheck<'a> {
+    current: &'a str,
+    latest: &'a str,
+    update_available: bool,
+}
+
 #[derive(Deserialize)]
-struct GithubRelease {
-    tag_name: String,
-    assets: Vec<GithubAsset>,
+pub(crate) struct GithubRelease {
+    pub(crate) tag_name: String,
+    pub(crate) assets: Vec<GithubAsset>,
 }
 
 #[derive(Deserialize)]
-struct GithubAsset {
-    name: String,
-    browser_download_url: String,
+pub(crate) struct GithubAsset {
+    pub(crate) name: String,
+    pub(crate) browser_download_url: String,
 }
 
-fn current_target() -> Option<&'static str> {
+pub(crate) fn current_target() -> Option<&'static str> {
     match (std::env::consts::OS, std::env::consts::ARCH) {
         ("linux", "x86_64") => Some("x86_64-unknown-linux-gnu"),
         ("linux", "aarch64") => Some("aarch64-unknown-linux-gnu"),
@@ -41,7 +53,7 @@ fn parse_version(v: &str) -> (u32, u32, u32) {
     )
 }
 
-pub fn self_update(check_only: bool) -> Result<(), String> {
+pub fn self_update(check_only: bool) -> Result<()> {
     let current = env!("CARGO_PKG_VERSION");
     let api_url = format!(
         "https://api.github.com/repos/{}/releases/latest",
@@ -53,30 +65,45 @@ pub fn self_update(check_only: bool) -> Result<(), String> {
     let release: GithubRelease = ureq::get(&api_url)
         .set("User-Agent", &format!("blazinit/{}", current))
         .call()
-        .map_err(|e| format!("Failed to reach GitHub: {}", e))?
+        .map_err(Error::network(&api_url))?
         .into_json()
-        .map_err(|e| format!("Failed to parse release info: {}", e))?;
+        .map_err(|e| Error::InvalidResponse {
+            url: api_url.clone(),
+            message: format!("invalid release info: {}", e),
+        })?;
 
     let latest = release.tag_name.trim_start_matches('v');
+    let update_available = parse_version(latest) > parse_version(current);
+
+    if check_only || !update_available {
+        output::emit(&UpdateCheck {
+            current,
+            latest,
+            update_available,
+        })?;
+    }
 
-    if parse_version(latest) <= parse_version(current) {
-        println!("{} ({})", "Already up to date.".green(), current.cyan());
+    if !update_available {
+        say!("{} ({})", "Already up to date.".green(), current.cyan());
         return Ok(());
     }
 
-    println!(
+    say!(
         "New version available: {} → {}",
         current.dimmed(),
         latest.green().bold()
     );
 
     if check_only {
-        println!("Run {} to install it.", "blazinit self-update".cyan());
+        say!("Run {} to install it.", "blazinit self-update".cyan());
         return Ok(());
     }
 
-    let target = current_target()
-        .ok_or("Self-update is not supported on this platform.")?;
+    let target = current_target().ok_or_else(|| {
+        Error::Unsupported(
+            "Self-update is not supported on this platform.".to_string(),
+        )
+    })?;
 
     let asset_name = if cfg!(windows) {
         format!("blazinit-{}.exe", target)
@@ -92,51 +119,50 @@ pub fn self_update(check_only: bool) -> Result<(), String> {
         .find(|a| a.name == asset_name)
         .map(|a| a.browser_download_url.as_str())
         .ok_or_else(|| {
-            format!(
+            Error::Unsupported(format!(
                 "No binary found for your platform ({target}). \
                  See https://github.com/{GITHUB_REPO}/releases"
-            )
+            ))
         })?;
 
     log::info!("downloading {}", download_url);
-    println!("Downloading {}...", asset_name.cyan());
+    say!("Downloading {}...", asset_name.cyan());
 
     let mut bytes: Vec<u8> = Vec::new();
     ureq::get(download_url)
         .call()
-        .map_err(|e| format!("Download failed: {}", e))?
+        .map_err(Error::network(download_url))?
         .into_reader()
         .read_to_end(&mut bytes)
-        .map_err(|e| format!("Failed to read download: {}", e))?;
+        .map_err(Error::network(download_url))?;
 
     let current_exe = std::env::current_exe()
-        .map_err(|e| format!("Cannot locate current executable: {}", e))?;
+        .map_err(Error::io("Cannot locate current executable"))?;
     let tmp = current_exe.with_extension("update.tmp");
 
     std::fs::write(&tmp, &bytes)
-        .map_err(|e| format!("Failed to write update to disk: {}", e))?;
+        .map_err(Error::io("Failed to write update to disk"))?;
 
     #[cfg(unix)]
     {
         use std::os::unix::fs::PermissionsExt;
         let mut perms = std::fs::metadata(&tmp)
-            .map_err(|e| format!("Failed to read temp file metadata: {}", e))?
+            .map_err(Error::io("Failed to read temp file metadata"))?
             .permissions();
         perms.set_mode(0o755);
         std::fs::set_permissions(&tmp, perms)
-            .map_err(|e| format!("Failed to set permissions: {}", e))?;
+            .map_err(Error::io("Failed to set permissions"))?;
     }
 
     std::fs::rename(&tmp, &current_exe).map_err(|e| {
         let _ = std::fs::remove_file(&tmp);
-        format!(
-            "Failed to replace binary (try running with elevated permissions): {}",
-            e
-        )
+        Error::io(
+            "Failed to replace binary (try running with elevated permissions)",
+        )(e)
     })?;
 
     log::info!("updated binary: v{} → v{}", current, latest);
-    println!("{} v{}!", "Updated to".green(), latest.green().bold());
+    say!("{} v{}!", "Updated to".green(), latest.green().bold());
 
     Ok(())
 }
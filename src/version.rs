@@ -0,0 +1,281 @@
+use std::{cmp::Ordering, fmt};
+
+use regex::Regex;
+
+use crate::error::{Error, Result};
+
+const DEFAULT_VERSION_PATTERN: &str = r"\d+(?:\.\d+)+";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Op {
+    Prefix,
+    Exact,
+    Greater,
+    GreaterEq,
+    Less,
+    LessEq,
+    Caret,
+    Tilde,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Comparator {
+    op: Op,
+    parts: Vec<u64>,
+    wildcard: bool,
+}
+
+// A loose version requirement such as `1.5.x`, `=1.5.7`, `>=1.2, <2` or
+// `^0.9`. Bare versions match by prefix so `1.5` accepts any 1.5.* release.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VersionReq {
+    raw: String,
+    comparators: Vec<Comparator>,
+}
+
+pub fn parse_version(v: &str) -> Option<Vec<u64>> {
+    let v = v.trim().trim_start_matches('v');
+    let numeric: String = v
+        .chars()
+        .take_while(|c| c.is_ascii_digit() || *c == '.')
+        .collect();
+    let parts: Vec<u64> = numeric
+        .split('.')
+        .filter(|p| !p.is_empty())
+        .map(|p| p.parse().ok())
+        .collect::<Option<_>>()?;
+    (!parts.is_empty()).then_some(parts)
+}
+
+fn compare(a: &[u64], b: &[u64]) -> Ordering {
+    let len = a.len().max(b.len());
+    for i in 0..len {
+        let ord = a
+            .get(i)
+            .copied()
+            .unwrap_or(0)
+            .cmp(&b.get(i).copied().unwrap_or(0));
+        if ord != Ordering::Equal {
+            return ord;
+        }
+    }
+    Ordering::Equal
+}
+
+fn bump(parts: &[u64], index: usize) -> Vec<u64> {
+    let mut upper: Vec<u64> = parts.iter().take(index + 1).copied().collect();
+    upper.resize(index + 1, 0);
+    upper[index] += 1;
+    upper
+}
+
+impl Comparator {
+    fn parse(s: &str) -> Result<Self> {
+        let s = s.trim();
+        let (op, rest) = [
+            (">=", Op::GreaterEq),
+            ("<=", Op::LessEq),
+            (">", Op::Greater),
+            ("<", Op::Less),
+            ("=", Op::Exact),
+            ("^", Op::Caret),
+            ("~", Op::Tilde),
+        ]
+        .iter()
+        .find_map(|(prefix, op)| s.strip_prefix(prefix).map(|r| (*op, r)))
+        .unwrap_or((Op::Prefix, s));
+
+        let rest = rest.trim().trim_start_matches('v');
+        let mut parts = Vec::new();
+        let mut wildcard = false;
+        for piece in rest.split('.') {
+            if matches!(piece, "x" | "X" | "*") {
+                wildcard = true;
+                break;
+            }
+            parts.push(
+                piece
+                    .parse()
+                    .map_err(|_| Error::InvalidVersion(s.to_string()))?,
+            );
+        }
+        if parts.is_empty() && !wildcard {
+            return Err(Error::InvalidVersion(s.to_string()));
+        }
+        let op = if wildcard && op == Op::Exact {
+            Op::Prefix
+        } else {
+            op
+        };
+        Ok(Comparator {
+            op,
+            parts,
+            wildcard,
+        })
+    }
+
+    fn matches(&self, v: &[u64]) -> bool {
+        let ord = compare(v, &self.parts);
+        match self.op {
+            // Missing parts count as zero, as in `compare`, so `1.5.0`
+            // accepts an installed `1.5`.
+            Op::Prefix => self
+                .parts
+                .iter()
+                .enumerate()
+                .all(|(i, p)| v.get(i).copied().unwrap_or(0) == *p),
+            Op::Exact => ord == Ordering::Equal,
+            Op::Greater => ord == Ordering::Greater,
+            Op::GreaterEq => ord != Ordering::Less,
+            Op::Less => ord == Ordering::Less,
+            Op::LessEq => ord != Ordering::Greater,
+            Op::Caret => {
+                let index = self
+                    .parts
+                    .iter()
+                    .position(|p| *p != 0)
+                    .unwrap_or(self.parts.len().saturating_sub(1));
+                ord != Ordering::Less
+                    && compare(v, &bump(&self.parts, index)) == Ordering::Less
+            }
+            Op::Tilde => {
+                let index = if self.parts.len() > 1 { 1 } else { 0 };
+                ord != Ordering::Less
+                    && compare(v, &bump(&self.parts, index)) == Ordering::Less
+            }
+        }
+    }
+}
+
+impl VersionReq {
+    pub fn parse(s: &str) -> Result<Self> {
+        let comparators = s
+            .split(',')
+            .map(Comparator::parse)
+            .collect::<Result<Vec<_>>>()?;
+        Ok(VersionReq {
+            raw: s.trim().to_string(),
+            comparators,
+        })
+    }
+
+    pub fn matches(&self, version: &str) -> bool {
+        match parse_version(version) {
+            Some(v) => self.comparators.iter().all(|c| c.matches(&v)),
+            None => false,
+        }
+    }
+
+    // The concrete version to hand to a package manager, if the requirement
+    // names exactly one version (`1.5.7` or `=1.5`). A shorter prefix such as
+    // `1.5` matches many releases and is left to detection instead.
+    pub fn install_version(&self) -> Option<String> {
+        match self.comparators.as_slice() {
+            [c] if !c.wildcard
+                && (c.op == Op::Exact
+                    || (c.op == Op::Prefix && c.parts.len() >= 3)) =>
+            {
+                Some(
+                    c.parts
+                        .iter()
+                        .map(|p| p.to_string())
+                        .collect::<Vec<_>>()
+                        .join("."),
+                )
+            }
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for VersionReq {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.raw)
+    }
+}
+
+pub fn extract_version(
+    output: &str,
+    pattern: Option<&str>,
+) -> Result<Option<String>> {
+    let pattern = pattern.unwrap_or(DEFAULT_VERSION_PATTERN);
+    let re = Regex::new(pattern).map_err(|source| Error::InvalidRegex {
+        pattern: pattern.to_string(),
+        source,
+    })?;
+    Ok(re.captures(output).map(|caps| {
+        caps.get(1)
+            .or_else(|| caps.get(0))
+            .map(|m| m.as_str().to_string())
+            .unwrap_or_default()
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn req(s: &str) -> VersionReq {
+        VersionReq::parse(s).unwrap()
+    }
+
+    #[test]
+    fn test_parse_version_loose() {
+        assert_eq!(parse_version("v1.5.7"), Some(vec![1, 5, 7]));
+        assert_eq!(parse_version("2.39.2-rc1"), Some(vec![2, 39, 2]));
+        assert_eq!(parse_version("abc"), None);
+    }
+
+    #[test]
+    fn test_wildcard_and_prefix_requirements() {
+        assert!(req("1.5.x").matches("1.5.7"));
+        assert!(req("1.5").matches("1.5.0"));
+        assert!(!req("1.5.x").matches("1.6.0"));
+        assert!(!req("1.5").matches("1.50.0"));
+        assert!(req("*").matches("3.0.0"));
+        assert!(req("1.5.0").matches("1.5"));
+        assert!(!req("1.5.0").matches("1.5.1"));
+    }
+
+    #[test]
+    fn test_exact_and_range_requirements() {
+        assert!(req("=1.5.7").matches("1.5.7"));
+        assert!(!req("=1.5.7").matches("1.5.8"));
+        assert!(req(">=1.2, <2").matches("1.9.9"));
+        assert!(!req(">=1.2, <2").matches("2.0.0"));
+        assert!(req("^1.2").matches("1.9.0"));
+        assert!(!req("^0.9").matches("0.10.0"));
+        assert!(req("~1.2.3").matches("1.2.9"));
+        assert!(!req("~1.2.3").matches("1.3.0"));
+    }
+
+    #[test]
+    fn test_install_version_only_for_single_version() {
+        assert_eq!(req("1.5.7").install_version(), Some("1.5.7".into()));
+        assert_eq!(req("=1.5.7").install_version(), Some("1.5.7".into()));
+        assert_eq!(req("=1.5").install_version(), Some("1.5".into()));
+        assert_eq!(req("1.5").install_version(), None);
+        assert_eq!(req("1.5.x").install_version(), None);
+        assert_eq!(req(">=1.5").install_version(), None);
+    }
+
+    #[test]
+    fn test_invalid_requirement() {
+        assert!(VersionReq::parse("latest").is_err());
+        assert!(VersionReq::parse(">=").is_err());
+    }
+
+    #[test]
+    fn test_extract_version() {
+        assert_eq!(
+            extract_version("git version 2.39.2", None).unwrap(),
+            Some("2.39.2".to_string())
+        );
+        assert_eq!(
+            extract_version("Terraform v1.5.7\non linux", Some(r"v(\S+)"))
+                .unwrap(),
+            Some("1.5.7".to_string())
+        );
+        assert_eq!(extract_version("no digits", None).unwrap(), None);
+    }
+}
@@ -242,3 +242,1026 @@ fn test_add_to_non_existent_profile_fails() {
             "Profile 'non-existent' does not exist",
         ));
 }
+
+#[test]
+fn test_lock_and_install_locked() {
+    let temp_dir = setup_test_env();
+
+    blazinit_cmd(&temp_dir)
+        .arg("add")
+        .arg("git")
+        .assert()
+        .success();
+
+    blazinit_cmd(&temp_dir)
+        .arg("install")
+        .arg("--locked")
+        .arg("--dry-run")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("has no lock file"));
+
+    blazinit_cmd(&temp_dir).arg("lock").assert().success();
+    assert!(
+        temp_dir
+            .path()
+            .join(".config/blazinit/profiles/default.lock.toml")
+            .exists()
+    );
+
+    blazinit_cmd(&temp_dir)
+        .arg("list")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("default.lock").not());
+
+    blazinit_cmd(&temp_dir)
+        .arg("install")
+        .arg("--locked")
+        .arg("--dry-run")
+        .assert()
+        .success();
+}
+
+#[test]
+fn test_status_exit_code() {
+    let temp_dir = setup_test_env();
+
+    blazinit_cmd(&temp_dir)
+        .arg("status")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No packages in this profile"));
+
+    let registry = temp_dir.path().join(".config/blazinit/registry");
+    std::fs::write(
+        registry.join("never-installed.toml"),
+        "detect = \"false\"\n\n[packages]\ncustom = \"true\"\n",
+    )
+    .unwrap();
+
+    blazinit_cmd(&temp_dir)
+        .arg("add")
+        .arg("never-installed")
+        .assert()
+        .success();
+
+    blazinit_cmd(&temp_dir)
+        .arg("status")
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("[missing]"))
+        .stderr(predicate::str::contains("not installed as required"));
+}
+
+#[test]
+fn test_doctor_json_report() {
+    let temp_dir = setup_test_env();
+    let config_dir = temp_dir.path().join(".config/blazinit");
+    std::fs::create_dir_all(&config_dir).unwrap();
+    std::fs::write(
+        config_dir.join("config.toml"),
+        "default_profile = \"default\"\nregistry_url = \"http://127.0.0.1:1\"\n",
+    )
+    .unwrap();
+
+    let output = blazinit_cmd(&temp_dir)
+        .arg("doctor")
+        .arg("--json")
+        .output()
+        .unwrap();
+    // The unreachable registry is a problem, but the report still comes out.
+    assert_eq!(output.status.code(), Some(8));
+
+    let report: serde_json::Value =
+        serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report["system"]["os"], std::env::consts::OS);
+    assert_eq!(report["registry"]["reachable"], false);
+    assert!(report["installers"].as_array().unwrap().len() > 1);
+    assert!(report["registry"]["errors"].as_array().unwrap().is_empty());
+}
+
+#[test]
+fn test_structured_output() {
+    let temp_dir = setup_test_env();
+    let registry = temp_dir.path().join(".config/blazinit/registry");
+    std::fs::create_dir_all(&registry).unwrap();
+    std::fs::write(
+        registry.join("never-installed.toml"),
+        "display = \"Never\"\ndetect = \"false\"\n\n[packages]\ncustom = \"true\"\n",
+    )
+    .unwrap();
+
+    blazinit_cmd(&temp_dir)
+        .args(["add", "never-installed"])
+        .assert()
+        .success();
+
+    let output = blazinit_cmd(&temp_dir)
+        .args(["list", "--json"])
+        .output()
+        .unwrap();
+    let list: serde_json::Value =
+        serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(list[0]["name"], "default");
+    assert_eq!(list[0]["default"], true);
+
+    let output = blazinit_cmd(&temp_dir)
+        .args(["show", "--format", "yaml"])
+        .output()
+        .unwrap();
+    let show: serde_yaml::Value =
+        serde_yaml::from_slice(&output.stdout).unwrap();
+    assert_eq!(show["name"], "default");
+    assert_eq!(show["packages"][0]["display"], "Never");
+
+    let output = blazinit_cmd(&temp_dir)
+        .args(["install", "--dry-run", "--json"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let report: serde_json::Value =
+        serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report["dry_run"], true);
+    assert_eq!(report["packages"][0]["outcome"], "would_install");
+    assert_eq!(report["packages"][0]["installer"], "custom");
+    assert_eq!(report["installed"], 1);
+    assert!(String::from_utf8_lossy(&output.stderr).contains("[dry-run]"));
+
+    let output = blazinit_cmd(&temp_dir)
+        .args(["status", "--json"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    let status: serde_json::Value =
+        serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(status["packages"][0]["state"], "missing");
+    assert_eq!(status["summary"]["missing"], 1);
+
+    let output = blazinit_cmd(&temp_dir)
+        .args(["registry", "list", "never", "--json"])
+        .output()
+        .unwrap();
+    let packages: serde_json::Value =
+        serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(packages[0]["name"], "never-installed");
+    assert_eq!(packages[0]["installers"][0], "custom");
+}
+
+#[test]
+fn test_exit_codes_by_category() {
+    let temp_dir = setup_test_env();
+
+    blazinit_cmd(&temp_dir)
+        .args(["show", "ghost"])
+        .assert()
+        .code(3)
+        .stderr(predicate::str::contains("Profile 'ghost' does not exist"));
+
+    blazinit_cmd(&temp_dir)
+        .args(["create", "work"])
+        .assert()
+        .success();
+    blazinit_cmd(&temp_dir)
+        .args(["create", "work"])
+        .assert()
+        .code(4)
+        .stderr(predicate::str::contains("Profile 'work' already exists"));
+
+    blazinit_cmd(&temp_dir)
+        .args(["add", "git", "work", "--version", "latest"])
+        .assert()
+        .code(5)
+        .stderr(predicate::str::contains("Invalid version requirement"));
+
+    let registry = temp_dir.path().join(".config/blazinit/registry");
+    std::fs::write(
+        registry.join("never-installed.toml"),
+        "detect = \"false\"\n\n[packages]\ncustom = \"false\"\n",
+    )
+    .unwrap();
+    blazinit_cmd(&temp_dir)
+        .args(["add", "never-installed", "work"])
+        .assert()
+        .success();
+
+    blazinit_cmd(&temp_dir)
+        .args(["status", "work"])
+        .assert()
+        .code(8);
+
+    blazinit_cmd(&temp_dir)
+        .args(["install", "work"])
+        .assert()
+        .code(7)
+        .stderr(predicate::str::contains("1 of 1 packages"));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_install_on_hosts_through_ssh_shim() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let temp_dir = setup_test_env();
+    // Stands in for `ssh -o BatchMode=yes HOST -- COMMAND` by running the
+    // command locally. The host "down" behaves like an unreachable machine.
+    let shim = temp_dir.path().join("fake-ssh");
+    std::fs::write(
+        &shim,
+        "#!/bin/sh\nhost=$3\nshift 4\n\
+         echo \"$host\" >> \"$(dirname \"$0\")/hosts.log\"\n\
+         if [ \"$host\" = down ]; then echo 'Connection refused' >&2; exit 255; fi\n\
+         exec sh -c \"$*\"\n",
+    )
+    .unwrap();
+    std::fs::set_permissions(&shim, std::fs::Permissions::from_mode(0o755))
+        .unwrap();
+
+    blazinit_cmd(&temp_dir).arg("list").assert().success();
+    let registry = temp_dir.path().join(".config/blazinit/registry");
+    std::fs::write(
+        registry.join("remote-tool.toml"),
+        "detect = \"false\"\n\n[packages]\ncustom = \"true\"\n",
+    )
+    .unwrap();
+    blazinit_cmd(&temp_dir)
+        .args(["add", "remote-tool"])
+        .assert()
+        .success();
+
+    let output = blazinit_cmd(&temp_dir)
+        .env("BLAZINIT_SSH", &shim)
+        .args(["install", "--host", "web1", "--host", "web2", "--json"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let hosts: serde_json::Value =
+        serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(hosts.as_array().unwrap().len(), 2);
+    for (host, name) in hosts.as_array().unwrap().iter().zip(["web1", "web2"]) {
+        assert_eq!(host["host"], name);
+        assert_eq!(host["report"]["installed"], 1);
+        assert_eq!(host["report"]["packages"][0]["installer"], "custom");
+    }
+    let log =
+        std::fs::read_to_string(temp_dir.path().join("hosts.log")).unwrap();
+    assert!(log.contains("web1") && log.contains("web2"));
+
+    blazinit_cmd(&temp_dir)
+        .env("BLAZINIT_SSH", &shim)
+        .args(["install", "--host", "web1", "--host", "down"])
+        .assert()
+        .code(7)
+        .stdout(predicate::str::contains("Summary:"))
+        .stdout(predicate::str::contains("Connection refused"))
+        .stderr(predicate::str::contains("1 of 2 hosts"));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_profile_in_container_through_runtime_shim() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let temp_dir = setup_test_env();
+    // Stands in for `docker run --rm -v STAGE:/blazinit IMAGE sh -c ...` by
+    // running the script locally against the staging directory.
+    let shim = temp_dir.path().join("fake-docker");
+    std::fs::write(
+        &shim,
+        "#!/bin/sh\nroot=${4%:/blazinit}\n\
+         echo \"$5\" > \"$(dirname \"$0\")/image.log\"\n\
+         shift 5\nBLAZINIT_ROOT=$root exec \"$@\"\n",
+    )
+    .unwrap();
+    std::fs::set_permissions(&shim, std::fs::Permissions::from_mode(0o755))
+        .unwrap();
+
+    blazinit_cmd(&temp_dir).arg("list").assert().success();
+    let registry = temp_dir.path().join(".config/blazinit/registry");
+    // Installs fine but is never detected afterwards.
+    std::fs::write(
+        registry.join("undetected.toml"),
+        "detect = \"false\"\n\n[packages]\ncustom = \"true\"\n",
+    )
+    .unwrap();
+    std::fs::write(
+        registry.join("unbuildable.toml"),
+        "detect = \"false\"\n\n[packages]\ncustom = \"false\"\n",
+    )
+    .unwrap();
+    std::fs::write(
+        registry.join("fine.toml"),
+        "detect = \"true\"\n\n[packages]\ncustom = \"true\"\n",
+    )
+    .unwrap();
+    blazinit_cmd(&temp_dir)
+        .args(["create", "ci"])
+        .assert()
+        .success();
+    blazinit_cmd(&temp_dir)
+        .args(["add", "fine", "ci"])
+        .assert()
+        .success();
+
+    blazinit_cmd(&temp_dir)
+        .env("BLAZINIT_CONTAINER_RUNTIME", &shim)
+        .args(["test", "ci", "--image", "debian:12"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("all 1 packages installed"));
+    let image =
+        std::fs::read_to_string(temp_dir.path().join("image.log")).unwrap();
+    assert_eq!(image.trim(), "debian:12");
+
+    for package in ["undetected", "unbuildable"] {
+        blazinit_cmd(&temp_dir)
+            .args(["add", package, "ci"])
+            .assert()
+            .success();
+    }
+    let output = blazinit_cmd(&temp_dir)
+        .env("BLAZINIT_CONTAINER_RUNTIME", &shim)
+        .args(["test", "ci", "--image", "debian:12", "--json"])
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(7));
+    let report: serde_json::Value =
+        serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report["failed_install"], serde_json::json!(["unbuildable"]));
+    assert_eq!(report["failed_detect"], serde_json::json!(["undetected"]));
+    assert_eq!(report["install"]["skipped"], 1);
+}
+
+#[test]
+fn test_export_script_formats() {
+    let temp_dir = setup_test_env();
+    blazinit_cmd(&temp_dir)
+        .args(["add", "git"])
+        .assert()
+        .success();
+
+    blazinit_cmd(&temp_dir)
+        .args(["export", "--to", "sh", "--installer", "brew"])
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with("#!/bin/sh\n"))
+        .stdout(predicate::str::contains("brew install git\n"));
+
+    let dockerfile = temp_dir.path().join("Dockerfile");
+    blazinit_cmd(&temp_dir)
+        .args(["export", "default"])
+        .arg(&dockerfile)
+        .args(["--to", "dockerfile", "--image", "ubuntu:24.04"])
+        .assert()
+        .success();
+    let content = std::fs::read_to_string(&dockerfile).unwrap();
+    assert!(content.contains("FROM ubuntu:24.04\n"));
+    assert!(content.contains("apt-get install -y git"));
+
+    blazinit_cmd(&temp_dir)
+        .args(["list", "--format", "dockerfile"])
+        .assert()
+        .code(2);
+    blazinit_cmd(&temp_dir)
+        .args(["export", "--to", "yaml"])
+        .assert()
+        .code(2);
+    blazinit_cmd(&temp_dir)
+        .args(["export", "--to", "toml", "--installer", "brew"])
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains("do not apply to TOML exports"));
+    blazinit_cmd(&temp_dir)
+        .args(["export", "--to", "sh", "--image", "ubuntu:24.04"])
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains("--image only applies"));
+}
+
+#[test]
+fn test_export_native_package_lists() {
+    let temp_dir = setup_test_env();
+    blazinit_cmd(&temp_dir)
+        .args(["add", "git"])
+        .assert()
+        .success();
+    let registry = temp_dir.path().join(".config/blazinit/registry");
+    std::fs::write(
+        registry.join("apt-only.toml"),
+        "[packages]\napt = \"apt-only\"\n",
+    )
+    .unwrap();
+    blazinit_cmd(&temp_dir)
+        .args(["add", "apt-only"])
+        .assert()
+        .success();
+
+    blazinit_cmd(&temp_dir)
+        .args(["export", "--to", "brewfile"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("brew \"git\"\n"))
+        .stdout(predicate::str::contains("apt-only").not())
+        .stderr(predicate::str::contains(
+            "'apt-only' has no brew package and was left out",
+        ));
+
+    blazinit_cmd(&temp_dir)
+        .args(["export", "--to", "apt-list"])
+        .assert()
+        .success()
+        .stdout("apt-only\ngit\n");
+
+    blazinit_cmd(&temp_dir)
+        .args(["export", "--to", "brewfile", "--installer", "apt"])
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains("only lists brew packages"));
+    blazinit_cmd(&temp_dir)
+        .args(["export", "--to", "apt-list", "--installer", "apt"])
+        .assert()
+        .success();
+}
+
+#[test]
+fn test_import_from_package_list() {
+    let temp_dir = setup_test_env();
+    let list = temp_dir.path().join("manual.txt");
+    std::fs::write(&list, "git\ncurl\nhtop\n").unwrap();
+
+    blazinit_cmd(&temp_dir)
+        .args(["import", "--from", "apt", "--name", "server"])
+        .arg(&list)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("with 2 packages from apt"))
+        .stdout(predicate::str::contains("# htop.toml"))
+        .stdout(predicate::str::contains("apt = \"htop\""));
+
+    blazinit_cmd(&temp_dir)
+        .args(["show", "server"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("- Git"))
+        .stdout(predicate::str::contains("- cURL"));
+
+    blazinit_cmd(&temp_dir)
+        .args(["import", "--name", "server"])
+        .assert()
+        .code(2);
+}
+
+#[test]
+fn test_registry_new_from_flags() {
+    let temp_dir = setup_test_env();
+
+    blazinit_cmd(&temp_dir)
+        .args(["registry", "new", "mytool", "--yes", "--no-probe"])
+        .assert()
+        .code(5)
+        .stderr(predicate::str::contains("no installer found"));
+
+    blazinit_cmd(&temp_dir)
+        .args(["registry", "new", "mytool", "--yes", "--no-probe"])
+        .args(["--installer", "apt=my-tool", "--depends-on", "git"])
+        .args(["--detect", "mytool --version"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("apt = \"my-tool\""));
+
+    blazinit_cmd(&temp_dir)
+        .args(["registry", "list", "mytool"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("mytool"));
+
+    blazinit_cmd(&temp_dir)
+        .args(["registry", "new", "mytool", "--yes", "--no-probe"])
+        .args(["--custom", "true"])
+        .assert()
+        .code(4);
+}
+
+#[test]
+fn test_export_bundle_carries_custom_packages() {
+    let source = setup_test_env();
+    blazinit_cmd(&source)
+        .args(["registry", "new", "mylib", "--yes", "--no-probe"])
+        .args(["--custom", "true"])
+        .assert()
+        .success();
+    blazinit_cmd(&source)
+        .args(["registry", "new", "mytool", "--yes", "--no-probe"])
+        .args(["--installer", "apt=my-tool", "--depends-on", "mylib"])
+        .args(["--display", "My Tool"])
+        .assert()
+        .success();
+    blazinit_cmd(&source)
+        .args(["create", "work"])
+        .assert()
+        .success();
+    for package in ["mytool", "git"] {
+        blazinit_cmd(&source)
+            .args(["add", package, "work"])
+            .assert()
+            .success();
+    }
+    let bundle = source.path().join("work.toml");
+    blazinit_cmd(&source)
+        .args(["export", "work", "--bundle"])
+        .arg(&bundle)
+        .assert()
+        .success();
+    let content = std::fs::read_to_string(&bundle).unwrap();
+    assert!(content.contains("[registry.mytool]"));
+    assert!(content.contains("[registry.mylib"));
+    assert!(!content.contains("[registry.git"));
+
+    let dest = setup_test_env();
+    blazinit_cmd(&dest).args(["show", "work"]).assert().code(3);
+    blazinit_cmd(&dest)
+        .arg("import")
+        .arg(&bundle)
+        .assert()
+        .code(10)
+        .stderr(predicate::str::contains("mylib: true"))
+        .stderr(predicate::str::contains("re-run with --trust"));
+    blazinit_cmd(&dest).args(["show", "work"]).assert().code(3);
+    blazinit_cmd(&dest)
+        .args(["import", "--trust"])
+        .arg(&bundle)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Added 2 bundled packages"));
+    blazinit_cmd(&dest)
+        .args(["show", "work"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("- My Tool"));
+
+    blazinit_cmd(&source)
+        .args(["export", "work", "--to", "sh", "--bundle"])
+        .assert()
+        .code(1);
+}
+
+#[test]
+fn test_import_from_git_repository() {
+    let temp_dir = setup_test_env();
+    let repo = temp_dir.path().join("team");
+    std::fs::create_dir_all(repo.join("profiles")).unwrap();
+    std::fs::write(
+        repo.join("profiles/work.toml"),
+        "name = \"work\"\n\n[[packages]]\nname = \"git\"\n",
+    )
+    .unwrap();
+    std::fs::write(temp_dir.path().join("secret.toml"), "name = \"secret\"\n")
+        .unwrap();
+    #[cfg(unix)]
+    std::os::unix::fs::symlink(
+        temp_dir.path().join("secret.toml"),
+        repo.join("escape.toml"),
+    )
+    .unwrap();
+    for args in [
+        vec!["init", "--quiet"],
+        vec!["add", "."],
+        vec![
+            "-c",
+            "user.name=test",
+            "-c",
+            "user.email=test@example.com",
+            "commit",
+            "--quiet",
+            "-m",
+            "profiles",
+        ],
+    ] {
+        let status = std::process::Command::new("git")
+            .args(&args)
+            .current_dir(&repo)
+            .status()
+            .unwrap();
+        assert!(status.success());
+    }
+    let source =
+        format!("git+file://{}#path=profiles/work.toml", repo.display());
+    let mut escapes = vec![".."];
+    if cfg!(unix) {
+        escapes.push("escape.toml");
+    }
+    for path in escapes {
+        let outside = format!("git+file://{}#path={}", repo.display(), path);
+        blazinit_cmd(&temp_dir)
+            .args(["import", &outside])
+            .assert()
+            .code(5)
+            .stderr(predicate::str::contains("outside the repository"));
+    }
+
+    blazinit_cmd(&temp_dir)
+        .args(["import", &source, "--as", "team-work"])
+        .assert()
+        .success();
+    blazinit_cmd(&temp_dir)
+        .args(["show", "team-work"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("- Git"));
+
+    blazinit_cmd(&temp_dir)
+        .args(["import", &source, "--as", "team-work"])
+        .assert()
+        .code(4);
+    blazinit_cmd(&temp_dir)
+        .args(["import", &source, "--as", "team-work", "--force"])
+        .args(["--sha256", &"0".repeat(64)])
+        .assert()
+        .code(6)
+        .stderr(predicate::str::contains("Checksum mismatch"));
+
+    let missing = format!("git+file://{}#path=nope.toml", repo.display());
+    blazinit_cmd(&temp_dir)
+        .args(["import", &missing])
+        .assert()
+        .code(9);
+}
+
+#[test]
+fn test_subscribe_and_sync() {
+    let temp_dir = setup_test_env();
+    let remote = temp_dir.path().join("team.toml");
+    std::fs::write(
+        &remote,
+        "name = \"team\"\n\n[[packages]]\nname = \"git\"\n",
+    )
+    .unwrap();
+    let source = remote.to_str().unwrap();
+
+    blazinit_cmd(&temp_dir)
+        .args(["subscribe", source])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Subscribed 'team'"));
+    blazinit_cmd(&temp_dir)
+        .arg("list")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("team (subscribed:"));
+    blazinit_cmd(&temp_dir)
+        .args(["show", "team"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Subscribed to"));
+    blazinit_cmd(&temp_dir)
+        .args(["remove", "git", "team"])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("will overwrite this change"));
+
+    blazinit_cmd(&temp_dir)
+        .args(["--json", "sync", "--dry-run"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"applied\": false"))
+        .stdout(predicate::str::contains("\"name\": \"git\""));
+    blazinit_cmd(&temp_dir)
+        .args(["sync", "team"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("+ git"));
+    blazinit_cmd(&temp_dir)
+        .args(["sync", "team"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Already up to date."));
+    blazinit_cmd(&temp_dir)
+        .args(["sync", "default"])
+        .assert()
+        .code(1);
+}
+
+#[test]
+fn test_diff_and_merge_profiles() {
+    let temp_dir = setup_test_env();
+    for name in ["laptop", "desktop"] {
+        blazinit_cmd(&temp_dir)
+            .args(["create", name])
+            .assert()
+            .success();
+    }
+    for args in [
+        vec!["add", "git", "laptop", "--installer", "apt"],
+        vec!["add", "curl", "laptop"],
+        vec!["add", "git", "desktop", "--installer", "brew"],
+    ] {
+        blazinit_cmd(&temp_dir).args(&args).assert().success();
+    }
+
+    blazinit_cmd(&temp_dir)
+        .args(["diff", "desktop", "laptop"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("+ curl"))
+        .stdout(predicate::str::contains(
+            "~ git (installer: brew -> installer: apt)",
+        ));
+
+    blazinit_cmd(&temp_dir)
+        .args(["merge", "laptop", "desktop"])
+        .assert()
+        .code(4)
+        .stdout(predicate::str::contains("! git"))
+        .stderr(predicate::str::contains("--strategy"));
+    blazinit_cmd(&temp_dir)
+        .args(["merge", "laptop", "desktop", "--strategy", "theirs"])
+        .assert()
+        .success();
+    blazinit_cmd(&temp_dir)
+        .args(["diff", "desktop", "laptop"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No differences."));
+
+    let foreign = temp_dir.path().join("foreign.toml");
+    std::fs::write(
+        &foreign,
+        "name = \"foreign\"\n\n[[packages]]\nname = \"not-in-registry\"\n",
+    )
+    .unwrap();
+    blazinit_cmd(&temp_dir)
+        .arg("merge")
+        .arg(&foreign)
+        .arg("desktop")
+        .assert()
+        .code(3)
+        .stderr(predicate::str::contains("not-in-registry"));
+    blazinit_cmd(&temp_dir)
+        .args(["show", "desktop"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("not-in-registry").not());
+
+    // Packages the destination already had are not the merge's concern.
+    let desktop = temp_dir
+        .path()
+        .join(".config/blazinit/profiles/desktop.toml");
+    let mut content = std::fs::read_to_string(&desktop).unwrap();
+    content.push_str("\n[[packages]]\nname = \"retired-tool\"\n");
+    std::fs::write(&desktop, content).unwrap();
+    let upstream = temp_dir.path().join("upstream.toml");
+    std::fs::write(
+        &upstream,
+        "name = \"upstream\"\n\n[[packages]]\nname = \"unzip\"\n",
+    )
+    .unwrap();
+    blazinit_cmd(&temp_dir)
+        .arg("merge")
+        .arg(&upstream)
+        .arg("desktop")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("+ unzip"));
+}
+
+#[test]
+fn test_edit_profile_validates_before_saving() {
+    let temp_dir = setup_test_env();
+    blazinit_cmd(&temp_dir)
+        .args(["create", "work"])
+        .assert()
+        .success();
+    blazinit_cmd(&temp_dir)
+        .args(["add", "git", "work"])
+        .assert()
+        .success();
+
+    blazinit_cmd(&temp_dir)
+        .args(["edit", "work"])
+        .env("VISUAL", "sed -i s/git/no-such-package/")
+        .assert()
+        .code(3)
+        .stderr(predicate::str::contains("your edits are in"));
+    blazinit_cmd(&temp_dir)
+        .args(["show", "work"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("- Git"));
+
+    blazinit_cmd(&temp_dir)
+        .args(["edit", "work"])
+        .env("VISUAL", "sed -i s/git/curl/")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Saved profile 'work'"));
+    blazinit_cmd(&temp_dir)
+        .args(["rename", "work", "job"])
+        .assert()
+        .success();
+    blazinit_cmd(&temp_dir)
+        .args(["show", "job"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("- cURL"));
+}
+
+#[test]
+fn test_bulk_add_and_remove() {
+    let temp_dir = setup_test_env();
+    blazinit_cmd(&temp_dir)
+        .args(["create", "work"])
+        .assert()
+        .success();
+    blazinit_cmd(&temp_dir)
+        .args(["add", "git,curl", "work"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Successfully added 2 packages."));
+    blazinit_cmd(&temp_dir)
+        .args(["add", "git,curl", "work"])
+        .assert()
+        .code(4);
+    blazinit_cmd(&temp_dir)
+        .args(["add", "git,curl", "work", "--ignore-existing"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("(already present)"));
+    blazinit_cmd(&temp_dir)
+        .args(["remove", "git,curl", "work"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Successfully removed 2 packages."));
+}
+
+#[test]
+fn test_not_found_suggests_close_matches() {
+    let temp_dir = setup_test_env();
+    blazinit_cmd(&temp_dir)
+        .args(["create", "work"])
+        .assert()
+        .success();
+    blazinit_cmd(&temp_dir)
+        .args(["add", "gti", "work"])
+        .assert()
+        .code(3)
+        .stderr(predicate::str::contains(
+            "Package 'gti' not found in registry. Did you mean 'git'?",
+        ));
+    blazinit_cmd(&temp_dir)
+        .args(["show", "wrok"])
+        .assert()
+        .code(3)
+        .stderr(predicate::str::contains(
+            "Profile 'wrok' does not exist. Did you mean 'work'?",
+        ));
+}
+
+#[test]
+fn test_pickers_need_a_terminal() {
+    let temp_dir = setup_test_env();
+    blazinit_cmd(&temp_dir)
+        .args(["create", "work"])
+        .assert()
+        .success();
+    blazinit_cmd(&temp_dir).arg("add").assert().code(2);
+    blazinit_cmd(&temp_dir)
+        .args(["add", "--interactive", "work"])
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains("needs an interactive terminal"));
+    blazinit_cmd(&temp_dir)
+        .args(["add", "-i"])
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains("needs an interactive terminal"));
+    // The profile goes with -i, not in the package list.
+    blazinit_cmd(&temp_dir)
+        .args(["add", "git", "work", "-i"])
+        .assert()
+        .code(2);
+    blazinit_cmd(&temp_dir)
+        .args(["install", "work", "--select"])
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains("needs an interactive terminal"));
+}
+
+#[test]
+fn test_install_only_except_and_tags() {
+    let temp_dir = setup_test_env();
+    blazinit_cmd(&temp_dir)
+        .args(["create", "work"])
+        .assert()
+        .success();
+    blazinit_cmd(&temp_dir)
+        .args(["add", "git,curl", "work", "--tag", "cli"])
+        .assert()
+        .success();
+    blazinit_cmd(&temp_dir)
+        .args(["add", "unzip", "work"])
+        .assert()
+        .success();
+    blazinit_cmd(&temp_dir)
+        .args(["show", "work"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("(tags: cli)"));
+
+    let installed = |args: &[&str]| -> Vec<String> {
+        let output = blazinit_cmd(&temp_dir)
+            .args(["install", "work", "--dry-run", "--force"])
+            .args(["--installer", "apt", "--format", "json"])
+            .args(args)
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        let report: serde_json::Value =
+            serde_json::from_slice(&output.stdout).unwrap();
+        report["packages"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|p| p["name"].as_str().unwrap().to_string())
+            .collect()
+    };
+    assert_eq!(installed(&["--tag", "cli"]), vec!["curl", "git"]);
+    assert_eq!(installed(&["--only", "unzip,git"]), vec!["git", "unzip"]);
+    assert_eq!(installed(&["--except", "curl"]), vec!["git", "unzip"]);
+    blazinit_cmd(&temp_dir)
+        .args(["install", "work", "--only", "jq"])
+        .assert()
+        .code(3);
+    blazinit_cmd(&temp_dir)
+        .args(["install", "work", "--tag", "cil"])
+        .assert()
+        .code(3)
+        .stderr(predicate::str::contains("cli"));
+
+    blazinit_cmd(&temp_dir)
+        .args(["add", "unzip", "work", "--tag", "cli", "--ignore-existing"])
+        .assert()
+        .success();
+    assert_eq!(installed(&["--tag", "cli"]), vec!["curl", "git", "unzip"]);
+}
+
+#[test]
+fn test_install_several_profiles_together() {
+    let temp_dir = setup_test_env();
+    for (profile, packages) in [
+        ("base", "git,curl"),
+        ("work", "git,unzip"),
+        ("personal", "curl"),
+    ] {
+        blazinit_cmd(&temp_dir)
+            .args(["create", profile])
+            .assert()
+            .success();
+        blazinit_cmd(&temp_dir)
+            .args(["add", packages, profile])
+            .assert()
+            .success();
+    }
+
+    let output = blazinit_cmd(&temp_dir)
+        .args([
+            "install",
+            "base",
+            "work",
+            "personal",
+            "--dry-run",
+            "--force",
+        ])
+        .args(["--installer", "apt", "--format", "json"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let report: serde_json::Value =
+        serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report["profile"], "base+work+personal");
+    let names: Vec<&str> = report["packages"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|p| p["name"].as_str().unwrap())
+        .collect();
+    assert_eq!(names, vec!["curl", "git", "unzip"]);
+
+    // An unpinned package takes the other profile's pin; two pins conflict.
+    for (profile, installer) in [("base", "apt"), ("personal", "brew")] {
+        blazinit_cmd(&temp_dir)
+            .args(["remove", "curl", profile])
+            .assert()
+            .success();
+        blazinit_cmd(&temp_dir)
+            .args(["add", "curl", profile, "--installer", installer])
+            .assert()
+            .success();
+    }
+    blazinit_cmd(&temp_dir)
+        .args(["install", "base", "personal", "--dry-run"])
+        .assert()
+        .code(4)
+        .stderr(predicate::str::contains(
+            "Package 'curl' is pinned differently in 'base' (installer: apt) and 'personal' (installer: brew)",
+        ));
+}
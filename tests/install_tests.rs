@@ -0,0 +1,257 @@
+use std::{env, fs};
+
+use blazinit::{
+    installer::{
+        InstallOutcome, InstallReport, detect_available_installer, run_install,
+    },
+    profile::{PackageRef, Profile},
+    reporter::SilentReporter,
+    runner::{CommandRunner, FakeRunner, RecordingRunner},
+};
+use serial_test::serial;
+use tempfile::TempDir;
+
+// Packages used by every test:
+//   base    detect `base-detect`, apt/brew `base`
+//   tool    depends on base, apt `tool-apt`, brew `tool-brew`
+//   app     depends on tool, custom `install-app`
+//   lonely  custom `install-lonely` only
+const REGISTRY: &[(&str, &str)] = &[
+    (
+        "base",
+        "detect = \"base-detect\"\n\n[packages]\napt = \"base\"\nbrew = \"base\"\n",
+    ),
+    (
+        "tool",
+        "detect = \"tool-detect\"\ndependencies = [\"base\"]\n\n[packages]\napt = \"tool-apt\"\nbrew = \"tool-brew\"\n",
+    ),
+    (
+        "app",
+        "detect = \"app-detect\"\ndependencies = [\"tool\"]\n\n[packages]\ncustom = \"install-app\"\n",
+    ),
+    (
+        "lonely",
+        "detect = \"lonely-detect\"\n\n[packages]\ncustom = \"install-lonely\"\n",
+    ),
+];
+
+fn setup_test_env() -> TempDir {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    unsafe {
+        env::set_var("HOME", temp_dir.path());
+        env::set_var("XDG_CONFIG_HOME", temp_dir.path().join(".config"));
+    }
+    let reg_dir = blazinit::registry::registry_dir();
+    fs::create_dir_all(&reg_dir).unwrap();
+    fs::write(reg_dir.join("metadata.toml"), "version = \"1\"\n").unwrap();
+    for (name, content) in REGISTRY {
+        fs::write(reg_dir.join(format!("{}.toml", name)), content).unwrap();
+    }
+    temp_dir
+}
+
+fn profile(names: &[&str]) -> Profile {
+    Profile {
+        name: "fake".to_string(),
+        origin: None,
+        packages: names
+            .iter()
+            .map(|name| PackageRef {
+                name: name.to_string(),
+                ..Default::default()
+            })
+            .collect(),
+    }
+}
+
+fn install(
+    profile: &Profile,
+    force: bool,
+    runner: &dyn CommandRunner,
+) -> InstallReport {
+    run_install(
+        profile,
+        force,
+        &None,
+        detect_available_installer(runner).as_deref(),
+        None,
+        runner,
+        &SilentReporter,
+    )
+    .unwrap()
+}
+
+fn outcomes(report: &InstallReport) -> Vec<(&str, InstallOutcome)> {
+    report
+        .packages
+        .iter()
+        .map(|p| (p.name.as_str(), p.outcome))
+        .collect()
+}
+
+#[test]
+#[serial]
+fn test_dependencies_install_first() {
+    let _temp = setup_test_env();
+    let runner = FakeRunner::new()
+        .with_program("apt")
+        .respond("sudo apt install -y base", 0, "")
+        .respond("sudo apt install -y tool-apt", 0, "")
+        .respond("install-app", 0, "");
+
+    let report = install(&profile(&["app"]), false, &runner);
+
+    assert_eq!(
+        outcomes(&report),
+        vec![
+            ("base", InstallOutcome::Installed),
+            ("tool", InstallOutcome::Installed),
+            ("app", InstallOutcome::Installed),
+        ]
+    );
+    assert_eq!(
+        runner.calls(),
+        vec![
+            "base-detect",
+            "sudo apt install -y base",
+            "tool-detect",
+            "sudo apt install -y tool-apt",
+            "app-detect",
+            "install-app",
+        ]
+    );
+}
+
+#[test]
+#[serial]
+fn test_installed_packages_are_skipped() {
+    let _temp = setup_test_env();
+    let runner = FakeRunner::new().respond("lonely-detect", 0, "lonely 1.0.0");
+
+    let report = install(&profile(&["lonely"]), false, &runner);
+
+    assert_eq!(outcomes(&report), vec![("lonely", InstallOutcome::Skipped)]);
+    assert_eq!((report.installed, report.skipped), (0, 1));
+    assert_eq!(runner.calls(), vec!["lonely-detect"]);
+}
+
+#[test]
+#[serial]
+fn test_force_reinstalls_without_detecting() {
+    let _temp = setup_test_env();
+    let runner = FakeRunner::new()
+        .respond("lonely-detect", 0, "lonely 1.0.0")
+        .respond("install-lonely", 0, "");
+
+    let report = install(&profile(&["lonely"]), true, &runner);
+
+    assert_eq!(
+        outcomes(&report),
+        vec![("lonely", InstallOutcome::Installed)]
+    );
+    assert_eq!(runner.calls(), vec!["install-lonely"]);
+}
+
+#[test]
+#[serial]
+fn test_failures_are_counted_and_do_not_stop_the_run() {
+    let _temp = setup_test_env();
+    let runner = FakeRunner::new()
+        .with_program("brew")
+        .respond("brew install base", 1, "")
+        .respond("install-lonely", 0, "");
+
+    let report = install(&profile(&["base", "lonely"]), false, &runner);
+
+    assert_eq!(
+        outcomes(&report),
+        vec![
+            ("base", InstallOutcome::Failed),
+            ("lonely", InstallOutcome::Installed),
+        ]
+    );
+    assert_eq!((report.installed, report.failed), (1, 1));
+    assert_eq!(
+        report.packages[0].error.as_deref(),
+        Some("Installing 'base' failed: exit status: 1")
+    );
+}
+
+#[test]
+#[serial]
+fn test_installer_selection_follows_available_programs() {
+    let _temp = setup_test_env();
+
+    // apt comes first in the priority list when both are present.
+    let runner = FakeRunner::new().with_program("brew").with_program("apt");
+    let report = install(&profile(&["tool"]), true, &runner);
+    assert_eq!(report.packages[1].installer.as_deref(), Some("apt"));
+    assert_eq!(
+        report.packages[1].command.as_deref(),
+        Some("sudo apt install -y tool-apt")
+    );
+
+    let runner = FakeRunner::new().with_program("brew");
+    let report = install(&profile(&["tool"]), true, &runner);
+    assert_eq!(report.packages[1].installer.as_deref(), Some("brew"));
+    assert_eq!(
+        report.packages[1].command.as_deref(),
+        Some("brew install tool-brew")
+    );
+
+    // Without a usable installer the package fails before anything runs.
+    let runner = FakeRunner::new().with_program("pacman");
+    let report = install(&profile(&["base"]), true, &runner);
+    assert_eq!(outcomes(&report), vec![("base", InstallOutcome::Failed)]);
+    assert!(runner.calls().is_empty());
+}
+
+#[test]
+#[serial]
+fn test_cli_installer_overrides_detection() {
+    let _temp = setup_test_env();
+    let runner = FakeRunner::new().with_program("apt").respond(
+        "brew install base",
+        0,
+        "",
+    );
+
+    let report = run_install(
+        &profile(&["base"]),
+        true,
+        &Some("brew".to_string()),
+        Some("apt"),
+        None,
+        &runner,
+        &SilentReporter,
+    )
+    .unwrap();
+
+    assert_eq!(report.packages[0].installer.as_deref(), Some("brew"));
+    assert_eq!(runner.calls(), vec!["brew install base"]);
+}
+
+#[test]
+#[serial]
+fn test_recording_runner_probes_but_never_installs() {
+    let _temp = setup_test_env();
+    let fake = FakeRunner::new().with_program("apt").respond(
+        "base-detect",
+        0,
+        "base 2.0.0",
+    );
+    let runner = RecordingRunner::new(&fake);
+
+    let report = install(&profile(&["tool"]), false, &runner);
+
+    assert_eq!(
+        outcomes(&report),
+        vec![
+            ("base", InstallOutcome::Skipped),
+            ("tool", InstallOutcome::WouldInstall),
+        ]
+    );
+    assert!(report.dry_run);
+    assert_eq!(runner.recorded(), vec!["sudo apt install -y tool-apt"]);
+    assert_eq!(fake.calls(), vec!["base-detect", "tool-detect"]);
+}